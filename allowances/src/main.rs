@@ -0,0 +1,203 @@
+use alloy::eips::BlockId;
+use alloy::primitives::utils::format_units;
+use alloy::primitives::Address;
+use alloy::providers::{Provider, ProviderBuilder};
+use clap::Parser;
+use payment_common::allowances;
+use payment_common::config::SignerBackend;
+use payment_common::{payments, rpc, Config, Error, Wallet};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// List the ERC-20 allowances granted by the payment wallet
+///
+/// Finds the spenders the wallet approved from `Approval` logs of every token
+/// registered on the active network, and reports the non-zero
+/// `allowance(owner, spender)` values as JSON. Does NOT require the wallet password.
+#[derive(Parser, Debug)]
+#[command(name = "allowances")]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Only check this token (registry symbol, e.g. USDC, or contract address)
+    #[arg(long)]
+    token: Option<String>,
+
+    /// Also check this spender, even if no Approval log names it (repeatable)
+    #[arg(long)]
+    spender: Vec<Address>,
+
+    /// First block to scan for Approval logs (default: 1,000,000 blocks before the
+    /// latest; 0 scans from genesis)
+    #[arg(long)]
+    from_block: Option<u64>,
+
+    /// Path to the wallet keystore file
+    #[arg(long, short = 'w')]
+    wallet: Option<PathBuf>,
+
+    /// Path to configuration file
+    #[arg(long, short = 'c')]
+    config: Option<PathBuf>,
+
+    /// Named environment from config.toml (default: $PAYMENT_ENV, then active_env)
+    #[arg(long)]
+    env: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct AllowancesOutput {
+    owner: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    network: Option<String>,
+    from_block: u64,
+    to_block: u64,
+    allowances: Vec<AllowanceInfo>,
+}
+
+/// One non-zero allowance
+#[derive(Debug, Serialize)]
+struct AllowanceInfo {
+    token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_symbol: Option<String>,
+    spender: String,
+    allowance: String,
+    raw: String,
+    /// At least 2^255: the spender can move the whole balance
+    unlimited: bool,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+
+    match run(args).await {
+        Ok(output) => match serde_json::to_string_pretty(&output) {
+            Ok(json) => {
+                println!("{}", json);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("Error serializing output: {}", e);
+                ExitCode::from(1)
+            }
+        },
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code() as u8)
+        }
+    }
+}
+
+async fn run(args: Args) -> payment_common::Result<AllowancesOutput> {
+    let config = Config::load_env(args.config.as_deref(), args.env.as_deref())?;
+    for warning in config.warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+    let owner = owner_address(&config, args.wallet)?;
+
+    let provider = ProviderBuilder::new()
+        .connect_client(rpc::client_from_config(&config, |failover| {
+            eprintln!("Warning: {}", failover)
+        })?);
+    let tokens = match &args.token {
+        Some(token) => vec![payments::resolve_token(&config, token, &provider).await?],
+        None => {
+            let mut warnings = Vec::new();
+            let tokens = allowances::tokens(&config, None, &mut warnings);
+            for warning in warnings {
+                eprintln!("Warning: {}", warning);
+            }
+            tokens
+        }
+    };
+    if tokens.is_empty() {
+        return Err(Error::MissingConfig(
+            "No tokens registered on the active network. Use --token <ADDRESS>".to_string(),
+        ));
+    }
+
+    let to_block = provider
+        .get_block_number()
+        .await
+        .map_err(|e| Error::Network(format!("Failed to get block number: {}", e)))?;
+    let from_block = match args.from_block {
+        Some(from_block) if from_block > to_block => {
+            return Err(Error::InvalidArgument(format!(
+                "--from-block {} is after the latest block {}",
+                from_block, to_block
+            )));
+        }
+        Some(from_block) => from_block,
+        None => to_block.saturating_sub(allowances::DEFAULT_LOOKBACK_BLOCKS),
+    };
+    if args.from_block.is_none() && from_block > 0 {
+        eprintln!("Older approvals are not scanned; use --from-block 0 to start at genesis");
+    }
+
+    eprintln!(
+        "Scanning Approval logs of {} token(s) from block {} to {}...",
+        tokens.len(),
+        from_block,
+        to_block
+    );
+    let addresses: Vec<Address> = tokens.iter().map(|t| t.address).collect();
+    let mut pairs =
+        allowances::approved_spenders(&provider, owner, &addresses, from_block, to_block).await?;
+    for spender in &args.spender {
+        pairs.extend(addresses.iter().map(|token| (*token, *spender)));
+    }
+
+    // Approvals may since have been spent or revoked: report what is left now
+    let mut found = Vec::new();
+    for (token_address, spender) in pairs {
+        let raw = allowances::allowance(
+            &provider,
+            token_address,
+            owner,
+            spender,
+            BlockId::number(to_block),
+        )
+        .await?;
+        if raw.is_zero() {
+            continue;
+        }
+        let token = tokens.iter().find(|t| t.address == token_address);
+        let decimals = token.map(|t| t.decimals).unwrap_or(18);
+        found.push(AllowanceInfo {
+            token: token_address.to_string(),
+            token_symbol: token.and_then(|t| t.symbol.clone()),
+            spender: spender.to_string(),
+            allowance: format_units(raw, decimals).unwrap_or_else(|_| raw.to_string()),
+            raw: raw.to_string(),
+            unlimited: allowances::is_unlimited(raw),
+        });
+    }
+
+    Ok(AllowancesOutput {
+        owner: owner.to_string(),
+        network: config.network.name.clone(),
+        from_block,
+        to_block,
+        allowances: found,
+    })
+}
+
+/// Address of the remote signer or the wallet keystore
+fn owner_address(config: &Config, wallet: Option<PathBuf>) -> payment_common::Result<Address> {
+    let address = match config.signer.backend {
+        SignerBackend::Remote => config.signer.address.clone().ok_or_else(|| {
+            Error::MissingConfig(
+                "signer.address must be set when signer.backend is remote".to_string(),
+            )
+        })?,
+        SignerBackend::Local => {
+            let wallet_path = wallet.unwrap_or_else(|| config.wallet_path());
+            Wallet::get_address(Some(&wallet_path))?
+        }
+    };
+    address
+        .parse()
+        .map_err(|_| Error::Wallet(format!("Invalid wallet address: {}", address)))
+}
@@ -1,4 +1,5 @@
 use clap::Parser;
+use payment_common::secret_store::{machine_passphrase, SealedSecret};
 use payment_common::{default_config_path, Config, Wallet};
 use std::fs;
 use std::path::PathBuf;
@@ -26,6 +27,10 @@ struct Args {
     #[arg(long, short = 'c')]
     config: Option<PathBuf>,
 
+    /// Named environment from config.toml (default: $PAYMENT_ENV, then active_env)
+    #[arg(long)]
+    env: Option<String>,
+
     /// Force overwrite if wallet already exists
     #[arg(long, short = 'f')]
     force: bool,
@@ -58,27 +63,14 @@ fn run(args: Args) -> payment_common::Result<()> {
     }
 
     // Load config
-    let config = Config::load_from(args.config.as_deref())?;
+    let config = Config::load_env(args.config.as_deref(), args.env.as_deref())?;
+    for warning in config.warnings() {
+        eprintln!("Warning: {}", warning);
+    }
 
     // Determine the wallet output path (CLI arg > config > default)
     let wallet_path = args.output.unwrap_or_else(|| config.wallet_path());
 
-    // Check if wallet already exists
-    if wallet_path.exists() {
-        if args.force {
-            fs::remove_file(&wallet_path)?;
-            eprintln!("Removed existing wallet at {}", wallet_path.display());
-        } else {
-            eprintln!(
-                "Error: Wallet already exists at {}\nUse --force to overwrite.",
-                wallet_path.display()
-            );
-            return Err(payment_common::Error::WalletExists(
-                wallet_path.display().to_string(),
-            ));
-        }
-    }
-
     // Read password from file or use provided password
     let password_from_file: Option<String> = if let Some(ref pw_file) = args.password_file {
         Some(fs::read_to_string(pw_file)?.trim().to_string())
@@ -86,7 +78,23 @@ fn run(args: Args) -> payment_common::Result<()> {
         None
     };
 
-    let password_str = args.password.as_deref().or(password_from_file.as_deref());
+    let mut password_str = args.password.as_deref().or(password_from_file.as_deref());
+
+    // With a secret store configured, generate the password here and seal it
+    // instead of writing it to the plaintext password file
+    let generated_password;
+    let secret_store = match config.secret_store_path() {
+        Some(store_path) if password_str.is_none() => {
+            let passphrase = machine_passphrase()?;
+            generated_password = Wallet::generate_password();
+            password_str = Some(&generated_password);
+            Some((
+                store_path,
+                SealedSecret::seal(&generated_password, &passphrase)?,
+            ))
+        }
+        _ => None,
+    };
 
     // Determine where to save the password file (only used if password is auto-generated)
     // Use config's password_file path
@@ -96,6 +104,22 @@ fn run(args: Args) -> payment_common::Result<()> {
         None
     };
 
+    // Check if wallet already exists
+    if wallet_path.exists() {
+        if args.force {
+            fs::remove_file(&wallet_path)?;
+            eprintln!("Removed existing wallet at {}", wallet_path.display());
+        } else {
+            eprintln!(
+                "Error: Wallet already exists at {}\nUse --force to overwrite.",
+                wallet_path.display()
+            );
+            return Err(payment_common::Error::WalletExists(
+                wallet_path.display().to_string(),
+            ));
+        }
+    }
+
     // Create the wallet
     let info = Wallet::create(
         password_str,
@@ -103,6 +127,10 @@ fn run(args: Args) -> payment_common::Result<()> {
         password_save_path.as_deref(),
     )?;
 
+    if let Some((store_path, sealed)) = &secret_store {
+        sealed.save(store_path)?;
+    }
+
     // Output the address to stdout (this is the only stdout output)
     println!("{}", info.address);
 
@@ -114,6 +142,9 @@ fn run(args: Args) -> payment_common::Result<()> {
         eprintln!("Password saved to: {}", pw_path.display());
         eprintln!("\nIMPORTANT: Keep your password file secure!");
     }
+    if let Some((store_path, _)) = &secret_store {
+        eprintln!("Password sealed in secret store: {}", store_path.display());
+    }
 
     eprintln!("\nFund this address to enable payments.");
 
@@ -0,0 +1,401 @@
+use alloy::eips::BlockId;
+use alloy::primitives::{address, Address, U256};
+use alloy::providers::Provider;
+use alloy::providers::ProviderBuilder;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use payment_common::config::ResolvedToken;
+use payment_common::payments::NATIVE_DECIMALS;
+use payment_common::pricing::{self, Price};
+use payment_common::{rpc, Config};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tokio::task::JoinSet;
+
+/// Multicall3, deployed at the same address on every major EVM chain
+pub const MULTICALL3: Address = address!("cA11bde05977b3631167028862bE2a173976CA11");
+
+sol! {
+    #[sol(rpc)]
+    contract IMulticall3 {
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        struct Result {
+            bool success;
+            bytes returnData;
+        }
+
+        function aggregate3(Call3[] calldata calls) external payable returns (Result[] memory returnData);
+        function getEthBalance(address addr) external view returns (uint256 balance);
+    }
+}
+
+sol! {
+    #[sol(rpc)]
+    contract IERC20 {
+        function balanceOf(address account) external view returns (uint256);
+    }
+}
+
+/// An asset whose balance is reported: the native gas token or an ERC-20
+#[derive(Debug, Clone, PartialEq)]
+pub struct Asset {
+    pub symbol: Option<String>,
+    /// Token contract (`None` for the native gas token)
+    pub token: Option<Address>,
+    pub decimals: u8,
+}
+
+impl Asset {
+    /// The gas token, with the symbol of the network's profile
+    pub fn native(config: &Config) -> Self {
+        Self {
+            symbol: Some(config.native_symbol()),
+            token: None,
+            decimals: NATIVE_DECIMALS,
+        }
+    }
+}
+
+impl From<&ResolvedToken> for Asset {
+    fn from(token: &ResolvedToken) -> Self {
+        Self {
+            symbol: token.symbol.clone(),
+            token: Some(token.address),
+            decimals: token.decimals,
+        }
+    }
+}
+
+/// One entry of the `balances` array
+#[derive(Debug, Serialize)]
+pub struct Balance {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    pub balance: String,
+    pub raw: String,
+    pub decimals: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usd_value: Option<String>,
+}
+
+/// Balances on one network of an --all-networks sweep
+#[derive(Debug, Serialize)]
+pub struct NetworkBalances {
+    pub chain_id: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub balances: Vec<Balance>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_usd_value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Balances of `owner` on every network profile, queried concurrently.
+///
+/// A network that fails (unreachable RPC, wrong chain) reports its error and does not
+/// affect the others.
+pub async fn sweep(
+    config: &Config,
+    owner: Address,
+    hide_zero: bool,
+) -> BTreeMap<String, NetworkBalances> {
+    let mut tasks = JoinSet::new();
+    for profile in config.network_profiles() {
+        let network_config = config.for_network(&profile.name);
+        tasks.spawn(async move {
+            let chain_id = profile.profile.chain_id;
+            let result = match network_config {
+                Ok(network_config) => network_balances(&network_config, owner, hide_zero).await,
+                Err(e) => Err(e.to_string()),
+            };
+            let network = match result {
+                Ok(balances) => NetworkBalances {
+                    chain_id,
+                    total_usd_value: total_usd_value(&balances),
+                    balances,
+                    error: None,
+                },
+                Err(error) => NetworkBalances {
+                    chain_id,
+                    balances: Vec::new(),
+                    total_usd_value: None,
+                    error: Some(error),
+                },
+            };
+            (profile.name, network)
+        });
+    }
+
+    let mut networks = BTreeMap::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((name, network)) => {
+                networks.insert(name, network);
+            }
+            Err(e) => eprintln!("Warning: Balance query task failed: {}", e),
+        }
+    }
+    networks
+}
+
+async fn network_balances(
+    config: &Config,
+    owner: Address,
+    hide_zero: bool,
+) -> Result<Vec<Balance>, String> {
+    let expected = config.network.chain_id;
+    let provider = ProviderBuilder::new().connect_client(
+        rpc::client_from_config(config, crate::warn_failover).map_err(|e| e.to_string())?,
+    );
+    let assets = assets(config, None);
+
+    let (chain_id, raw) = tokio::join!(
+        provider.get_chain_id(),
+        fetch(&provider, owner, &assets, BlockId::latest())
+    );
+    let chain_id = chain_id.map_err(|e| e.to_string())?;
+    if let Some(expected) = expected.filter(|expected| *expected != chain_id) {
+        return Err(format!(
+            "Chain ID mismatch: expected {}, got {}",
+            expected, chain_id
+        ));
+    }
+    let raw = raw.map_err(|e| e.to_string())?;
+    let prices = prices(
+        &provider,
+        config,
+        &assets,
+        BlockId::latest(),
+        pricing::now(),
+    )
+    .await;
+    Ok(to_balances(assets, raw, hide_zero, &prices))
+}
+
+/// USD prices of the assets that have a price feed on the network, keyed by symbol.
+///
+/// Prices are read at `block`; `reference_time` is that block's time, for the staleness check.
+pub async fn prices<P: Provider>(
+    provider: &P,
+    config: &Config,
+    assets: &[Asset],
+    block: BlockId,
+    reference_time: u64,
+) -> BTreeMap<String, Price> {
+    let mut prices = BTreeMap::new();
+    for symbol in assets.iter().filter_map(|a| a.symbol.as_deref()) {
+        let feed = match pricing::feed_address(config, symbol) {
+            Ok(Some(feed)) => feed,
+            Ok(None) => continue,
+            Err(e) => {
+                eprintln!("Warning: {}", e);
+                continue;
+            }
+        };
+        let max_age = pricing::max_price_age(config, symbol);
+        match pricing::fetch_price(provider, symbol, feed, block, reference_time, max_age).await {
+            Ok(price) => {
+                prices.insert(symbol.to_string(), price);
+            }
+            Err(e) => eprintln!("Warning: No USD value for {}: {}", symbol, e),
+        }
+    }
+    prices
+}
+
+/// Sum of the balances' USD values, if any has one
+pub fn total_usd_value(balances: &[Balance]) -> Option<String> {
+    let values: Vec<&str> = balances
+        .iter()
+        .filter_map(|b| b.usd_value.as_deref())
+        .collect();
+    (!values.is_empty()).then(|| pricing::sum_usd(values))
+}
+
+/// Pair assets with their fetched balances and USD values, warning about the ones that failed
+pub fn to_balances(
+    assets: Vec<Asset>,
+    raw: Vec<Option<U256>>,
+    hide_zero: bool,
+    prices: &BTreeMap<String, Price>,
+) -> Vec<Balance> {
+    let mut balances = Vec::new();
+    for (asset, raw) in assets.into_iter().zip(raw) {
+        let Some(raw) = raw else {
+            eprintln!(
+                "Warning: Could not fetch {} balance",
+                asset.symbol.as_deref().unwrap_or("token")
+            );
+            continue;
+        };
+        if hide_zero && raw.is_zero() {
+            continue;
+        }
+        balances.push(Balance {
+            token: asset.token.map(|t| t.to_string()),
+            balance: crate::raw_to_human(&raw.to_string(), asset.decimals),
+            raw: raw.to_string(),
+            decimals: asset.decimals,
+            usd_value: asset
+                .symbol
+                .as_ref()
+                .and_then(|s| prices.get(s))
+                .map(|price| price.usd_value(raw, asset.decimals)),
+            symbol: asset.symbol,
+        });
+    }
+    balances
+}
+
+/// The native gas token, every token registered on the active network, and `extra`
+/// (e.g. a --token address that is not registered)
+pub fn assets(config: &Config, extra: Option<&ResolvedToken>) -> Vec<Asset> {
+    let mut assets = vec![Asset::native(config)];
+    if let Some(network) = &config.network.name {
+        for (symbol, entry) in config.token_registry(network) {
+            match entry.address.parse() {
+                Ok(address) => assets.push(Asset {
+                    symbol: Some(symbol),
+                    token: Some(address),
+                    decimals: entry.decimals,
+                }),
+                Err(_) => eprintln!(
+                    "Warning: Skipping {} (invalid address {})",
+                    symbol, entry.address
+                ),
+            }
+        }
+    }
+    for token in extra.into_iter().chain(config.default_token().as_ref()) {
+        if !assets.iter().any(|a| a.token == Some(token.address)) {
+            assets.push(token.into());
+        }
+    }
+    assets
+}
+
+/// Balance of `owner` for each asset at `block`, in one Multicall3 round trip.
+///
+/// Chains without Multicall3 (e.g. a fresh local devnet, or blocks from before it was
+/// deployed) fall back to one call per asset. An entry is `None` when that asset's
+/// query failed.
+pub async fn fetch<P: Provider>(
+    provider: &P,
+    owner: Address,
+    assets: &[Asset],
+    block: BlockId,
+) -> Result<Vec<Option<U256>>, alloy::contract::Error> {
+    match aggregate(provider, owner, assets, block).await {
+        Ok(balances) => Ok(balances),
+        // The RPC itself is unreachable: one call per asset would fail the same way
+        Err(alloy::contract::Error::TransportError(e)) if e.as_error_resp().is_none() => {
+            Err(e.into())
+        }
+        Err(e) => {
+            eprintln!(
+                "Warning: Multicall3 unavailable ({}), querying balances one by one",
+                e
+            );
+            let mut balances = Vec::with_capacity(assets.len());
+            for asset in assets {
+                balances.push(fetch_one(provider, owner, asset, block).await);
+            }
+            Ok(balances)
+        }
+    }
+}
+
+async fn aggregate<P: Provider>(
+    provider: &P,
+    owner: Address,
+    assets: &[Asset],
+    block: BlockId,
+) -> Result<Vec<Option<U256>>, alloy::contract::Error> {
+    let calls = assets
+        .iter()
+        .map(|asset| match asset.token {
+            Some(token) => IMulticall3::Call3 {
+                target: token,
+                allowFailure: true,
+                callData: IERC20::balanceOfCall::new((owner,)).abi_encode().into(),
+            },
+            None => IMulticall3::Call3 {
+                target: MULTICALL3,
+                allowFailure: true,
+                callData: IMulticall3::getEthBalanceCall::new((owner,))
+                    .abi_encode()
+                    .into(),
+            },
+        })
+        .collect();
+
+    let results = IMulticall3::new(MULTICALL3, provider)
+        .aggregate3(calls)
+        .block(block)
+        .call()
+        .await?;
+    Ok(results
+        .into_iter()
+        .map(|result| {
+            // Both calls return a single uint256
+            result
+                .success
+                .then(|| IERC20::balanceOfCall::abi_decode_returns(&result.returnData).ok())
+                .flatten()
+        })
+        .collect())
+}
+
+async fn fetch_one<P: Provider>(
+    provider: &P,
+    owner: Address,
+    asset: &Asset,
+    block: BlockId,
+) -> Option<U256> {
+    match asset.token {
+        Some(token) => IERC20::new(token, provider)
+            .balanceOf(owner)
+            .block(block)
+            .call()
+            .await
+            .ok(),
+        None => provider.get_balance(owner).block_id(block).await.ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assets_native_first_and_deduplicated() {
+        let mut config = Config::default();
+        config.apply_network_profile("base-sepolia").unwrap();
+
+        let assets = assets(&config, config.default_token().as_ref());
+        assert_eq!(assets[0], Asset::native(&config));
+        assert_eq!(assets[0].symbol.as_deref(), Some("ETH"));
+        let usdc: Vec<_> = assets
+            .iter()
+            .filter(|a| a.symbol.as_deref() == Some("USDC"))
+            .collect();
+        assert_eq!(usdc.len(), 1);
+
+        // An unregistered --token address is appended
+        let extra = ResolvedToken {
+            address: address!("000000000000000000000000000000000000dEaD"),
+            symbol: None,
+            decimals: 18,
+        };
+        let with_extra = super::assets(&config, Some(&extra));
+        assert_eq!(with_extra.len(), assets.len() + 1);
+        assert_eq!(with_extra.last().unwrap().token, Some(extra.address));
+    }
+}
@@ -0,0 +1,133 @@
+use alloy::eips::BlockNumberOrTag;
+use alloy::providers::Provider;
+use chrono::{DateTime, SecondsFormat};
+use serde::Serialize;
+
+/// The block balances were read at
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockInfo {
+    pub number: u64,
+    pub hash: String,
+    pub timestamp: u64,
+    /// `timestamp` as RFC 3339 (UTC)
+    pub time: String,
+}
+
+/// Parse a --block value: a number (decimal or 0x hex) or latest, safe, finalized, earliest
+pub fn parse_block(value: &str) -> Result<BlockNumberOrTag, String> {
+    if let Ok(number) = value.parse::<u64>() {
+        return Ok(BlockNumberOrTag::Number(number));
+    }
+    match value.parse::<BlockNumberOrTag>() {
+        Ok(BlockNumberOrTag::Pending) => {
+            Err("pending has no settled balances; use latest instead".to_string())
+        }
+        Ok(block) => Ok(block),
+        Err(_) => Err(format!(
+            "expected a block number or latest, safe, finalized, earliest (got {})",
+            value
+        )),
+    }
+}
+
+/// Parse an --at value (RFC 3339, e.g. 2025-01-31T23:59:59Z) into a Unix timestamp
+pub fn parse_timestamp(value: &str) -> Result<u64, String> {
+    let time = DateTime::parse_from_rfc3339(value).map_err(|e| {
+        format!(
+            "expected an RFC 3339 timestamp like 2025-01-31T23:59:59Z ({})",
+            e
+        )
+    })?;
+    u64::try_from(time.timestamp()).map_err(|_| format!("{} is before 1970", value))
+}
+
+/// Look up a block by number or tag
+pub async fn by_number<P: Provider>(
+    provider: &P,
+    block: BlockNumberOrTag,
+) -> Result<BlockInfo, Box<dyn std::error::Error>> {
+    let block = provider
+        .get_block_by_number(block)
+        .await?
+        .ok_or_else(|| format!("Block {} not found", block))?;
+    let header = &block.header;
+    Ok(BlockInfo {
+        number: header.number,
+        hash: header.hash.to_string(),
+        timestamp: header.timestamp,
+        time: format_timestamp(header.timestamp),
+    })
+}
+
+/// The last block produced at or before `timestamp`, found by binary search over block numbers
+pub async fn at_timestamp<P: Provider>(
+    provider: &P,
+    timestamp: u64,
+) -> Result<BlockInfo, Box<dyn std::error::Error>> {
+    let latest = by_number(provider, BlockNumberOrTag::Latest).await?;
+    if latest.timestamp <= timestamp {
+        return Ok(latest);
+    }
+    let genesis = by_number(provider, BlockNumberOrTag::Earliest).await?;
+    if timestamp < genesis.timestamp {
+        return Err(format!(
+            "{} is before the chain's first block ({})",
+            format_timestamp(timestamp),
+            genesis.time
+        )
+        .into());
+    }
+
+    // Invariant: block `low` is at or before the timestamp, block `high` is after it
+    let (mut low, mut high) = (genesis, latest.number);
+    while high - low.number > 1 {
+        let mid = by_number(
+            provider,
+            BlockNumberOrTag::Number(low.number + (high - low.number) / 2),
+        )
+        .await?;
+        if mid.timestamp <= timestamp {
+            low = mid;
+        } else {
+            high = mid.number;
+        }
+    }
+    Ok(low)
+}
+
+fn format_timestamp(timestamp: u64) -> String {
+    i64::try_from(timestamp)
+        .ok()
+        .and_then(|t| DateTime::from_timestamp(t, 0))
+        .map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_block() {
+        assert_eq!(parse_block("123").unwrap(), BlockNumberOrTag::Number(123));
+        assert_eq!(parse_block("0x10").unwrap(), BlockNumberOrTag::Number(16));
+        assert_eq!(parse_block("safe").unwrap(), BlockNumberOrTag::Safe);
+        assert_eq!(
+            parse_block("finalized").unwrap(),
+            BlockNumberOrTag::Finalized
+        );
+        assert!(parse_block("pending").is_err());
+        assert!(parse_block("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("1970-01-01T00:01:00Z").unwrap(), 60);
+        assert_eq!(
+            parse_timestamp("2025-01-01T01:00:00+01:00").unwrap(),
+            1_735_689_600
+        );
+        assert!(parse_timestamp("2025-01-01").is_err());
+        assert_eq!(format_timestamp(1_735_689_600), "2025-01-01T00:00:00Z");
+    }
+}
@@ -1,24 +1,31 @@
-use alloy::primitives::Address;
+mod balances;
+mod block;
+mod qr;
+mod runway;
+
+use alloy::eips::{BlockId, BlockNumberOrTag};
+use alloy::primitives::{Address, U256};
 use alloy::providers::ProviderBuilder;
-use alloy::sol;
+use balances::{Asset, Balance, NetworkBalances};
+use block::BlockInfo;
 use clap::Parser;
-use payment_common::{Config, Wallet};
+use payment_common::config::{ResolvedToken, SignerBackend};
+use payment_common::names::NameResolver;
+use payment_common::pricing::{self, Price};
+use payment_common::wallet::WalletVerification;
+use payment_common::{payments, rpc, Config, Wallet};
+use qr::QrInfo;
+use runway::GasRunway;
 use serde::Serialize;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::process::ExitCode;
 
-// ERC-20 balanceOf function
-sol! {
-    #[sol(rpc)]
-    contract IERC20 {
-        function balanceOf(address account) external view returns (uint256);
-    }
-}
-
-/// Get the public Ethereum address and token balance from a payment wallet
+/// Get the public Ethereum address and balances from a payment wallet
 ///
-/// Reads the wallet keystore file and outputs JSON with the public address
-/// and current token balance. Does NOT require the wallet password.
+/// Reads the wallet keystore file and outputs JSON with the public address,
+/// the native gas token balance and the balance of every registered token.
+/// Does NOT require the wallet password.
 #[derive(Parser, Debug)]
 #[command(name = "get-address")]
 #[command(version, about, long_about = None)]
@@ -30,12 +37,62 @@ struct Args {
     /// Path to configuration file
     #[arg(long, short = 'c')]
     config: Option<PathBuf>,
+
+    /// Named environment from config.toml (default: $PAYMENT_ENV, then active_env)
+    #[arg(long)]
+    env: Option<String>,
+
+    /// Decrypt the wallet and verify the keystore before reporting the address
+    #[arg(long)]
+    verify: bool,
+
+    /// Report the balance of this token (registry symbol, e.g. DAI, or contract address)
+    /// instead of the default token
+    #[arg(long)]
+    token: Option<String>,
+
+    /// Leave zero balances out of the balances array
+    #[arg(long)]
+    hide_zero: bool,
+
+    /// Report balances on every network profile (built-in and custom) at once
+    #[arg(long, conflicts_with_all = ["token", "block", "at"])]
+    all_networks: bool,
+
+    /// Read balances at this block: a number or latest, safe, finalized, earliest
+    /// (historical blocks need an archive RPC endpoint)
+    #[arg(long, value_parser = block::parse_block)]
+    block: Option<BlockNumberOrTag>,
+
+    /// Read balances at the last block at or before this RFC 3339 time
+    /// (e.g. 2025-01-31T23:59:59Z)
+    #[arg(long, value_parser = block::parse_timestamp, conflicts_with = "block")]
+    at: Option<u64>,
+
+    /// Look up the wallet's primary ENS name or Basename (reverse record, checked forward)
+    #[arg(long)]
+    name: bool,
+
+    /// Show the address as a QR code in the terminal (on stderr)
+    #[arg(long)]
+    qr: bool,
+
+    /// Encode an EIP-681 request for this amount of the token (or native ETH if no
+    /// token is configured) instead of the bare address
+    #[arg(long)]
+    qr_amount: Option<String>,
+
+    /// Also write the QR code to this file (.svg or .png)
+    #[arg(long)]
+    qr_file: Option<PathBuf>,
 }
 
 #[derive(Serialize)]
 struct WalletInfo {
     address: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     balance: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     token: Option<String>,
@@ -43,6 +100,22 @@ struct WalletInfo {
     token_symbol: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     network: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block: Option<BlockInfo>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    balances: Vec<Balance>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_usd_value: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    networks: BTreeMap<String, NetworkBalances>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_runway: Option<GasRunway>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    qr: Option<QrInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verification: Option<WalletVerification>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
 }
 
 #[tokio::main]
@@ -53,7 +126,13 @@ async fn main() -> ExitCode {
         Ok(info) => match serde_json::to_string_pretty(&info) {
             Ok(json) => {
                 println!("{}", json);
-                ExitCode::SUCCESS
+                match &info.verification {
+                    Some(report) if !report.ok => {
+                        eprintln!("Error: Wallet verification failed");
+                        ExitCode::from(1)
+                    }
+                    _ => ExitCode::SUCCESS,
+                }
             }
             Err(e) => {
                 eprintln!("Error serializing wallet info: {}", e);
@@ -69,73 +148,265 @@ async fn main() -> ExitCode {
 
 async fn run(args: Args) -> Result<WalletInfo, Box<dyn std::error::Error>> {
     // Load config
-    let config = Config::load_from(args.config.as_deref()).unwrap_or_default();
+    // A broken config falls back to defaults, but never when an environment was asked for
+    let config = match Config::load_env(args.config.as_deref(), args.env.as_deref()) {
+        Ok(config) => config,
+        Err(e) if args.env.is_some() => return Err(e.into()),
+        Err(_) => Config::default(),
+    };
+    for warning in config.warnings() {
+        eprintln!("Warning: {}", warning);
+    }
 
     // Get wallet path
     let wallet_path = args.wallet.unwrap_or_else(|| config.wallet_path());
 
-    // Get address from wallet
-    let address = Wallet::get_address(Some(&wallet_path))?;
-
-    // Get decimals (default to 6 for USDC)
-    let decimals = config.payment.default_token_decimals.unwrap_or(6);
-
-    // Try to get balance if network is configured
-    let (balance, token, token_symbol, network) = if let (Some(rpc_url), Some(token_addr)) =
-        (&config.network.rpc_url, &config.payment.default_token)
-    {
-        match get_token_balance(&address, rpc_url, token_addr, decimals).await {
-            Ok(bal) => (
-                Some(bal),
-                Some(token_addr.clone()),
-                config.payment.default_token_symbol.clone(),
-                config.network.name.clone(),
-            ),
-            Err(e) => {
-                eprintln!("Warning: Could not fetch balance: {}", e);
-                (
-                    None,
-                    Some(token_addr.clone()),
-                    config.payment.default_token_symbol.clone(),
-                    config.network.name.clone(),
-                )
-            }
+    // Get address from the remote signer config or the wallet
+    let address = match config.signer.backend {
+        SignerBackend::Remote => config
+            .signer
+            .address
+            .clone()
+            .ok_or("signer.address must be set when signer.backend is remote")?,
+        SignerBackend::Local => Wallet::get_address(Some(&wallet_path))?,
+    };
+
+    // Run the same checks as verify-wallet when asked
+    let verification = if args.verify {
+        if config.signer.backend == SignerBackend::Remote {
+            return Err("--verify requires a local wallet (signer.backend is remote)".into());
         }
+        let password = config.wallet_password()?;
+        let password_path = config
+            .secret_store_path()
+            .unwrap_or_else(|| config.password_path());
+        Some(Wallet::verify(
+            Some(&wallet_path),
+            &password,
+            Some(&password_path),
+        )?)
     } else {
-        (None, None, None, None)
+        None
+    };
+
+    // Token to report (CLI symbol or address > config default)
+    let token = match &args.token {
+        Some(token) => {
+            let provider = ProviderBuilder::new()
+                .connect_client(rpc::client_from_config(&config, warn_failover)?);
+            Some(payments::resolve_token(&config, token, &provider).await?)
+        }
+        None => config.default_token(),
     };
 
-    Ok(WalletInfo {
+    // Native and token balances, if a network is configured
+    let mut info = WalletInfo {
         address,
-        balance,
-        token,
-        token_symbol,
-        network,
-    })
+        name: None,
+        balance: None,
+        token: None,
+        token_symbol: None,
+        network: None,
+        block: None,
+        balances: Vec::new(),
+        total_usd_value: None,
+        networks: BTreeMap::new(),
+        gas_runway: None,
+        qr: None,
+        verification,
+        warnings: Vec::new(),
+    };
+
+    // Primary name from the reverse record, verified by resolving it forward
+    if args.name {
+        let owner: Address = info.address.parse()?;
+        match NameResolver::new(&config)
+            .on_failover(warn_failover)
+            .primary_name(owner)
+            .await
+        {
+            Ok(Some(check)) => {
+                if let Some(warning) = check.warning {
+                    eprintln!("Warning: {}", warning);
+                    info.warnings.push(warning);
+                }
+                info.name = Some(check.name);
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Warning: Could not look up name: {}", e),
+        }
+    }
+
+    // QR code for funding the wallet: the address, or an EIP-681 payment request
+    if args.qr || args.qr_amount.is_some() || args.qr_file.is_some() {
+        let owner: Address = info.address.parse()?;
+        let content = match &args.qr_amount {
+            Some(amount) => {
+                qr::payment_uri(owner, config.network.chain_id, token.as_ref(), amount)?
+            }
+            None => owner.to_string(),
+        };
+        let code = qr::encode(&content)?;
+        if args.qr {
+            eprintln!("{}", qr::to_unicode(&code));
+            eprintln!("{}", content);
+        }
+        if let Some(path) = &args.qr_file {
+            qr::write_file(&code, path)?;
+            eprintln!("QR code written to {}", path.display());
+        }
+        info.qr = Some(QrInfo {
+            content,
+            file: args.qr_file.as_ref().map(|p| p.display().to_string()),
+        });
+    }
+    if args.all_networks {
+        let owner: Address = info.address.parse()?;
+        info.networks = balances::sweep(&config, owner, args.hide_zero).await;
+        return Ok(info);
+    }
+    // A historical query was asked for explicitly, so failing it is an error
+    let historical = args.block.is_some() || args.at.is_some();
+    if config.rpc_endpoints().is_empty() {
+        if historical {
+            return Err("--block and --at need a configured network (network.rpc_url)".into());
+        }
+        return Ok(info);
+    }
+    // Reported even when the balances cannot be fetched (balance is then null)
+    info.network = config.network.name.clone();
+    if let Some(token) = &token {
+        info.token = Some(token.address.to_string());
+        info.token_symbol = token.symbol.clone();
+    }
+    let assets = balances::assets(&config, token.as_ref());
+    let (block, raw, prices) =
+        match get_balances(&info.address, &config, &assets, args.block, args.at).await {
+            Ok(result) => result,
+            Err(e) if historical => return Err(e),
+            Err(e) => {
+                eprintln!("Warning: Could not fetch balances: {}", e);
+                return Ok(info);
+            }
+        };
+    info.block = block;
+    let token_balance = token
+        .as_ref()
+        .and_then(|token| assets.iter().position(|a| a.token == Some(token.address)))
+        .and_then(|i| raw[i]);
+    if let (Some(token), Some(raw)) = (&token, token_balance) {
+        info.balance = Some(raw_to_human(&raw.to_string(), token.decimals));
+    }
+    // Payments the gas balance covers, only meaningful for the current balance
+    let native_balance = raw.first().copied().flatten();
+    if let (false, Some(native_balance)) = (historical, native_balance) {
+        match estimate_runway(
+            &info.address,
+            &config,
+            token.as_ref(),
+            token_balance,
+            native_balance,
+        )
+        .await
+        {
+            Ok(runway) => {
+                if runway.payments < runway.min_payments {
+                    let warning = format!(
+                        "Gas balance covers only {} payments (payment.min_gas_runway is {}); \
+                         send {} to {} to keep paying",
+                        runway.payments,
+                        runway.min_payments,
+                        config.native_symbol(),
+                        info.address
+                    );
+                    eprintln!("Warning: {}", warning);
+                    info.warnings.push(warning);
+                }
+                info.gas_runway = Some(runway);
+            }
+            Err(e) => eprintln!("Warning: Could not estimate gas runway: {}", e),
+        }
+    }
+    info.balances = balances::to_balances(assets, raw, args.hide_zero, &prices);
+    info.total_usd_value = balances::total_usd_value(&info.balances);
+
+    Ok(info)
 }
 
-async fn get_token_balance(
+/// Gas runway of the default (or --token) token's transfers at current fees
+async fn estimate_runway(
     address: &str,
-    rpc_url: &str,
-    token_addr: &str,
-    decimals: u8,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let wallet_address: Address = address.parse()?;
-    let token_address: Address = token_addr.parse()?;
+    config: &Config,
+    token: Option<&ResolvedToken>,
+    token_balance: Option<U256>,
+    native_balance: U256,
+) -> Result<GasRunway, Box<dyn std::error::Error>> {
+    let owner: Address = address.parse()?;
+    let provider =
+        ProviderBuilder::new().connect_client(rpc::client_from_config(config, warn_failover)?);
+    let min_payments = config
+        .payment
+        .min_gas_runway
+        .unwrap_or(runway::DEFAULT_MIN_GAS_RUNWAY);
+    runway::gas_runway(
+        &provider,
+        owner,
+        token,
+        token_balance,
+        native_balance,
+        min_payments,
+    )
+    .await
+}
 
-    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+type Prices = BTreeMap<String, Price>;
 
-    let token_contract = IERC20::new(token_address, &provider);
-    let balance_raw = token_contract.balanceOf(wallet_address).call().await?;
+/// Balances and USD prices at the block chosen with --block or --at (with that
+/// block's details), or at the latest block
+async fn get_balances(
+    address: &str,
+    config: &Config,
+    assets: &[Asset],
+    block: Option<BlockNumberOrTag>,
+    at: Option<u64>,
+) -> Result<(Option<BlockInfo>, Vec<Option<U256>>, Prices), Box<dyn std::error::Error>> {
+    let wallet_address: Address = address.parse()?;
+    let provider =
+        ProviderBuilder::new().connect_client(rpc::client_from_config(config, warn_failover)?);
 
-    // Convert to human-readable units
-    let balance_str = balance_raw.to_string();
-    let human_balance = raw_to_human(&balance_str, decimals);
+    let block = match (block, at) {
+        (Some(number), _) => Some(block::by_number(&provider, number).await?),
+        (None, Some(timestamp)) => Some(block::at_timestamp(&provider, timestamp).await?),
+        (None, None) => None,
+    };
+    // Pin tags like latest to the resolved number so every asset is read at the same block
+    let block_id = block
+        .as_ref()
+        .map(|b| BlockId::number(b.number))
+        .unwrap_or_else(BlockId::latest);
+    if let Some(block) = &block {
+        eprintln!(
+            "Reading balances at block {} ({})",
+            block.number, block.time
+        );
+    }
 
-    Ok(human_balance)
+    let raw = balances::fetch(&provider, wallet_address, assets, block_id).await?;
+    // Historical prices are judged stale relative to the block, not to now
+    let reference_time = block
+        .as_ref()
+        .map(|b| b.timestamp)
+        .unwrap_or_else(pricing::now);
+    let prices = balances::prices(&provider, config, assets, block_id, reference_time).await;
+    Ok((block, raw, prices))
 }
 
 /// Convert raw blockchain units to human-readable units
+/// Report an RPC endpoint that failed over to the next one
+fn warn_failover(failover: &rpc::Failover) {
+    eprintln!("Warning: {}", failover);
+}
+
 fn raw_to_human(raw: &str, decimals: u8) -> String {
     let decimals = decimals as usize;
 
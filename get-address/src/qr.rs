@@ -0,0 +1,164 @@
+use alloy::primitives::utils::parse_units;
+use alloy::primitives::{Address, U256};
+use payment_common::config::ResolvedToken;
+use payment_common::payments::NATIVE_DECIMALS;
+use qrcode::render::{svg, unicode};
+use qrcode::{Color, QrCode};
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Pixels per QR module in PNG output
+const PNG_MODULE_PIXELS: usize = 8;
+
+/// Light modules around the code, as the QR spec requires
+const QUIET_ZONE_MODULES: usize = 4;
+
+/// What the QR code encodes, reported in the JSON output
+#[derive(Debug, Serialize)]
+pub struct QrInfo {
+    pub content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+/// EIP-681 URI requesting `amount` of `token` (or of the native token) sent to `to`.
+///
+/// ERC-20: `ethereum:<token>@<chain>/transfer?address=<to>&uint256=<raw>`;
+/// native: `ethereum:<to>@<chain>?value=<wei>`.
+pub fn payment_uri(
+    to: Address,
+    chain_id: Option<u64>,
+    token: Option<&ResolvedToken>,
+    amount: &str,
+) -> Result<String, String> {
+    let chain = chain_id.map(|id| format!("@{}", id)).unwrap_or_default();
+    let decimals = token.map_or(NATIVE_DECIMALS, |t| t.decimals);
+    // parse_units would silently truncate extra digits and accept negative amounts
+    let fraction = amount.split_once('.').map_or("", |(_, f)| f);
+    if amount.starts_with('-') || fraction.len() > decimals as usize {
+        return Err(format!(
+            "Invalid amount '{}': expected a positive amount with at most {} decimals",
+            amount, decimals
+        ));
+    }
+    let raw: U256 = parse_units(amount, decimals)
+        .map_err(|e| format!("Invalid amount '{}': {}", amount, e))?
+        .into();
+    Ok(match token {
+        Some(token) => format!(
+            "ethereum:{}{}/transfer?address={}&uint256={}",
+            token.address, chain, to, raw
+        ),
+        None => format!("ethereum:{}{}?value={}", to, chain, raw),
+    })
+}
+
+/// Encode `content` as a QR code
+pub fn encode(content: &str) -> Result<QrCode, String> {
+    QrCode::new(content.as_bytes()).map_err(|e| format!("Cannot encode QR code: {}", e))
+}
+
+/// Render for a terminal, two modules per character row (light on dark, for dark themes)
+pub fn to_unicode(code: &QrCode) -> String {
+    code.render::<unicode::Dense1x2>()
+        .dark_color(unicode::Dense1x2::Light)
+        .light_color(unicode::Dense1x2::Dark)
+        .build()
+}
+
+/// Write the code to `path` as SVG or PNG, chosen by the file extension
+pub fn write_file(code: &QrCode, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("svg") => {
+            let image = code.render::<svg::Color>().min_dimensions(256, 256).build();
+            std::fs::write(path, image)?;
+        }
+        Some("png") => write_png(code, path)?,
+        _ => {
+            return Err(format!(
+                "Unsupported QR file type: {} (use .svg or .png)",
+                path.display()
+            )
+            .into())
+        }
+    }
+    Ok(())
+}
+
+fn write_png(code: &QrCode, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let modules = code.width();
+    let colors = code.to_colors();
+    let side = (modules + 2 * QUIET_ZONE_MODULES) * PNG_MODULE_PIXELS;
+
+    let mut pixels = vec![0xffu8; side * side];
+    for (i, color) in colors.iter().enumerate() {
+        if *color != Color::Dark {
+            continue;
+        }
+        let x0 = (i % modules + QUIET_ZONE_MODULES) * PNG_MODULE_PIXELS;
+        let y0 = (i / modules + QUIET_ZONE_MODULES) * PNG_MODULE_PIXELS;
+        for y in y0..y0 + PNG_MODULE_PIXELS {
+            pixels[y * side + x0..y * side + x0 + PNG_MODULE_PIXELS].fill(0);
+        }
+    }
+
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(path)?),
+        side as u32,
+        side as u32,
+    );
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&pixels)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::address;
+
+    const TO: Address = address!("52908400098527886E0F7030069857D2E4169EE7");
+
+    #[test]
+    fn test_payment_uri() {
+        let usdc = ResolvedToken {
+            address: address!("833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"),
+            symbol: Some("USDC".to_string()),
+            decimals: 6,
+        };
+        assert_eq!(
+            payment_uri(TO, Some(8453), Some(&usdc), "2.5").unwrap(),
+            "ethereum:0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913@8453/transfer\
+             ?address=0x52908400098527886E0F7030069857D2E4169EE7&uint256=2500000"
+        );
+        assert_eq!(
+            payment_uri(TO, None, None, "0.01").unwrap(),
+            "ethereum:0x52908400098527886E0F7030069857D2E4169EE7?value=10000000000000000"
+        );
+        assert!(payment_uri(TO, None, Some(&usdc), "1.0000001").is_err());
+    }
+
+    #[test]
+    fn test_write_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let code = encode(&TO.to_string()).unwrap();
+        assert!(!to_unicode(&code).is_empty());
+
+        let svg = dir.path().join("address.svg");
+        write_file(&code, &svg).unwrap();
+        assert!(std::fs::read_to_string(&svg).unwrap().contains("<svg"));
+
+        let png = dir.path().join("address.PNG");
+        write_file(&code, &png).unwrap();
+        assert_eq!(&std::fs::read(&png).unwrap()[1..4], b"PNG");
+
+        assert!(write_file(&code, &dir.path().join("address.gif")).is_err());
+    }
+}
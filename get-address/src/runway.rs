@@ -0,0 +1,74 @@
+use alloy::primitives::{Address, U256};
+use alloy::providers::Provider;
+use payment_common::config::ResolvedToken;
+use payment_common::gas;
+use payment_common::payments::NATIVE_DECIMALS;
+use serde::Serialize;
+
+/// Default for `payment.min_gas_runway`
+pub const DEFAULT_MIN_GAS_RUNWAY: u64 = 10;
+
+/// How many payments the native balance pays gas for
+#[derive(Debug, Serialize)]
+pub struct GasRunway {
+    pub payments: u64,
+    pub gas_per_payment: u64,
+    /// Whether `gas_per_payment` was estimated for this wallet (otherwise a typical value)
+    pub estimated: bool,
+    /// Current gas price in wei
+    pub gas_price: String,
+    /// L1 data fee of each payment on OP-stack networks, in human-readable units
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l1_fee_per_payment: Option<String>,
+    /// Native token each payment spends on gas (including any L1 data fee), in
+    /// human-readable units
+    pub cost_per_payment: String,
+    /// `payment.min_gas_runway`
+    pub min_payments: u64,
+}
+
+/// Estimate the gas of a `transfer` of `token` (or a native transfer) at current fees
+pub async fn gas_runway<P: Provider>(
+    provider: &P,
+    owner: Address,
+    token: Option<&ResolvedToken>,
+    token_balance: Option<U256>,
+    native_balance: U256,
+    min_payments: u64,
+) -> Result<GasRunway, Box<dyn std::error::Error>> {
+    let cost = gas::transfer_cost(provider, owner, token, token_balance).await?;
+    let total = cost.total();
+    Ok(GasRunway {
+        payments: payments(native_balance, total),
+        gas_per_payment: cost.gas,
+        estimated: cost.estimated,
+        gas_price: cost.gas_price.to_string(),
+        l1_fee_per_payment: cost
+            .l1_fee
+            .map(|fee| crate::raw_to_human(&fee.to_string(), NATIVE_DECIMALS)),
+        cost_per_payment: crate::raw_to_human(&total.to_string(), NATIVE_DECIMALS),
+        min_payments,
+    })
+}
+
+/// Whole payments `balance` covers at `cost` each (unlimited when gas is free)
+fn payments(balance: U256, cost: U256) -> u64 {
+    if cost.is_zero() {
+        return u64::MAX;
+    }
+    u64::try_from(balance / cost).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payments() {
+        let cost = U256::from(65_000u64 * 1_000_000_000);
+        // 0.001 ETH at 1 gwei and 65k gas per payment
+        assert_eq!(payments(U256::from(1_000_000_000_000_000u64), cost), 15);
+        assert_eq!(payments(U256::ZERO, cost), 0);
+        assert_eq!(payments(U256::from(1), U256::ZERO), u64::MAX);
+    }
+}
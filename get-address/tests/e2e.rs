@@ -0,0 +1,118 @@
+use alloy::primitives::{Address, U256};
+use serde_json::Value;
+use test_support::{MockChain, TestHome, GAS_PRICE, TOKEN_CALL_GAS};
+
+const GET_ADDRESS: &str = env!("CARGO_BIN_EXE_get-address");
+
+/// The reported wallet address, which keeps the keystore's lowercase form
+fn address(info: &Value) -> Address {
+    info["address"].as_str().unwrap().parse().unwrap()
+}
+
+fn get_address(home: &TestHome, args: &[&str]) -> Value {
+    let output = home
+        .command(GET_ADDRESS)
+        .args(args)
+        .output()
+        .expect("run get-address");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("JSON output")
+}
+
+#[test]
+fn test_get_address_balance_and_runway() {
+    let chain = MockChain::start();
+    let token = chain.deploy_token(6);
+    let home = TestHome::new(&chain, Some((token, 6)));
+    let cost = TOKEN_CALL_GAS as u128 * GAS_PRICE;
+    chain.fund(home.address(), U256::from(3 * cost));
+    chain.mint(token, home.address(), U256::from(12_340_000));
+
+    let info = get_address(&home, &[]);
+    assert_eq!(address(&info), home.address());
+    assert_eq!(info["balance"], "12.34");
+    assert_eq!(info["token_symbol"], "TEST");
+
+    let runway = &info["gas_runway"];
+    assert_eq!(runway["payments"], 3);
+    assert_eq!(runway["gas_per_payment"], TOKEN_CALL_GAS);
+    assert_eq!(runway["estimated"], true);
+    let warnings = info["warnings"].as_array().expect("low runway warning");
+    assert!(warnings[0].as_str().unwrap().contains("runway"));
+}
+
+#[test]
+fn test_get_address_at_past_block() {
+    let chain = MockChain::start();
+    let token = chain.deploy_token(6);
+    let home = TestHome::new(&chain, Some((token, 6)));
+    chain.mint(token, home.address(), U256::from(5_000_000));
+    chain.mine();
+    chain.mint(token, home.address(), U256::from(7_000_000));
+
+    assert_eq!(get_address(&home, &[])["balance"], "7");
+
+    let info = get_address(&home, &["--block", "0"]);
+    assert_eq!(info["balance"], "5");
+    assert_eq!(info["block"]["number"], 0);
+
+    // Block 1 is 12 seconds after block 0 (2023-11-14T22:13:20Z)
+    let info = get_address(&home, &["--at", "2023-11-14T22:13:25Z"]);
+    assert_eq!(info["balance"], "5");
+    assert_eq!(info["block"]["number"], 0);
+}
+
+#[test]
+fn test_get_address_reports_token_when_rpc_fails() {
+    let chain = MockChain::start();
+    let token = chain.deploy_token(6);
+    let home = TestHome::new(&chain, Some((token, 6)));
+
+    // Nothing listens on the discard port
+    let output = home
+        .command(GET_ADDRESS)
+        .env("PAYMENT_NETWORK_RPC_URL", "http://127.0.0.1:9")
+        .output()
+        .expect("run get-address");
+    assert!(output.status.success());
+    let info: Value = serde_json::from_slice(&output.stdout).expect("JSON output");
+    assert!(info["balance"].is_null());
+    assert_eq!(
+        info["token"].as_str().unwrap().parse::<Address>().unwrap(),
+        token
+    );
+    assert_eq!(info["token_symbol"], "TEST");
+    assert!(info["network"].is_string());
+}
+
+#[test]
+fn test_get_address_runway_includes_l1_fee() {
+    let chain = MockChain::start();
+    let token = chain.deploy_token(6);
+    let home = TestHome::new(&chain, Some((token, 6)));
+    // The L1 data fee doubles the cost of each payment
+    let cost = TOKEN_CALL_GAS as u128 * GAS_PRICE;
+    chain.set_l1_fee(U256::from(cost));
+    chain.fund(home.address(), U256::from(20 * cost));
+    chain.mint(token, home.address(), U256::from(1_000_000));
+
+    let info = get_address(&home, &[]);
+    let runway = &info["gas_runway"];
+    assert_eq!(runway["payments"], 10);
+    assert_eq!(runway["l1_fee_per_payment"], "0.000052");
+    assert_eq!(runway["cost_per_payment"], "0.000104");
+}
+
+#[test]
+fn test_get_address_verify() {
+    let chain = MockChain::start();
+    let home = TestHome::new(&chain, None);
+
+    let info = get_address(&home, &["--verify"]);
+    assert_eq!(address(&info), home.address());
+    assert_eq!(info["verification"]["ok"], true);
+}
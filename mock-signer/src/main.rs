@@ -0,0 +1,302 @@
+use alloy::consensus::{TxEip1559, TxLegacy, TypedTransaction};
+use alloy::eips::eip2718::Encodable2718;
+use alloy::network::TxSignerSync;
+use alloy::primitives::{hex, Bytes, TxKind, U256};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::SignerSync;
+use clap::Parser;
+use payment_common::signer::TypedData;
+use payment_common::Wallet;
+use serde_json::{json, Value};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use test_support::server;
+
+/// Minimal Web3Signer-compatible signing service for offline testing
+///
+/// Serves `eth_accounts`, `eth_signTransaction`, `eth_sign` and
+/// `eth_signTypedData` over HTTP JSON-RPC with a single key. Never use it with a key that holds real funds.
+#[derive(Parser, Debug)]
+#[command(name = "mock-signer")]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:9000")]
+    listen: String,
+
+    /// Keystore to load the key from (a random key is generated if omitted)
+    #[arg(long, short = 'w', requires = "password_file")]
+    wallet: Option<PathBuf>,
+
+    /// Password file for the keystore
+    #[arg(long)]
+    password_file: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code() as u8)
+        }
+    }
+}
+
+fn run(args: Args) -> payment_common::Result<()> {
+    let signer = match (&args.wallet, &args.password_file) {
+        (Some(wallet), Some(password_file)) => {
+            let password = Wallet::load_password(Some(password_file))?;
+            let key = eth_keystore::decrypt_key(wallet, password).map_err(|e| {
+                payment_common::Error::Wallet(format!("Failed to decrypt wallet: {}", e))
+            })?;
+            PrivateKeySigner::from_slice(&key)
+                .map_err(|e| payment_common::Error::Wallet(format!("Invalid private key: {}", e)))?
+        }
+        _ => PrivateKeySigner::random(),
+    };
+
+    let listener = TcpListener::bind(&args.listen)?;
+    eprintln!(
+        "mock-signer serving {} on http://{}",
+        signer.address(),
+        listener.local_addr()?
+    );
+    server::serve_json_rpc(listener, |request| handle_rpc(request, &signer));
+    Ok(())
+}
+
+fn handle_rpc(request: &Value, signer: &PrivateKeySigner) -> Value {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request.get("method").and_then(Value::as_str).unwrap_or("");
+
+    let result = match method {
+        "eth_accounts" => Ok(json!([signer.address()])),
+        "eth_signTransaction" => request
+            .get("params")
+            .and_then(|p| p.get(0))
+            .ok_or_else(|| "Missing transaction parameter".to_string())
+            .and_then(|params| sign_transaction(params, signer)),
+        "eth_sign" => sign_message(request.get("params"), signer),
+        "eth_signTypedData" => sign_typed_data(request.get("params"), signer),
+        _ => Err(format!("Method not found: {}", method)),
+    };
+
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(message) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32000, "message": message },
+        }),
+    }
+}
+
+fn sign_transaction(params: &Value, signer: &PrivateKeySigner) -> Result<Value, String> {
+    let from = params.get("from").and_then(Value::as_str).unwrap_or("");
+    if !from.eq_ignore_ascii_case(&signer.address().to_string()) {
+        return Err(format!("No key for account {}", from));
+    }
+
+    let mut tx = typed_transaction_from_rpc(params).map_err(|e| e.to_string())?;
+    let signature = match &mut tx {
+        TypedTransaction::Legacy(t) => signer.sign_transaction_sync(t),
+        TypedTransaction::Eip1559(t) => signer.sign_transaction_sync(t),
+        _ => return Err("Unsupported transaction type".to_string()),
+    }
+    .map_err(|e| e.to_string())?;
+
+    let signed = tx.into_envelope(signature);
+    Ok(json!(format!("0x{}", hex::encode(signed.encoded_2718()))))
+}
+
+/// Convert the JSON-RPC transaction object sent to `eth_signTransaction` back into a
+/// typed transaction
+fn typed_transaction_from_rpc(params: &Value) -> payment_common::Result<TypedTransaction> {
+    fn quantity(params: &Value, key: &str) -> payment_common::Result<Option<U256>> {
+        params
+            .get(key)
+            .and_then(Value::as_str)
+            .map(|s| {
+                U256::from_str_radix(s.trim_start_matches("0x"), 16).map_err(|_| {
+                    payment_common::Error::InvalidArgument(format!("Invalid {}: {}", key, s))
+                })
+            })
+            .transpose()
+    }
+    fn required(params: &Value, key: &str) -> payment_common::Result<U256> {
+        quantity(params, key)?
+            .ok_or_else(|| payment_common::Error::InvalidArgument(format!("Missing {}", key)))
+    }
+    fn narrow<T: TryFrom<U256>>(value: U256, key: &str) -> payment_common::Result<T> {
+        T::try_from(value)
+            .map_err(|_| payment_common::Error::InvalidArgument(format!("{} is out of range", key)))
+    }
+
+    let to =
+        match params.get("to").and_then(Value::as_str) {
+            Some(to) => TxKind::Call(to.parse().map_err(|_| {
+                payment_common::Error::InvalidArgument(format!("Invalid to: {}", to))
+            })?),
+            None => TxKind::Create,
+        };
+    let input: Bytes = match params.get("data").or_else(|| params.get("input")) {
+        Some(data) => serde_json::from_value(data.clone())?,
+        None => Bytes::new(),
+    };
+    let value = quantity(params, "value")?.unwrap_or_default();
+    let nonce = narrow(required(params, "nonce")?, "nonce")?;
+    let gas_limit = narrow(required(params, "gas")?, "gas")?;
+    let chain_id = quantity(params, "chainId")?
+        .map(|c| narrow(c, "chainId"))
+        .transpose()?;
+
+    if let Some(max_fee) = quantity(params, "maxFeePerGas")? {
+        Ok(TypedTransaction::Eip1559(TxEip1559 {
+            chain_id: chain_id.ok_or_else(|| {
+                payment_common::Error::InvalidArgument("Missing chainId".to_string())
+            })?,
+            nonce,
+            gas_limit,
+            max_fee_per_gas: narrow(max_fee, "maxFeePerGas")?,
+            max_priority_fee_per_gas: narrow(
+                required(params, "maxPriorityFeePerGas")?,
+                "maxPriorityFeePerGas",
+            )?,
+            to,
+            value,
+            input,
+            access_list: Default::default(),
+        }))
+    } else {
+        Ok(TypedTransaction::Legacy(TxLegacy {
+            chain_id,
+            nonce,
+            gas_price: narrow(required(params, "gasPrice")?, "gasPrice")?,
+            gas_limit,
+            to,
+            value,
+            input,
+        }))
+    }
+}
+
+/// Check the `[address, payload]` params of `eth_sign`/`eth_signTypedData` and return the payload
+fn account_params<'a>(
+    params: Option<&'a Value>,
+    signer: &PrivateKeySigner,
+) -> Result<&'a Value, String> {
+    let from = params
+        .and_then(|p| p.get(0))
+        .and_then(Value::as_str)
+        .unwrap_or("");
+    if !from.eq_ignore_ascii_case(&signer.address().to_string()) {
+        return Err(format!("No key for account {}", from));
+    }
+    params
+        .and_then(|p| p.get(1))
+        .ok_or_else(|| "Missing data parameter".to_string())
+}
+
+fn sign_message(params: Option<&Value>, signer: &PrivateKeySigner) -> Result<Value, String> {
+    let data = account_params(params, signer)?
+        .as_str()
+        .ok_or_else(|| "Data must be a hex string".to_string())?;
+    let message = hex::decode(data).map_err(|e| e.to_string())?;
+    let signature = signer
+        .sign_message_sync(&message)
+        .map_err(|e| e.to_string())?;
+    Ok(json!(hex::encode_prefixed(signature.as_bytes())))
+}
+
+fn sign_typed_data(params: Option<&Value>, signer: &PrivateKeySigner) -> Result<Value, String> {
+    let typed_data: TypedData = serde_json::from_value(account_params(params, signer)?.clone())
+        .map_err(|e| e.to_string())?;
+    let signature = signer
+        .sign_dynamic_typed_data_sync(&typed_data)
+        .map_err(|e| e.to_string())?;
+    Ok(json!(hex::encode_prefixed(signature.as_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::consensus::{Transaction, TxLegacy, TypedTransaction};
+    use alloy::primitives::{address, TxKind, U256};
+    use payment_common::signer::RemoteSigner;
+    use payment_common::PaymentSigner;
+    use std::time::Duration;
+
+    fn spawn_mock_signer() -> (String, PrivateKeySigner) {
+        let signer = PrivateKeySigner::random();
+        let server_signer = signer.clone();
+        let url = server::spawn_json_rpc(move |request| handle_rpc(request, &server_signer));
+        (url, signer)
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_round_trip() {
+        let (url, key) = spawn_mock_signer();
+        let remote = RemoteSigner::connect(&url, None, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(remote.address(), key.address());
+
+        let tx = TypedTransaction::Legacy(TxLegacy {
+            chain_id: Some(84532),
+            nonce: 7,
+            gas_price: 1_000_000_000,
+            gas_limit: 21_000,
+            to: TxKind::Call(address!("0x000000000000000000000000000000000000dEaD")),
+            value: U256::from(1234u64),
+            input: Default::default(),
+        });
+        let envelope = remote.sign_transaction(tx).await.unwrap();
+
+        assert_eq!(envelope.nonce(), 7);
+        assert_eq!(envelope.chain_id(), Some(84532));
+        assert_eq!(envelope.value(), U256::from(1234u64));
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_rejects_unknown_address() {
+        let (url, _) = spawn_mock_signer();
+        let other = PrivateKeySigner::random().address();
+        let result = RemoteSigner::connect(&url, Some(other), Duration::from_secs(5)).await;
+        assert!(matches!(result, Err(payment_common::Error::Signer(_))));
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_signs_messages() {
+        let (url, key) = spawn_mock_signer();
+        let remote = RemoteSigner::connect(&url, None, Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        let signature = remote.sign_message(b"hello").await.unwrap();
+        assert_eq!(
+            signature.recover_address_from_msg(b"hello").unwrap(),
+            key.address()
+        );
+
+        let typed_data: TypedData = serde_json::from_value(json!({
+            "types": {
+                "EIP712Domain": [{ "name": "name", "type": "string" }],
+                "Login": [{ "name": "nonce", "type": "uint256" }],
+            },
+            "primaryType": "Login",
+            "domain": { "name": "example" },
+            "message": { "nonce": 1 },
+        }))
+        .unwrap();
+        let signature = remote.sign_typed_data(&typed_data).await.unwrap();
+        let hash = typed_data.eip712_signing_hash().unwrap();
+        assert_eq!(
+            signature.recover_address_from_prehash(&hash).unwrap(),
+            key.address()
+        );
+    }
+}
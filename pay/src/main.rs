@@ -1,38 +1,34 @@
-use alloy::network::{EthereumWallet, TransactionBuilder};
-use alloy::primitives::{Address, U256};
-use alloy::providers::{Provider, ProviderBuilder};
-use alloy::signers::local::PrivateKeySigner;
-use alloy::sol;
 use clap::Parser;
+use payment_common::payments::{
+    Amount, PaymentError, PaymentEvent, PaymentReceipt, PaymentRequest,
+};
 use payment_common::Config;
-use std::fs;
+use serde::Serialize;
 use std::path::PathBuf;
 use std::process::ExitCode;
 
-// ERC-20 transfer function
-sol! {
-    #[sol(rpc)]
-    contract IERC20 {
-        function transfer(address to, uint256 amount) external returns (bool);
-        function balanceOf(address account) external view returns (uint256);
-    }
-}
-
 /// Make token payments from an x402 wallet
 #[derive(Parser, Debug)]
 #[command(name = "pay")]
 #[command(about = "Transfer tokens from your x402 wallet")]
 #[command(version)]
 struct Args {
-    /// Recipient Ethereum address
+    /// Recipient Ethereum address, ENS or Basename (e.g. alice.eth, bob.base.eth),
+    /// or @label from the address book (payment-config contacts)
     #[arg(long)]
     to: String,
 
-    /// Amount to transfer (in smallest unit, e.g., wei for ETH, 6 decimals for USDC)
-    #[arg(long)]
-    amount: String,
+    /// Amount to transfer in token units (e.g. 1.5 for 1.5 USDC, or 0.01 for 0.01 ETH)
+    #[arg(long, required_unless_present = "amount_usd")]
+    amount: Option<String>,
+
+    /// Amount in US dollars (e.g. 2.50), converted at the token's current on-chain price
+    /// (needs a price feed for the token, see price_feeds in config.toml)
+    #[arg(long, conflicts_with = "amount")]
+    amount_usd: Option<String>,
 
-    /// ERC-20 token contract address (omit for native ETH/gas token)
+    /// ERC-20 token symbol from the registry (e.g., USDC, EURC) or contract address
+    /// (omit for the configured default token or native ETH/gas token)
     #[arg(long)]
     token: Option<String>,
 
@@ -64,397 +60,248 @@ struct Args {
     #[arg(long, short = 'c')]
     config: Option<PathBuf>,
 
+    /// Named environment from config.toml (default: $PAYMENT_ENV, then active_env)
+    #[arg(long)]
+    env: Option<String>,
+
     /// Don't wait for transaction confirmation
     #[arg(long)]
     no_wait: bool,
+
+    /// Print a JSON summary of the payment instead of only the transaction hash
+    #[arg(long)]
+    json: bool,
+}
+
+/// Summary of a sent payment, printed with --json
+#[derive(Serialize)]
+struct PaymentOutput {
+    tx_hash: String,
+    status: &'static str,
+    from: String,
+    to: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contact: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    amount: String,
+    raw_amount: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usd_value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    network: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    env: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_number: Option<u64>,
+    /// USD value of the gas the confirmed transaction paid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_usd_value: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+}
+
+impl PaymentOutput {
+    fn new(receipt: PaymentReceipt, env: Option<&str>) -> Self {
+        let (token, token_symbol) = match receipt.token {
+            Some(token) => (Some(token.address.to_string()), token.symbol),
+            None => (None, None),
+        };
+        PaymentOutput {
+            tx_hash: receipt.tx_hash.to_string(),
+            status: if receipt.confirmed {
+                "confirmed"
+            } else {
+                "sent"
+            },
+            from: receipt.from.to_string(),
+            to: receipt.to.to_string(),
+            contact: receipt.contact,
+            name: receipt.name,
+            amount: receipt.amount,
+            raw_amount: receipt.raw_amount.to_string(),
+            usd_value: receipt.usd_value,
+            token,
+            token_symbol,
+            network: receipt.network,
+            env: env.map(str::to_string),
+            block_number: receipt.block_number,
+            gas_usd_value: receipt.gas_usd_value,
+            warnings: receipt.warnings,
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> ExitCode {
     let args = Args::parse();
+    let json = args.json;
 
     match run(args).await {
-        Ok(tx_hash) => {
-            println!("{}", tx_hash);
+        Ok(payment) if json => match serde_json::to_string_pretty(&payment) {
+            Ok(output) => {
+                println!("{}", output);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("Error serializing payment: {}", e);
+                ExitCode::from(1)
+            }
+        },
+        Ok(payment) => {
+            println!("{}", payment.tx_hash);
             ExitCode::SUCCESS
         }
         Err(e) => {
             eprintln!("Error: {}", e);
-            // Map error to exit code
-            let code = match &e {
-                PayError::InsufficientBalance(_) => 1,
-                PayError::TransactionFailed(_) => 2,
-                PayError::NetworkError(_) => 3,
-                PayError::MissingConfig(_) => 10,
-                PayError::InvalidConfig(_) => 11,
-                PayError::WalletNotFound(_) => 12,
-                PayError::InvalidArgument(_) => 20,
-                _ => 1,
-            };
-            ExitCode::from(code)
+            ExitCode::from(e.exit_code() as u8)
         }
     }
 }
 
-#[derive(Debug)]
-enum PayError {
-    InsufficientBalance(String),
-    TransactionFailed(String),
-    NetworkError(String),
-    MissingConfig(String),
-    InvalidConfig(String),
-    WalletNotFound(String),
-    InvalidArgument(String),
-    Other(String),
-}
-
-impl std::fmt::Display for PayError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            PayError::InsufficientBalance(msg) => write!(f, "Insufficient balance: {}", msg),
-            PayError::TransactionFailed(msg) => write!(f, "Transaction failed: {}", msg),
-            PayError::NetworkError(msg) => write!(f, "Network error: {}", msg),
-            PayError::MissingConfig(msg) => write!(f, "Missing configuration: {}", msg),
-            PayError::InvalidConfig(msg) => write!(f, "Invalid configuration: {}", msg),
-            PayError::WalletNotFound(msg) => write!(f, "Wallet not found: {}", msg),
-            PayError::InvalidArgument(msg) => write!(f, "Invalid argument: {}", msg),
-            PayError::Other(msg) => write!(f, "{}", msg),
+/// Report each step of the payment on stderr
+fn print_progress(event: &PaymentEvent) {
+    match event {
+        PaymentEvent::Resolving(name) => eprintln!("Resolving {}...", name),
+        PaymentEvent::Warning(warning) => eprintln!("Warning: {}", warning),
+        PaymentEvent::Parties {
+            from,
+            to,
+            contact,
+            name,
+        } => {
+            eprintln!("From: {}", from);
+            match (contact, name) {
+                (Some(label), _) => eprintln!("To: @{} ({})", label, to),
+                (None, Some(name)) => eprintln!("To: {} ({})", name, to),
+                (None, None) => eprintln!("To: {}", to),
+            }
+        }
+        PaymentEvent::Connecting(urls) => eprintln!("Connecting to {}...", urls.join(", ")),
+        PaymentEvent::RpcFailover(failover) => eprintln!("Warning: {}", failover),
+        PaymentEvent::Signer(event) if event.is_warning() => eprintln!("Warning: {}", event),
+        PaymentEvent::Signer(event) => eprintln!("{}", event),
+        PaymentEvent::UsdConversion {
+            usd,
+            price,
+            symbol,
+            amount,
+        } => eprintln!(
+            "${} at ${} per {} = {} {}",
+            usd, price, symbol, amount, symbol
+        ),
+        PaymentEvent::Amount {
+            amount,
+            symbol,
+            raw,
+            decimals,
+            usd_value,
+        } => eprintln!(
+            "Amount: {} {}(raw: {} with {} decimals{})",
+            amount,
+            symbol
+                .as_deref()
+                .map(|s| format!("{} ", s))
+                .unwrap_or_default(),
+            raw,
+            decimals,
+            usd_value
+                .as_deref()
+                .map(|v| format!(", ${}", v))
+                .unwrap_or_default()
+        ),
+        PaymentEvent::GasPrice(wei) => {
+            eprintln!("Network gas price: {} Gwei", wei / 1_000_000_000)
         }
+        PaymentEvent::Sending {
+            raw,
+            to,
+            token: Some(_),
+        } => eprintln!("Sending {} tokens to {}...", raw, to),
+        PaymentEvent::Sending {
+            raw,
+            to,
+            token: None,
+        } => eprintln!("Sending {} wei to {}...", raw, to),
+        PaymentEvent::Sent(tx_hash) => eprintln!("Transaction sent: {}", tx_hash),
+        PaymentEvent::Waiting => eprintln!("Waiting for confirmation..."),
+        PaymentEvent::Confirmed(block) => eprintln!("Confirmed in block {}", block),
+        _ => {}
     }
 }
 
-async fn run(args: Args) -> Result<String, PayError> {
-    // Load config
-    let config =
-        Config::load_from(args.config.as_deref()).map_err(|e| PayError::Other(e.to_string()))?;
+async fn run(args: Args) -> Result<PaymentOutput, PaymentError> {
+    // Load config, then apply CLI overrides
+    let mut config = Config::load_env(args.config.as_deref(), args.env.as_deref())
+        .map_err(|e| PaymentError::Other(e.to_string()))?;
+    for warning in config.warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+    if let Some(rpc_url) = &args.rpc {
+        // An explicit endpoint replaces the configured failover list
+        config.network.rpc_urls.clear();
+        config
+            .set_from_flag("network.rpc_url", rpc_url)
+            .map_err(|e| PaymentError::InvalidArgument(e.to_string()))?;
+    }
+    if let Some(chain_id) = args.chain_id {
+        config
+            .set_from_flag("network.chain_id", &chain_id.to_string())
+            .map_err(|e| PaymentError::InvalidArgument(e.to_string()))?;
+    }
+    if let Some(env) = config.env_name() {
+        eprintln!("Environment: {}", env);
+    }
 
     // Check network config
     if let Err(prompt) = config.check_network_config() {
-        let json =
-            serde_json::to_string_pretty(&prompt).map_err(|e| PayError::Other(e.to_string()))?;
+        let json = serde_json::to_string_pretty(&prompt)
+            .map_err(|e| PaymentError::Other(e.to_string()))?;
         eprintln!("{}", json);
-        return Err(PayError::MissingConfig(
+        return Err(PaymentError::MissingConfig(
             "Network configuration is incomplete. Run: payment-config use-network <network-name>"
                 .to_string(),
         ));
     }
 
-    // Get RPC URL (CLI > config)
-    let rpc_url = args
-        .rpc
-        .or(config.network.rpc_url.clone())
-        .ok_or_else(|| PayError::MissingConfig("RPC URL not configured".to_string()))?;
-
-    // Get chain ID (CLI > config > auto-detect)
-    let chain_id = args.chain_id.or(config.network.chain_id);
-
-    // Get wallet path
-    let wallet_path = args.wallet.unwrap_or_else(|| config.wallet_path());
-    if !wallet_path.exists() {
-        return Err(PayError::WalletNotFound(wallet_path.display().to_string()));
-    }
-
-    // Get password
-    let password = if let Some(pw) = args.password {
-        pw
-    } else if let Some(pw_file) = args.password_file {
-        fs::read_to_string(&pw_file)
-            .map_err(|e| PayError::Other(format!("Failed to read password file: {}", e)))?
-            .trim()
-            .to_string()
-    } else {
-        // Try config's password file
-        let pw_path = config.password_path();
-        if pw_path.exists() {
-            fs::read_to_string(&pw_path)
-                .map_err(|e| PayError::Other(format!("Failed to read password file: {}", e)))?
-                .trim()
-                .to_string()
-        } else {
-            return Err(PayError::InvalidArgument(
-                "No password provided. Use --password, --password-file, or configure wallet.password_file".to_string(),
-            ));
+    let amount = match (args.amount, args.amount_usd) {
+        (_, Some(usd)) => Amount::Usd(usd),
+        (Some(human), None) => Amount::Units(human),
+        (None, None) => {
+            return Err(PaymentError::InvalidArgument(
+                "--amount or --amount-usd is required".to_string(),
+            ))
         }
     };
-
-    // Parse recipient address
-    let to_address: Address = args.to.parse().map_err(|_| {
-        PayError::InvalidArgument(format!("Invalid recipient address: {}", args.to))
-    })?;
-
-    // Get decimals (default to 6 for USDC)
-    let decimals = config.payment.default_token_decimals.unwrap_or(6);
-
-    // Parse amount (human-readable) and convert to blockchain units
-    let amount: U256 = human_to_raw(&args.amount, decimals).map_err(|e| {
-        PayError::InvalidArgument(format!("Invalid amount '{}': {}", args.amount, e))
-    })?;
-
-    eprintln!(
-        "Amount: {} (raw: {} with {} decimals)",
-        args.amount, amount, decimals
-    );
-
-    // Get token address (CLI > config default)
-    let token_address: Option<Address> =
-        if let Some(token) = args.token {
-            Some(token.parse().map_err(|_| {
-                PayError::InvalidArgument(format!("Invalid token address: {}", token))
-            })?)
-        } else {
-            config
-                .payment
-                .default_token
-                .as_ref()
-                .and_then(|t| t.parse().ok())
-        };
-
-    // Decrypt wallet
-    eprintln!("Decrypting wallet...");
-    let keystore_content = fs::read_to_string(&wallet_path)
-        .map_err(|e| PayError::Other(format!("Failed to read wallet: {}", e)))?;
-    let _keystore: serde_json::Value = serde_json::from_str(&keystore_content)
-        .map_err(|e| PayError::Other(format!("Invalid keystore format: {}", e)))?;
-
-    // Get the directory and filename for eth_keystore
-    let keystore_dir = wallet_path.parent().unwrap_or(std::path::Path::new("."));
-    let keystore_name = wallet_path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("wallet.json");
-
-    let private_key = eth_keystore::decrypt_key(keystore_dir.join(keystore_name), &password)
-        .map_err(|e| PayError::Other(format!("Failed to decrypt wallet: {}", e)))?;
-
-    let signer: PrivateKeySigner = PrivateKeySigner::from_slice(&private_key)
-        .map_err(|e| PayError::Other(format!("Invalid private key: {}", e)))?;
-
-    let from_address = signer.address();
-    eprintln!("From: {}", from_address);
-    eprintln!("To: {}", to_address);
-
-    // Create provider
-    eprintln!("Connecting to {}...", rpc_url);
-    let wallet = EthereumWallet::from(signer);
-
-    let provider = ProviderBuilder::new().wallet(wallet).connect_http(
-        rpc_url
-            .parse()
-            .map_err(|_| PayError::InvalidConfig(format!("Invalid RPC URL: {}", rpc_url)))?,
-    );
-
-    // Verify chain ID if specified
-    if let Some(expected_chain_id) = chain_id {
-        let actual_chain_id = provider
-            .get_chain_id()
-            .await
-            .map_err(|e| PayError::NetworkError(format!("Failed to get chain ID: {}", e)))?;
-        if actual_chain_id != expected_chain_id {
-            return Err(PayError::InvalidConfig(format!(
-                "Chain ID mismatch: expected {}, got {}",
-                expected_chain_id, actual_chain_id
-            )));
-        }
+    let mut request = PaymentRequest::new(args.to, amount).on_progress(print_progress);
+    if let Some(token) = args.token {
+        request = request.token(token);
     }
-
-    // Get gas price (CLI override or fetch from network)
-    let gas_price_wei: u128 = if let Some(gwei) = args.gas_price {
+    if let Some(wallet) = args.wallet {
+        request = request.wallet(wallet);
+    }
+    if let Some(password) = args.password {
+        request = request.password(password);
+    }
+    if let Some(password_file) = args.password_file {
+        request = request.password_file(password_file);
+    }
+    if let Some(gwei) = args.gas_price {
         // Convert Gwei to Wei (1 Gwei = 10^9 Wei)
-        let wei = (gwei * 1_000_000_000.0) as u128;
         eprintln!("Using gas price: {} Gwei", gwei);
-        wei
-    } else {
-        let price = provider
-            .get_gas_price()
-            .await
-            .map_err(|e| PayError::NetworkError(format!("Failed to get gas price: {}", e)))?;
-        eprintln!("Network gas price: {} Gwei", price / 1_000_000_000);
-        price
-    };
-
-    // Send transaction
-    let tx_hash = if let Some(token_addr) = token_address {
-        // ERC-20 transfer
-        eprintln!("Sending {} tokens to {}...", amount, to_address);
-
-        // Check token balance first
-        let token_contract = IERC20::new(token_addr, &provider);
-        let balance = token_contract
-            .balanceOf(from_address)
-            .call()
-            .await
-            .map_err(|e| PayError::NetworkError(format!("Failed to get token balance: {}", e)))?;
-
-        if balance < amount {
-            return Err(PayError::InsufficientBalance(format!(
-                "Token balance {} is less than amount {}",
-                balance, amount
-            )));
-        }
-
-        // Send transfer transaction with gas price
-        let tx = token_contract
-            .transfer(to_address, amount)
-            .gas_price(gas_price_wei);
-        let pending_tx = tx.send().await.map_err(|e| {
-            PayError::TransactionFailed(format!("Failed to send transaction: {}", e))
-        })?;
-
-        let tx_hash = *pending_tx.tx_hash();
-        eprintln!("Transaction sent: {}", tx_hash);
-
-        if !args.no_wait {
-            eprintln!("Waiting for confirmation...");
-            let receipt = pending_tx
-                .get_receipt()
-                .await
-                .map_err(|e| PayError::TransactionFailed(format!("Transaction failed: {}", e)))?;
-
-            if !receipt.status() {
-                return Err(PayError::TransactionFailed(
-                    "Transaction reverted".to_string(),
-                ));
-            }
-            eprintln!(
-                "Confirmed in block {}",
-                receipt.block_number.unwrap_or_default()
-            );
-        }
-
-        format!("{}", tx_hash)
-    } else {
-        // Native ETH transfer
-        eprintln!("Sending {} wei to {}...", amount, to_address);
-
-        // Check ETH balance first
-        let balance = provider
-            .get_balance(from_address)
-            .await
-            .map_err(|e| PayError::NetworkError(format!("Failed to get balance: {}", e)))?;
-
-        let gas_limit = U256::from(21000); // Standard ETH transfer gas
-        let total_cost = amount + (gas_limit * U256::from(gas_price_wei));
-
-        if balance < total_cost {
-            return Err(PayError::InsufficientBalance(format!(
-                "Balance {} is less than amount + gas ({})",
-                balance, total_cost
-            )));
-        }
-
-        // Build and send transaction with gas price
-        let tx = alloy::rpc::types::TransactionRequest::default()
-            .with_to(to_address)
-            .with_value(amount)
-            .with_gas_price(gas_price_wei);
-
-        let pending_tx = provider.send_transaction(tx).await.map_err(|e| {
-            PayError::TransactionFailed(format!("Failed to send transaction: {}", e))
-        })?;
-
-        let tx_hash = *pending_tx.tx_hash();
-        eprintln!("Transaction sent: {}", tx_hash);
-
-        if !args.no_wait {
-            eprintln!("Waiting for confirmation...");
-            let receipt = pending_tx
-                .get_receipt()
-                .await
-                .map_err(|e| PayError::TransactionFailed(format!("Transaction failed: {}", e)))?;
-
-            if !receipt.status() {
-                return Err(PayError::TransactionFailed(
-                    "Transaction reverted".to_string(),
-                ));
-            }
-            eprintln!(
-                "Confirmed in block {}",
-                receipt.block_number.unwrap_or_default()
-            );
-        }
-
-        format!("{}", tx_hash)
-    };
-
-    Ok(tx_hash)
-}
-
-/// Convert human-readable amount to raw blockchain units
-fn human_to_raw(human: &str, decimals: u8) -> Result<U256, String> {
-    let decimals = decimals as usize;
-    let human = human.trim();
-
-    if human.is_empty() {
-        return Err("Amount cannot be empty".to_string());
+        request = request.gas_price((gwei * 1_000_000_000.0) as u128);
     }
-
-    // Handle both integer and decimal inputs
-    let (integer_part, decimal_part) = if let Some(pos) = human.find('.') {
-        let (int_str, dec_str) = human.split_at(pos);
-        let int_part = if int_str.is_empty() { "0" } else { int_str };
-        (int_part.to_string(), dec_str[1..].to_string()) // Skip the '.'
-    } else {
-        (human.to_string(), String::new())
-    };
-
-    // Validate parts are numeric
-    if !integer_part.chars().all(|c| c.is_ascii_digit()) || integer_part.is_empty() {
-        return Err("Invalid integer part".to_string());
+    if args.no_wait {
+        request = request.no_wait();
     }
-    if !decimal_part.chars().all(|c| c.is_ascii_digit()) {
-        return Err("Invalid decimal part".to_string());
+    if args.json {
+        request = request.reverse_lookup();
     }
 
-    // Pad or truncate decimal part to match decimals
-    let decimal_padded = if decimal_part.len() < decimals {
-        format!("{:0<width$}", decimal_part, width = decimals)
-    } else if decimal_part.len() > decimals {
-        // Truncate (could also error here)
-        decimal_part[..decimals].to_string()
-    } else {
-        decimal_part
-    };
-
-    // Combine integer and decimal parts
-    let raw_str = format!("{}{}", integer_part, decimal_padded);
-
-    // Remove leading zeros but keep at least one digit
-    let raw_trimmed = raw_str.trim_start_matches('0');
-    let raw_final = if raw_trimmed.is_empty() {
-        "0"
-    } else {
-        raw_trimmed
-    };
-
-    raw_final
-        .parse::<U256>()
-        .map_err(|e| format!("Failed to parse amount: {}", e))
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_human_to_raw() {
-        // Test with 6 decimals (USDC)
-        assert_eq!(human_to_raw("1", 6).unwrap(), U256::from(1_000_000u64));
-        assert_eq!(human_to_raw("1.0", 6).unwrap(), U256::from(1_000_000u64));
-        assert_eq!(human_to_raw("1.5", 6).unwrap(), U256::from(1_500_000u64));
-        assert_eq!(human_to_raw("0.5", 6).unwrap(), U256::from(500_000u64));
-        assert_eq!(human_to_raw(".5", 6).unwrap(), U256::from(500_000u64));
-        assert_eq!(human_to_raw("0.000001", 6).unwrap(), U256::from(1u64));
-        assert_eq!(human_to_raw("100", 6).unwrap(), U256::from(100_000_000u64));
-        assert_eq!(human_to_raw("0", 6).unwrap(), U256::from(0u64));
-        assert_eq!(human_to_raw("0.0", 6).unwrap(), U256::from(0u64));
-        assert_eq!(
-            human_to_raw("1.123456", 6).unwrap(),
-            U256::from(1_123_456u64)
-        );
-        // Test truncation of extra decimals
-        assert_eq!(
-            human_to_raw("1.1234567", 6).unwrap(),
-            U256::from(1_123_456u64)
-        );
-    }
+    let receipt = request.send(&config).await?;
+    Ok(PaymentOutput::new(receipt, config.env_name()))
 }
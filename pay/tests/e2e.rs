@@ -0,0 +1,173 @@
+use alloy::primitives::{address, Address, U256};
+use payment_common::gas::NATIVE_TRANSFER_GAS;
+use serde_json::Value;
+use test_support::{MockChain, TestHome, GAS_PRICE, TOKEN_CALL_GAS};
+
+const PAY: &str = env!("CARGO_BIN_EXE_pay");
+const RECIPIENT: Address = address!("52908400098527886E0F7030069857D2E4169EE7");
+const ONE_ETH: u64 = 1_000_000_000_000_000_000;
+
+/// Run pay to `RECIPIENT`, returning its exit code and JSON output (null on failure)
+fn pay(home: &TestHome, args: &[&str]) -> (Option<i32>, Value) {
+    pay_to(home, &RECIPIENT.to_string(), args)
+}
+
+fn pay_to(home: &TestHome, to: &str, args: &[&str]) -> (Option<i32>, Value) {
+    let output = home
+        .command(PAY)
+        .args(["--to", to, "--json"])
+        .args(args)
+        .output()
+        .expect("run pay");
+    let json = serde_json::from_slice(&output.stdout).unwrap_or(Value::Null);
+    (output.status.code(), json)
+}
+
+#[test]
+fn test_pay_token() {
+    let chain = MockChain::start();
+    let token = chain.deploy_token(6);
+    let home = TestHome::new(&chain, Some((token, 6)));
+    chain.fund(home.address(), U256::from(ONE_ETH));
+    chain.mint(token, home.address(), U256::from(5_000_000));
+
+    let (code, payment) = pay(&home, &["--amount", "1.5"]);
+    assert_eq!(code, Some(0));
+    assert_eq!(payment["status"], "confirmed");
+    assert_eq!(payment["raw_amount"], "1500000");
+    assert_eq!(payment["token_symbol"], "TEST");
+    assert_eq!(payment["block_number"], 1);
+
+    assert_eq!(chain.token_balance(token, RECIPIENT), U256::from(1_500_000));
+    assert_eq!(
+        chain.token_balance(token, home.address()),
+        U256::from(3_500_000)
+    );
+    assert_eq!(chain.nonce(home.address()), 1);
+    let gas = U256::from(TOKEN_CALL_GAS as u128 * GAS_PRICE);
+    assert_eq!(chain.balance(home.address()), U256::from(ONE_ETH) - gas);
+}
+
+#[test]
+fn test_pay_unregistered_token_reads_decimals() {
+    let chain = MockChain::start();
+    let token = chain.deploy_token(18);
+    let home = TestHome::new(&chain, None);
+    chain.fund(home.address(), U256::from(ONE_ETH));
+    chain.mint(token, home.address(), U256::from(5 * ONE_ETH));
+
+    let (code, payment) = pay(&home, &["--token", &token.to_string(), "--amount", "1"]);
+    assert_eq!(code, Some(0));
+    assert_eq!(payment["raw_amount"], ONE_ETH.to_string());
+    assert_eq!(chain.token_balance(token, RECIPIENT), U256::from(ONE_ETH));
+}
+
+#[test]
+fn test_pay_native_uses_next_nonce() {
+    let chain = MockChain::start();
+    let home = TestHome::new(&chain, None);
+    chain.fund(home.address(), U256::from(ONE_ETH));
+
+    let mut sent = U256::ZERO;
+    for nonce in 1..=2 {
+        let (code, payment) = pay(&home, &["--amount", "0.01"]);
+        assert_eq!(code, Some(0));
+        assert_eq!(payment["status"], "confirmed");
+        assert_eq!(chain.nonce(home.address()), nonce);
+        sent += payment["raw_amount"]
+            .as_str()
+            .unwrap()
+            .parse::<U256>()
+            .unwrap();
+    }
+
+    assert_eq!(chain.balance(RECIPIENT), sent);
+    let gas = U256::from(2 * NATIVE_TRANSFER_GAS as u128 * GAS_PRICE);
+    assert_eq!(
+        chain.balance(home.address()),
+        U256::from(ONE_ETH) - sent - gas
+    );
+}
+
+#[test]
+fn test_pay_native_amount_usd() {
+    let chain = MockChain::start();
+    let home = TestHome::new(&chain, None);
+    chain.fund(home.address(), U256::from(ONE_ETH));
+    // $3000.00 with 8 decimals
+    let feed = chain.deploy_price_feed("ETH", U256::from(300_000_000_000u64), 8);
+    home.add_price_feed("ETH", feed);
+
+    let (code, payment) = pay(&home, &["--amount-usd", "30"]);
+    assert_eq!(code, Some(0));
+    assert_eq!(payment["amount"], "0.01");
+    assert_eq!(payment["raw_amount"], "10000000000000000");
+    assert_eq!(payment["usd_value"], "30.00");
+    assert_eq!(chain.balance(RECIPIENT), U256::from(ONE_ETH / 100));
+}
+
+#[test]
+fn test_pay_insufficient_balance_sends_nothing() {
+    let chain = MockChain::start();
+    let token = chain.deploy_token(6);
+    let home = TestHome::new(&chain, Some((token, 6)));
+    chain.fund(home.address(), U256::from(ONE_ETH));
+    chain.mint(token, home.address(), U256::from(1_000_000));
+
+    let (code, _) = pay(&home, &["--amount", "2"]);
+    assert_eq!(code, Some(1));
+    assert!(!chain
+        .methods()
+        .iter()
+        .any(|m| m == "eth_sendRawTransaction"));
+    assert_eq!(chain.nonce(home.address()), 0);
+    assert_eq!(chain.token_balance(token, RECIPIENT), U256::ZERO);
+}
+
+#[test]
+fn test_pay_contact_next_to_config() {
+    let chain = MockChain::start();
+    let home = TestHome::new(&chain, None);
+    chain.fund(home.address(), U256::from(ONE_ETH));
+
+    // A config outside the data directory brings its own address book
+    let dir = home.path().join("other");
+    std::fs::create_dir(&dir).unwrap();
+    std::fs::copy(home.path().join("config.toml"), dir.join("config.toml")).unwrap();
+    std::fs::write(
+        dir.join("contacts.toml"),
+        format!("[vendor]\naddress = \"{}\"\n", RECIPIENT),
+    )
+    .unwrap();
+    let config = dir.join("config.toml");
+
+    let (code, payment) = pay_to(
+        &home,
+        "@vendor",
+        &["--amount", "0.01", "--config", config.to_str().unwrap()],
+    );
+    assert_eq!(code, Some(0));
+    assert_eq!(payment["contact"], "vendor");
+    assert_eq!(chain.balance(RECIPIENT), U256::from(ONE_ETH / 100));
+}
+
+#[test]
+fn test_pay_address_with_broken_contacts() {
+    let chain = MockChain::start();
+    let home = TestHome::new(&chain, None);
+    chain.fund(home.address(), U256::from(ONE_ETH));
+    std::fs::write(home.path().join("contacts.toml"), "[vendor\n").unwrap();
+
+    // Paying a raw address only warns
+    let (code, payment) = pay(&home, &["--amount", "0.01"]);
+    assert_eq!(code, Some(0));
+    let warnings = payment["warnings"].as_array().unwrap();
+    assert!(warnings
+        .iter()
+        .any(|w| w.as_str().unwrap().contains("contacts.toml")));
+
+    // Paying a label needs the address book
+    let (code, _) = pay_to(&home, "@vendor", &["--amount", "0.01"]);
+    assert_eq!(code, Some(11));
+    assert_eq!(chain.nonce(home.address()), 1);
+}
@@ -0,0 +1,410 @@
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Signing agent for payment tools
+///
+/// Unlocks the wallet keystore once, keeps the key in memory and signs
+/// requests from `pay` over a Unix socket until it has been idle for the TTL.
+#[derive(Parser, Debug)]
+#[command(name = "payment-agent")]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Path to the agent socket (default: $PAYMENT_AGENT_SOCK or <data-dir>/agent.sock)
+    #[arg(long, short = 's', global = true)]
+    socket: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Unlock the wallet and serve signing requests (runs in the foreground)
+    Start {
+        /// Path to the wallet keystore file
+        #[arg(long, short = 'w')]
+        wallet: Option<PathBuf>,
+
+        /// Read wallet password from file
+        #[arg(long)]
+        password_file: Option<PathBuf>,
+
+        /// Read wallet password from the first line of stdin
+        #[arg(long, conflicts_with = "password_file")]
+        password_stdin: bool,
+
+        /// Path to configuration file
+        #[arg(long, short = 'c')]
+        config: Option<PathBuf>,
+
+        /// Named environment from config.toml (default: $PAYMENT_ENV, then active_env)
+        #[arg(long)]
+        env: Option<String>,
+
+        /// Lock and exit after this many seconds without requests
+        #[arg(long, default_value_t = 900)]
+        ttl: u64,
+
+        /// Delete the password file once the wallet is unlocked
+        #[arg(long, conflicts_with = "password_stdin")]
+        remove_password_file: bool,
+    },
+
+    /// Show whether an agent is running and which address it holds
+    Status,
+
+    /// Tell the running agent to forget its key and exit
+    Stop,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code() as u8)
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn run(_args: Args) -> payment_common::Result<()> {
+    Err(payment_common::Error::Agent(
+        "payment-agent requires Unix domain sockets and is not supported on this platform"
+            .to_string(),
+    ))
+}
+
+#[cfg(unix)]
+fn run(args: Args) -> payment_common::Result<()> {
+    let socket = args
+        .socket
+        .unwrap_or_else(payment_common::default_agent_socket_path);
+
+    match args.command {
+        Commands::Start {
+            wallet,
+            password_file,
+            password_stdin,
+            config,
+            env,
+            ttl,
+            remove_password_file,
+        } => server::cmd_start(server::StartOptions {
+            socket,
+            wallet,
+            password_file,
+            password_stdin,
+            config,
+            env,
+            ttl: std::time::Duration::from_secs(ttl),
+            remove_password_file,
+        }),
+        Commands::Status => cmd_status(&socket),
+        Commands::Stop => cmd_stop(&socket),
+    }
+}
+
+#[cfg(unix)]
+fn cmd_status(socket: &std::path::Path) -> payment_common::Result<()> {
+    let client = payment_common::agent::AgentClient::new(Some(socket));
+    let status = match client.address() {
+        Ok(address) => serde_json::json!({
+            "running": true,
+            "socket": socket.display().to_string(),
+            "address": address.to_string(),
+        }),
+        Err(_) => serde_json::json!({
+            "running": false,
+            "socket": socket.display().to_string(),
+        }),
+    };
+    println!("{}", serde_json::to_string_pretty(&status)?);
+    Ok(())
+}
+
+#[cfg(unix)]
+fn cmd_stop(socket: &std::path::Path) -> payment_common::Result<()> {
+    payment_common::agent::AgentClient::new(Some(socket)).lock()?;
+    eprintln!("Agent locked and stopped.");
+    Ok(())
+}
+
+#[cfg(unix)]
+mod server {
+    use alloy::signers::local::PrivateKeySigner;
+    use alloy::signers::SignerSync;
+    use payment_common::agent::{AgentClient, AgentRequest, AgentResponse};
+    use payment_common::{Config, Error, Result, Wallet};
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    pub struct StartOptions {
+        pub socket: PathBuf,
+        pub wallet: Option<PathBuf>,
+        pub password_file: Option<PathBuf>,
+        pub password_stdin: bool,
+        pub config: Option<PathBuf>,
+        pub env: Option<String>,
+        pub ttl: Duration,
+        pub remove_password_file: bool,
+    }
+
+    /// Key material and activity tracking shared between the listener and the idle watchdog
+    struct AgentState {
+        signer: Mutex<Option<PrivateKeySigner>>,
+        last_used: Mutex<Instant>,
+    }
+
+    impl AgentState {
+        fn is_locked(&self) -> bool {
+            self.signer.lock().unwrap().is_none()
+        }
+
+        fn lock(&self) {
+            // Dropping the signer zeroizes the secret key
+            self.signer.lock().unwrap().take();
+        }
+
+        fn touch(&self) {
+            *self.last_used.lock().unwrap() = Instant::now();
+        }
+    }
+
+    pub fn cmd_start(opts: StartOptions) -> Result<()> {
+        let config = Config::load_env(opts.config.as_deref(), opts.env.as_deref())?;
+        for warning in config.warnings() {
+            eprintln!("Warning: {}", warning);
+        }
+        let wallet_path = opts.wallet.unwrap_or_else(|| config.wallet_path());
+        if !wallet_path.exists() {
+            return Err(Error::WalletNotFound(wallet_path.display().to_string()));
+        }
+
+        // Refuse to replace a live agent, but clean up a stale socket file
+        if opts.socket.exists() {
+            if AgentClient::new(Some(&opts.socket)).address().is_ok() {
+                return Err(Error::Agent(format!(
+                    "An agent is already running at {}",
+                    opts.socket.display()
+                )));
+            }
+            fs::remove_file(&opts.socket)?;
+        }
+
+        // Password: stdin > --password-file > config's secret store or password file
+        let password_path = match &opts.password_file {
+            Some(path) => Some(path.clone()),
+            None if config.secret_store_path().is_none() => Some(config.password_path()),
+            None => None,
+        };
+        let password = if opts.password_stdin {
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line)?;
+            line.trim().to_string()
+        } else if let Some(path) = &opts.password_file {
+            Wallet::load_password(Some(path))?
+        } else {
+            config.wallet_password()?
+        };
+
+        eprintln!("Decrypting wallet...");
+        let private_key = eth_keystore::decrypt_key(&wallet_path, &password)
+            .map_err(|e| Error::Wallet(format!("Failed to decrypt wallet: {}", e)))?;
+        let signer = PrivateKeySigner::from_slice(&private_key)
+            .map_err(|e| Error::Wallet(format!("Invalid private key: {}", e)))?;
+        drop(private_key);
+        let address = signer.address();
+
+        if opts.remove_password_file {
+            match &password_path {
+                Some(path) => {
+                    fs::remove_file(path)?;
+                    eprintln!("Removed password file {}", path.display());
+                }
+                None => eprintln!("Password is in the secret store; nothing to remove"),
+            }
+        }
+
+        let listener = bind_private(&opts.socket)?;
+
+        let state = Arc::new(AgentState {
+            signer: Mutex::new(Some(signer)),
+            last_used: Mutex::new(Instant::now()),
+        });
+        spawn_idle_watchdog(Arc::clone(&state), opts.ttl, opts.socket.clone());
+
+        eprintln!(
+            "Agent unlocked {} on {} (idle timeout {}s)",
+            address,
+            opts.socket.display(),
+            opts.ttl.as_secs()
+        );
+
+        for stream in listener.incoming() {
+            if state.is_locked() {
+                break;
+            }
+            match stream {
+                Ok(stream) => {
+                    if let Err(e) = handle_connection(&state, stream) {
+                        eprintln!("Warning: agent connection failed: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("Warning: failed to accept connection: {}", e),
+            }
+            if state.is_locked() {
+                break;
+            }
+        }
+
+        let _ = fs::remove_file(&opts.socket);
+        eprintln!("Agent locked.");
+        Ok(())
+    }
+
+    /// Bind the socket inside a fresh owner-only directory and move it into place once the
+    /// socket itself is owner-only, so other users never get a window to connect
+    fn bind_private(socket: &Path) -> Result<UnixListener> {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+        let parent = socket
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        payment_common::ensure_private_dir(parent)?;
+
+        let staging = parent.join(format!(".agent-{}.tmp", std::process::id()));
+        fs::DirBuilder::new().mode(0o700).create(&staging)?;
+        let staged = staging.join("agent.sock");
+        let result = UnixListener::bind(&staged).and_then(|listener| {
+            fs::set_permissions(&staged, fs::Permissions::from_mode(0o600))?;
+            fs::rename(&staged, socket)?;
+            Ok(listener)
+        });
+        let _ = fs::remove_dir_all(&staging);
+        Ok(result?)
+    }
+
+    /// Lock the agent once it has been idle for `ttl`, then wake the accept loop so it exits
+    fn spawn_idle_watchdog(state: Arc<AgentState>, ttl: Duration, socket: PathBuf) {
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_secs(1));
+            if state.is_locked() {
+                return;
+            }
+            if state.last_used.lock().unwrap().elapsed() >= ttl {
+                state.lock();
+                let _ = UnixStream::connect(&socket);
+                return;
+            }
+        });
+    }
+
+    fn handle_connection(state: &AgentState, stream: UnixStream) -> Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+        let mut reader = BufReader::new(&stream);
+        let mut writer = &stream;
+
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 0 {
+            let response = match serde_json::from_str::<AgentRequest>(&line) {
+                Ok(request) => handle_request(state, request),
+                Err(e) => AgentResponse::error(format!("Invalid request: {}", e)),
+            };
+            let mut reply = serde_json::to_string(&response)?;
+            reply.push('\n');
+            writer.write_all(reply.as_bytes())?;
+            line.clear();
+
+            if state.is_locked() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(state: &AgentState, request: AgentRequest) -> AgentResponse {
+        let guard = state.signer.lock().unwrap();
+        let Some(signer) = guard.as_ref() else {
+            return AgentResponse::error("Agent is locked");
+        };
+
+        match request {
+            // Only signing counts as activity, so polling the status cannot keep the key unlocked
+            AgentRequest::Address => {
+                let address = signer.address();
+                drop(guard);
+                AgentResponse {
+                    ok: true,
+                    address: Some(address),
+                    ..Default::default()
+                }
+            }
+            AgentRequest::SignHash { hash } => {
+                let result = signer.sign_hash_sync(&hash);
+                drop(guard);
+                state.touch();
+                match result {
+                    Ok(signature) => AgentResponse {
+                        ok: true,
+                        signature: Some(signature),
+                        ..Default::default()
+                    },
+                    Err(e) => AgentResponse::error(format!("Signing failed: {}", e)),
+                }
+            }
+            AgentRequest::Lock => {
+                drop(guard);
+                state.lock();
+                AgentResponse {
+                    ok: true,
+                    ..Default::default()
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use alloy::primitives::B256;
+        use std::os::unix::fs::PermissionsExt;
+
+        #[test]
+        fn test_only_signing_refreshes_ttl() {
+            let idle = Duration::from_secs(60);
+            let state = AgentState {
+                signer: Mutex::new(Some(PrivateKeySigner::random())),
+                last_used: Mutex::new(Instant::now() - idle),
+            };
+
+            assert!(handle_request(&state, AgentRequest::Address).ok);
+            assert!(state.last_used.lock().unwrap().elapsed() >= idle);
+
+            assert!(handle_request(&state, AgentRequest::SignHash { hash: B256::ZERO }).ok);
+            assert!(state.last_used.lock().unwrap().elapsed() < idle);
+        }
+
+        #[test]
+        fn test_socket_is_owner_only() {
+            let dir = tempfile::tempdir().unwrap();
+            let socket = dir.path().join("agent.sock");
+            let _listener = bind_private(&socket).unwrap();
+
+            let mode = fs::metadata(&socket).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+            // The staging directory is gone and the socket still accepts connections
+            assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+            UnixStream::connect(&socket).unwrap();
+        }
+    }
+}
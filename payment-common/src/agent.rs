@@ -0,0 +1,232 @@
+use crate::error::{Error, Result};
+use alloy::consensus::SignableTransaction;
+use alloy::network::TxSigner;
+use alloy::primitives::{Address, ChainId, Signature, B256};
+use alloy::signers::Signer;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long a client waits for the agent before giving up
+const AGENT_IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Request sent to the payment agent (one JSON object per line)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum AgentRequest {
+    /// Return the address of the unlocked key
+    Address,
+    /// Sign a 32-byte prehash with the unlocked key
+    SignHash { hash: B256 },
+    /// Drop the key from memory and shut the agent down
+    Lock,
+}
+
+/// Response returned by the payment agent (one JSON object per line)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentResponse {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<Address>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<Signature>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AgentResponse {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            error: Some(message.into()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Client for a running `payment-agent` listening on a Unix socket
+#[derive(Debug, Clone)]
+pub struct AgentClient {
+    socket: PathBuf,
+}
+
+impl AgentClient {
+    /// Create a client for the agent socket at `socket` (or the default socket path)
+    pub fn new(socket: Option<&Path>) -> Self {
+        Self {
+            socket: socket
+                .map(PathBuf::from)
+                .unwrap_or_else(crate::default_agent_socket_path),
+        }
+    }
+
+    /// Path of the socket this client talks to
+    pub fn socket(&self) -> &Path {
+        &self.socket
+    }
+
+    /// Send a single request and wait for the response
+    pub fn request(&self, request: &AgentRequest) -> Result<AgentResponse> {
+        let stream = UnixStream::connect(&self.socket).map_err(|e| {
+            Error::Agent(format!(
+                "Cannot connect to agent at {}: {}",
+                self.socket.display(),
+                e
+            ))
+        })?;
+        stream.set_read_timeout(Some(AGENT_IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(AGENT_IO_TIMEOUT))?;
+
+        let mut line = serde_json::to_string(request)?;
+        line.push('\n');
+        (&stream).write_all(line.as_bytes())?;
+
+        let mut reader = BufReader::new(&stream);
+        let mut reply = String::new();
+        reader.read_line(&mut reply)?;
+        if reply.is_empty() {
+            return Err(Error::Agent("Agent closed the connection".to_string()));
+        }
+
+        let response: AgentResponse = serde_json::from_str(&reply)?;
+        if !response.ok {
+            return Err(Error::Agent(
+                response
+                    .error
+                    .unwrap_or_else(|| "Agent request failed".to_string()),
+            ));
+        }
+        Ok(response)
+    }
+
+    /// Get the address of the key held by the agent
+    pub fn address(&self) -> Result<Address> {
+        self.request(&AgentRequest::Address)?
+            .address
+            .ok_or_else(|| Error::Agent("Agent did not return an address".to_string()))
+    }
+
+    /// Ask the agent to sign a prehash
+    pub fn sign_hash(&self, hash: B256) -> Result<Signature> {
+        self.request(&AgentRequest::SignHash { hash })?
+            .signature
+            .ok_or_else(|| Error::Agent("Agent did not return a signature".to_string()))
+    }
+
+    /// Ask the agent to forget its key and exit
+    pub fn lock(&self) -> Result<()> {
+        self.request(&AgentRequest::Lock).map(|_| ())
+    }
+}
+
+/// Signer that delegates every signature to a running `payment-agent`
+#[derive(Debug, Clone)]
+pub struct AgentSigner {
+    client: AgentClient,
+    address: Address,
+    chain_id: Option<ChainId>,
+}
+
+impl AgentSigner {
+    /// Connect to the agent and fetch the address of its unlocked key
+    pub fn connect(socket: Option<&Path>) -> Result<Self> {
+        let client = AgentClient::new(socket);
+        let address = client.address()?;
+        Ok(Self {
+            client,
+            address,
+            chain_id: None,
+        })
+    }
+
+    /// Ask the agent for a signature on the blocking pool, since the socket IO is synchronous
+    async fn request_signature(&self, hash: B256) -> alloy::signers::Result<Signature> {
+        let client = self.client.clone();
+        tokio::task::spawn_blocking(move || client.sign_hash(hash))
+            .await
+            .map_err(alloy::signers::Error::other)?
+            .map_err(alloy::signers::Error::other)
+    }
+}
+
+#[async_trait::async_trait]
+impl Signer for AgentSigner {
+    async fn sign_hash(&self, hash: &B256) -> alloy::signers::Result<Signature> {
+        self.request_signature(*hash).await
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> Option<ChainId> {
+        self.chain_id
+    }
+
+    fn set_chain_id(&mut self, chain_id: Option<ChainId>) {
+        self.chain_id = chain_id;
+    }
+}
+
+#[async_trait::async_trait]
+impl TxSigner<Signature> for AgentSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &mut dyn SignableTransaction<Signature>,
+    ) -> alloy::signers::Result<Signature> {
+        if let Some(chain_id) = self.chain_id {
+            if !tx.set_chain_id_checked(chain_id) {
+                return Err(alloy::signers::Error::TransactionChainIdMismatch {
+                    signer: chain_id,
+                    tx: tx.chain_id().unwrap_or_default(),
+                });
+            }
+        }
+        self.request_signature(tx.signature_hash()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_request_wire_format() {
+        let request = AgentRequest::SignHash { hash: B256::ZERO };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["op"], "sign_hash");
+        assert_eq!(json["hash"], format!("{}", B256::ZERO));
+
+        let json = serde_json::to_string(&AgentRequest::Address).unwrap();
+        assert_eq!(json, r#"{"op":"address"}"#);
+    }
+
+    #[test]
+    fn test_client_reports_agent_error() {
+        let dir = tempdir().unwrap();
+        let socket = dir.path().join("agent.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut line = String::new();
+            BufReader::new(&stream).read_line(&mut line).unwrap();
+            let mut reply =
+                serde_json::to_string(&AgentResponse::error("Agent is locked")).unwrap();
+            reply.push('\n');
+            (&stream).write_all(reply.as_bytes()).unwrap();
+        });
+
+        let result = AgentClient::new(Some(&socket)).address();
+        server.join().unwrap();
+        assert!(matches!(result, Err(Error::Agent(msg)) if msg == "Agent is locked"));
+    }
+}
@@ -0,0 +1,222 @@
+use crate::config::{Config, ResolvedToken};
+use crate::error::{Error, Result};
+use alloy::eips::BlockId;
+use alloy::primitives::{Address, U256};
+use alloy::providers::Provider;
+use alloy::rpc::types::{Filter, Log};
+use alloy::sol;
+use alloy::sol_types::SolEvent;
+use std::collections::BTreeSet;
+
+sol! {
+    #[sol(rpc)]
+    contract IERC20Approve {
+        event Approval(address indexed owner, address indexed spender, uint256 value);
+
+        function allowance(address owner, address spender) external view returns (uint256);
+        function approve(address spender, uint256 amount) external returns (bool);
+    }
+}
+
+/// Allowances at or above 2^255 are "infinite" approvals: tokens that decrement
+/// a max allowance on each spend never bring it below this
+pub fn is_unlimited(allowance: U256) -> bool {
+    allowance.bit(255)
+}
+
+/// Tokens whose allowances are checked: every token registered on the active
+/// network, the default token, and `extra` (e.g. an unregistered --token address).
+///
+/// Registry entries with an invalid address are skipped with a warning.
+pub fn tokens(
+    config: &Config,
+    extra: Option<&ResolvedToken>,
+    warnings: &mut Vec<String>,
+) -> Vec<ResolvedToken> {
+    let mut tokens = Vec::new();
+    if let Some(network) = &config.network.name {
+        for (symbol, entry) in config.token_registry(network) {
+            match entry.address.parse() {
+                Ok(address) => tokens.push(ResolvedToken {
+                    address,
+                    symbol: Some(symbol),
+                    decimals: entry.decimals,
+                }),
+                Err(_) => warnings.push(format!(
+                    "Skipping {} (invalid address {})",
+                    symbol, entry.address
+                )),
+            }
+        }
+    }
+    for token in extra.into_iter().cloned().chain(config.default_token()) {
+        if !tokens.iter().any(|t| t.address == token.address) {
+            tokens.push(token);
+        }
+    }
+    tokens
+}
+
+/// Blocks `allowances` scans back from the latest block unless told where to start
+/// (about 4.6 months on Ethereum mainnet, 23 days on Base)
+pub const DEFAULT_LOOKBACK_BLOCKS: u64 = 1_000_000;
+
+/// Most log queries one scan sends before giving up
+const MAX_LOG_REQUESTS: usize = 1_000;
+
+/// (token, spender) pairs that `owner` approved, from `Approval` logs of `tokens`
+/// between `from_block` and `to_block`.
+///
+/// Providers cap the block range (or result count) of a log query; a range rejected
+/// for that reason is split in half and retried until the pieces are accepted. Any
+/// other error, or more than `MAX_LOG_REQUESTS` queries, ends the scan.
+pub async fn approved_spenders<P: Provider>(
+    provider: &P,
+    owner: Address,
+    tokens: &[Address],
+    from_block: u64,
+    to_block: u64,
+) -> Result<BTreeSet<(Address, Address)>> {
+    let filter = Filter::new()
+        .address(tokens.to_vec())
+        .event_signature(IERC20Approve::Approval::SIGNATURE_HASH)
+        .topic1(owner.into_word());
+
+    let mut spenders = BTreeSet::new();
+    let mut ranges = vec![(from_block, to_block)];
+    let mut requests = 0;
+    while let Some((from, to)) = ranges.pop() {
+        requests += 1;
+        if requests > MAX_LOG_REQUESTS {
+            return Err(Error::Network(format!(
+                "Gave up reading Approval logs after {} queries; the RPC endpoint \
+                 accepts only small block ranges. Use a later --from-block",
+                MAX_LOG_REQUESTS
+            )));
+        }
+        let query = filter.clone().from_block(from).to_block(to);
+        match provider.get_logs(&query).await {
+            Ok(logs) => spenders.extend(logs.iter().filter_map(spender)),
+            Err(e)
+                if to > from
+                    && e.as_error_resp()
+                        .is_some_and(|resp| is_range_limit(&resp.message)) =>
+            {
+                let mid = from + (to - from) / 2;
+                ranges.push((mid + 1, to));
+                ranges.push((from, mid));
+            }
+            Err(e) => {
+                return Err(Error::Network(format!(
+                    "Failed to read Approval logs for blocks {}-{}: {}",
+                    from, to, e
+                )))
+            }
+        }
+    }
+    Ok(spenders)
+}
+
+/// Whether a log query error says the block range or result count was too large
+/// (rather than e.g. a rate limit, which splitting would only make worse)
+fn is_range_limit(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    if message.contains("rate limit") || message.contains("request limit") {
+        return false;
+    }
+    [
+        "range",
+        "more than",
+        "too many",
+        "response size",
+        "result limit",
+    ]
+    .iter()
+    .any(|phrase| message.contains(phrase))
+}
+
+/// Token and spender of an `Approval` log (ERC-721 approvals, with a third
+/// indexed topic, are skipped)
+fn spender(log: &Log) -> Option<(Address, Address)> {
+    match log.topics() {
+        [_, _, spender] => Some((log.address(), Address::from_word(*spender))),
+        _ => None,
+    }
+}
+
+/// Current `allowance(owner, spender)` on `token`
+pub async fn allowance<P: Provider>(
+    provider: &P,
+    token: Address,
+    owner: Address,
+    spender: Address,
+    block: BlockId,
+) -> Result<U256> {
+    IERC20Approve::new(token, provider)
+        .allowance(owner, spender)
+        .block(block)
+        .call()
+        .await
+        .map_err(|e| {
+            Error::Network(format!(
+                "Failed to read allowance of {} on {}: {}",
+                spender, token, e
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, Bytes, LogData};
+
+    #[test]
+    fn test_spender_from_approval_log() {
+        let token = address!("036CbD53842c5426634e7929541eC2318f3dCF7e");
+        let owner = address!("774701a3a131748d98ba0909a9f1bd42e78f8b04");
+        let spender_address = address!("000000000022D473030F116dDEE9F6B43aC78BA3");
+        let log = |topics| Log {
+            inner: alloy::primitives::Log {
+                address: token,
+                data: LogData::new_unchecked(topics, Bytes::new()),
+            },
+            ..Default::default()
+        };
+
+        let erc20 = log(vec![
+            IERC20Approve::Approval::SIGNATURE_HASH,
+            owner.into_word(),
+            spender_address.into_word(),
+        ]);
+        assert_eq!(spender(&erc20), Some((token, spender_address)));
+
+        let erc721 = log(vec![
+            IERC20Approve::Approval::SIGNATURE_HASH,
+            owner.into_word(),
+            spender_address.into_word(),
+            U256::from(7).into(),
+        ]);
+        assert_eq!(spender(&erc721), None);
+
+        assert!(is_unlimited(U256::MAX));
+        assert!(is_unlimited(U256::MAX - U256::from(1_000_000)));
+        assert!(!is_unlimited(U256::from(1_000_000)));
+    }
+
+    #[test]
+    fn test_is_range_limit() {
+        assert!(is_range_limit("query exceeds max block range 100000"));
+        assert!(is_range_limit("query returned more than 10000 results"));
+        assert!(is_range_limit(
+            "Log response size exceeded. You can make eth_getLogs requests with up to a 2K block range"
+        ));
+        assert!(is_range_limit("eth_getLogs is limited to a 10,000 range"));
+        assert!(!is_range_limit("daily request limit exceeded"));
+        assert!(!is_range_limit(
+            "Your app has exceeded its compute units per second capacity (rate limit)"
+        ));
+        assert!(!is_range_limit(
+            "the method eth_getLogs does not exist/is not available"
+        ));
+    }
+}
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Result of a single diagnostic check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// A named diagnostic check with a human-readable detail and an optional fix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl Check {
+    pub fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    pub fn warn(name: &str, detail: impl Into<String>, fix: Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            fix,
+        }
+    }
+
+    pub fn fail(name: &str, detail: impl Into<String>, fix: Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            fix,
+        }
+    }
+}
+
+/// Whether every check in the list passed (warnings are allowed)
+pub fn all_passed(checks: &[Check]) -> bool {
+    checks.iter().all(|c| c.status != CheckStatus::Fail)
+}
+
+/// Check that a secret file is readable and writable by its owner only
+#[cfg(unix)]
+pub fn check_private_file(name: &str, path: &Path) -> Check {
+    use std::os::unix::fs::PermissionsExt;
+
+    match std::fs::metadata(path) {
+        Ok(meta) => {
+            let mode = meta.permissions().mode() & 0o777;
+            if mode & 0o077 == 0 {
+                Check::pass(name, format!("{} has mode {:o}", path.display(), mode))
+            } else {
+                Check::fail(
+                    name,
+                    format!(
+                        "{} has mode {:o} and is accessible by other users",
+                        path.display(),
+                        mode
+                    ),
+                    Some(format!("chmod 600 {}", path.display())),
+                )
+            }
+        }
+        Err(e) => Check::fail(name, format!("Cannot stat {}: {}", path.display(), e), None),
+    }
+}
+
+/// Check that a secret file is readable and writable by its owner only
+#[cfg(not(unix))]
+pub fn check_private_file(name: &str, path: &Path) -> Check {
+    Check::warn(
+        name,
+        format!(
+            "Permissions of {} are not checked on this platform",
+            path.display()
+        ),
+        None,
+    )
+}
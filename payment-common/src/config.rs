@@ -1,17 +1,405 @@
 use crate::error::{Error, Result};
-use crate::{default_config_path, default_data_dir, ensure_data_dir};
+use crate::migrations;
+use crate::secret_store::{machine_passphrase, SealedSecret};
+use crate::wallet::Wallet;
+use crate::{
+    default_config_path, default_contacts_path, default_data_dir, ensure_private_dir, write_private,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+/// Schema version written to new and migrated config files
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Gas token symbol of networks whose profile does not set `native_symbol`
+pub const DEFAULT_NATIVE_SYMBOL: &str = "ETH";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Schema version of the file this config was loaded from (0 for files predating it)
+    #[serde(default)]
+    pub config_version: u32,
+    /// Environment used when neither `--env` nor `PAYMENT_ENV` picks one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_env: Option<String>,
+    #[serde(default)]
+    pub wallet: WalletConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub payment: PaymentConfig,
+    #[serde(default)]
+    pub signer: SignerConfig,
+    /// User-defined network profiles (`[profiles.<name>]`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, UserNetworkProfile>,
+    /// User token registry (`[tokens.<network>.<symbol>]`), merged over the built-in tokens
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tokens: BTreeMap<String, BTreeMap<String, TokenEntry>>,
+    /// User price feeds (`[price_feeds.<network>.<symbol>]`), merged over the built-in feeds
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub price_feeds: BTreeMap<String, BTreeMap<String, PriceFeedEntry>>,
+    /// Named environments (`[envs.<name>]`), each a complete wallet/network/payment/signer setup
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub envs: BTreeMap<String, EnvConfig>,
+    /// Profiles read from `networks.toml` next to the config file (never written to config.toml)
+    #[serde(skip)]
+    pub networks_file_profiles: BTreeMap<String, UserNetworkProfile>,
+    /// Environment whose sections are loaded into `wallet`, `network`, `payment` and `signer`
+    #[serde(skip)]
+    selected_env: Option<String>,
+    /// Top-level sections set aside while an environment is selected
+    #[serde(skip)]
+    base_sections: Option<Box<EnvConfig>>,
+    /// Where each non-default value came from
+    #[serde(skip)]
+    origins: BTreeMap<String, ValueOrigin>,
+    /// config.toml this config was loaded from (whether or not it exists)
+    #[serde(skip)]
+    source_path: Option<PathBuf>,
+    /// Problems found while loading that did not stop it (unknown keys, ...)
+    #[serde(skip)]
+    warnings: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            config_version: CONFIG_VERSION,
+            active_env: None,
+            wallet: WalletConfig::default(),
+            network: NetworkConfig::default(),
+            payment: PaymentConfig::default(),
+            signer: SignerConfig::default(),
+            profiles: BTreeMap::new(),
+            tokens: BTreeMap::new(),
+            price_feeds: BTreeMap::new(),
+            envs: BTreeMap::new(),
+            networks_file_profiles: BTreeMap::new(),
+            selected_env: None,
+            base_sections: None,
+            origins: BTreeMap::new(),
+            source_path: None,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// The per-environment sections of the config
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnvConfig {
     #[serde(default)]
     pub wallet: WalletConfig,
     #[serde(default)]
     pub network: NetworkConfig,
     #[serde(default)]
     pub payment: PaymentConfig,
+    #[serde(default)]
+    pub signer: SignerConfig,
+}
+
+/// Where an effective config value came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueOrigin {
+    Default,
+    File,
+    Env,
+    Flag,
+}
+
+impl std::fmt::Display for ValueOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValueOrigin::Default => write!(f, "default"),
+            ValueOrigin::File => write!(f, "file"),
+            ValueOrigin::Env => write!(f, "env"),
+            ValueOrigin::Flag => write!(f, "flag"),
+        }
+    }
+}
+
+/// How a config value is written on the command line and validated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Text,
+    Path,
+    /// http:// or https:// URL
+    Url,
+    /// Comma-separated list of URLs
+    UrlList,
+    Integer,
+    /// Token decimals (0-255)
+    Decimals,
+    /// Hex address, stored EIP-55 checksummed
+    Address,
+    /// Decimal token amount (e.g., 10.50)
+    Amount,
+    /// Signer backend name
+    Backend,
+}
+
+impl ValueKind {
+    /// Validate `value` and convert it to what the config file stores
+    fn parse(self, key: &str, value: &str) -> Result<toml::Value> {
+        let invalid = |expected: &str| {
+            Error::Config(format!(
+                "Invalid value for {}: {} (expected {})",
+                key, value, expected
+            ))
+        };
+        Ok(match self {
+            ValueKind::Text | ValueKind::Path => toml::Value::String(value.to_string()),
+            ValueKind::Url => toml::Value::String(validate_url(value)?),
+            ValueKind::UrlList => unreachable!("handled by Config::set"),
+            ValueKind::Integer => toml::Value::Integer(
+                value
+                    .parse::<u64>()
+                    .ok()
+                    .and_then(|v| i64::try_from(v).ok())
+                    .ok_or_else(|| invalid("a non-negative integer"))?,
+            ),
+            ValueKind::Decimals => toml::Value::Integer(
+                value
+                    .parse::<u8>()
+                    .map_err(|_| invalid("an integer from 0 to 255"))?
+                    .into(),
+            ),
+            ValueKind::Address => toml::Value::String(checksum_address(value)?),
+            ValueKind::Amount => {
+                let (whole, fraction) = value.split_once('.').unwrap_or((value, "0"));
+                let digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+                if !digits(whole) || !digits(fraction) {
+                    return Err(invalid("a decimal amount like 10.50"));
+                }
+                toml::Value::String(value.to_string())
+            }
+            ValueKind::Backend => toml::Value::String(value.parse::<SignerBackend>()?.to_string()),
+        })
+    }
+}
+
+impl std::fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ValueKind::Text => "text",
+            ValueKind::Path => "path",
+            ValueKind::Url => "url",
+            ValueKind::UrlList => "url list",
+            ValueKind::Integer => "integer",
+            ValueKind::Decimals => "decimals",
+            ValueKind::Address => "address",
+            ValueKind::Amount => "amount",
+            ValueKind::Backend => "local|remote",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A settable config key
+#[derive(Debug, Clone, Copy)]
+pub struct KeySpec {
+    pub key: &'static str,
+    pub kind: ValueKind,
+    pub description: &'static str,
+}
+
+const fn spec(key: &'static str, kind: ValueKind, description: &'static str) -> KeySpec {
+    KeySpec {
+        key,
+        kind,
+        description,
+    }
+}
+
+/// Kind and help text of every key accepted by `get`, `set` and `unset`, in display order.
+///
+/// The keys themselves come from the section structs (see `Config::valid_keys`); a test
+/// checks that each has a spec here.
+pub const KEY_SPECS: &[KeySpec] = &[
+    spec(
+        "wallet.path",
+        ValueKind::Path,
+        "Path to wallet keystore file",
+    ),
+    spec(
+        "wallet.password_file",
+        ValueKind::Path,
+        "Path to password file",
+    ),
+    spec(
+        "wallet.secret_store",
+        ValueKind::Path,
+        "Path to the encrypted password store",
+    ),
+    spec(
+        "network.name",
+        ValueKind::Text,
+        "Network name (e.g., base-mainnet)",
+    ),
+    spec(
+        "network.chain_id",
+        ValueKind::Integer,
+        "Chain ID for transaction signing",
+    ),
+    spec(
+        "network.rpc_url",
+        ValueKind::Url,
+        "Blockchain RPC endpoint URL",
+    ),
+    spec(
+        "network.rpc_urls",
+        ValueKind::UrlList,
+        "Fallback RPC endpoints, tried in order",
+    ),
+    spec(
+        "network.rpc_timeout_secs",
+        ValueKind::Integer,
+        "RPC request timeout in seconds (default 30)",
+    ),
+    spec(
+        "payment.default_token",
+        ValueKind::Address,
+        "Default ERC-20 token contract address",
+    ),
+    spec(
+        "payment.default_token_symbol",
+        ValueKind::Text,
+        "Token symbol (e.g., USDC)",
+    ),
+    spec(
+        "payment.default_token_decimals",
+        ValueKind::Decimals,
+        "Token decimals (e.g., 6 for USDC)",
+    ),
+    spec(
+        "payment.max_auto_payment",
+        ValueKind::Amount,
+        "Maximum auto-payment amount",
+    ),
+    spec(
+        "payment.max_price_age_secs",
+        ValueKind::Integer,
+        "Oldest price feed answer accepted for USD values, in seconds, for feeds without \
+         their own max_age_secs (default 3900)",
+    ),
+    spec(
+        "payment.min_gas_runway",
+        ValueKind::Integer,
+        "Warn when the gas balance covers fewer payments than this (default 10)",
+    ),
+    spec(
+        "signer.backend",
+        ValueKind::Backend,
+        "Signing backend: local (default) or remote",
+    ),
+    spec("signer.url", ValueKind::Url, "Remote signing service URL"),
+    spec(
+        "signer.address",
+        ValueKind::Address,
+        "Address to sign with on the remote service",
+    ),
+    spec(
+        "signer.timeout_secs",
+        ValueKind::Integer,
+        "Remote signer request timeout in seconds",
+    ),
+];
+
+/// Look up the spec of a settable key
+pub fn key_spec(key: &str) -> Result<&'static KeySpec> {
+    KEY_SPECS
+        .iter()
+        .find(|spec| spec.key == key)
+        .ok_or_else(|| Error::Config(format!("Unknown config key: {}", key)))
+}
+
+/// Field names of a struct, as its derived `Deserialize` lists them.
+///
+/// Read from the deserializer side because serializing a default value leaves out
+/// `None` and `skip_serializing_if` fields.
+pub(crate) fn struct_fields<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    use serde::de::{self, Visitor};
+
+    struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> de::Deserializer<'de> for FieldNames<'_> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(
+            self,
+            _: V,
+        ) -> std::result::Result<V::Value, Self::Error> {
+            Err(de::Error::custom("only struct field names are read"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            visitor: V,
+        ) -> std::result::Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            self.deserialize_any(visitor)
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+            byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map
+            enum identifier ignored_any
+        }
+    }
+
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+/// Check that `url` is an http(s) URL
+fn validate_url(url: &str) -> Result<String> {
+    let parsed = alloy::transports::http::reqwest::Url::parse(url)
+        .map_err(|e| Error::Config(format!("Invalid URL: {} ({})", url, e)))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(Error::Config(format!(
+            "Invalid URL: {} (expected http:// or https://)",
+            url
+        )));
+    }
+    Ok(url.to_string())
+}
+
+/// Parse an address and return its EIP-55 checksummed form.
+///
+/// Mixed-case input must already carry a valid checksum; all-lowercase or all-uppercase
+/// input is accepted and checksummed.
+pub(crate) fn checksum_address(value: &str) -> Result<String> {
+    use alloy::primitives::Address;
+
+    let hex = value.strip_prefix("0x").unwrap_or(value);
+    let mixed_case =
+        hex.chars().any(|c| c.is_ascii_lowercase()) && hex.chars().any(|c| c.is_ascii_uppercase());
+    let address = if mixed_case {
+        Address::parse_checksummed(value, None)
+            .map_err(|_| Error::Config(format!("Invalid address checksum: {}", value)))?
+    } else {
+        value
+            .parse::<Address>()
+            .map_err(|_| Error::Config(format!("Invalid address: {}", value)))?
+    };
+    Ok(address.to_checksum(None))
+}
+
+/// Environment variable that overrides `key` (e.g., `network.rpc_url` -> `PAYMENT_NETWORK_RPC_URL`)
+pub fn env_var_for_key(key: &str) -> String {
+    format!("PAYMENT_{}", key.replace('.', "_").to_uppercase())
+}
+
+/// Environment lookup backed by the process environment
+fn process_env(var: &str) -> Option<String> {
+    std::env::var(var).ok()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +408,9 @@ pub struct WalletConfig {
     pub path: String,
     #[serde(default = "default_password_path_string")]
     pub password_file: String,
+    /// Argon2-sealed password file that replaces `password_file` when set
+    #[serde(default)]
+    pub secret_store: Option<String>,
 }
 
 fn default_wallet_path_string() -> String {
@@ -35,6 +426,7 @@ impl Default for WalletConfig {
         Self {
             path: default_wallet_path_string(),
             password_file: default_password_path_string(),
+            secret_store: None,
         }
     }
 }
@@ -47,6 +439,50 @@ pub struct NetworkConfig {
     pub chain_id: Option<u64>,
     #[serde(default)]
     pub rpc_url: Option<String>,
+    /// Fallback RPC endpoints, tried in order after `rpc_url`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rpc_urls: Vec<RpcEndpoint>,
+    /// Request timeout for endpoints without their own `timeout_secs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpc_timeout_secs: Option<u64>,
+}
+
+/// An RPC endpoint, written either as a plain URL string or as `{ url, timeout_secs }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "RpcEndpointToml")]
+pub struct RpcEndpoint {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl RpcEndpoint {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            timeout_secs: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RpcEndpointToml {
+    Url(String),
+    Full {
+        url: String,
+        #[serde(default)]
+        timeout_secs: Option<u64>,
+    },
+}
+
+impl From<RpcEndpointToml> for RpcEndpoint {
+    fn from(value: RpcEndpointToml) -> Self {
+        match value {
+            RpcEndpointToml::Url(url) => RpcEndpoint::new(url),
+            RpcEndpointToml::Full { url, timeout_secs } => RpcEndpoint { url, timeout_secs },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -59,6 +495,74 @@ pub struct PaymentConfig {
     pub default_token_decimals: Option<u8>,
     #[serde(default)]
     pub max_auto_payment: Option<String>,
+    #[serde(default)]
+    pub max_price_age_secs: Option<u64>,
+    #[serde(default)]
+    pub min_gas_runway: Option<u64>,
+}
+
+/// Which backend signs transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SignerBackend {
+    /// Local keystore file (or a running payment-agent holding it)
+    #[default]
+    Local,
+    /// Remote signing service speaking the Web3Signer eth1 JSON-RPC API
+    Remote,
+}
+
+impl std::fmt::Display for SignerBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignerBackend::Local => write!(f, "local"),
+            SignerBackend::Remote => write!(f, "remote"),
+        }
+    }
+}
+
+impl std::str::FromStr for SignerBackend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "local" => Ok(SignerBackend::Local),
+            "remote" => Ok(SignerBackend::Remote),
+            _ => Err(Error::Config(format!(
+                "Invalid signer backend: {} (expected local or remote)",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignerConfig {
+    #[serde(default)]
+    pub backend: SignerBackend,
+    /// URL of the remote signing service
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Address to sign with on the remote service (first account if unset)
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default = "default_signer_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_signer_timeout_secs() -> u64 {
+    30
+}
+
+impl Default for SignerConfig {
+    fn default() -> Self {
+        Self {
+            backend: SignerBackend::default(),
+            url: None,
+            address: None,
+            timeout_secs: default_signer_timeout_secs(),
+        }
+    }
 }
 
 impl Config {
@@ -67,19 +571,263 @@ impl Config {
         Self::load_from(None)
     }
 
-    /// Load config from a specific path
+    /// Load config from a specific path, applying `PAYMENT_<SECTION>_<KEY>` environment overrides
     pub fn load_from(path: Option<&Path>) -> Result<Self> {
+        Self::load_env(path, None)
+    }
+
+    /// Like `load_from`, with the named environment selected instead of the default one
+    pub fn load_env(path: Option<&Path>, env: Option<&str>) -> Result<Self> {
+        Self::load_env_with(path, env, process_env)
+    }
+
+    /// Like `load_env`, reading `PAYMENT_ENV` and the overrides with `lookup`
+    /// instead of the process environment
+    pub fn load_env_with(
+        path: Option<&Path>,
+        env: Option<&str>,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self> {
+        let mut config = Self::read_file(path, Some(env), &lookup)?;
+        config.apply_env_overrides(lookup)?;
+        Ok(config)
+    }
+
+    /// Load config from the file only, without environment overrides.
+    ///
+    /// Use this when the config will be saved back, so overrides are not persisted.
+    pub fn load_file(path: Option<&Path>) -> Result<Self> {
+        Self::load_file_env(path, None)
+    }
+
+    /// Like `load_file`, with the named environment selected.
+    ///
+    /// Without `env`, the environment comes from `PAYMENT_ENV`, then `active_env`.
+    pub fn load_file_env(path: Option<&Path>, env: Option<&str>) -> Result<Self> {
+        Self::read_file(path, Some(env), &process_env)
+    }
+
+    /// Load config from the file only, keeping the top-level sections in effect.
+    ///
+    /// For commands that manage the environments themselves: no environment is
+    /// selected, and an unknown `active_env` is only a warning.
+    pub fn load_file_unselected(path: Option<&Path>) -> Result<Self> {
+        Self::read_file(path, None, &process_env)
+    }
+
+    /// Read config.toml, selecting `Some(env)` (see `load_file_env`) or, with `None`, none.
+    ///
+    /// `lookup` reads `PAYMENT_ENV` when the environment is not given.
+    fn read_file(
+        path: Option<&Path>,
+        env: Option<Option<&str>>,
+        lookup: &impl Fn(&str) -> Option<String>,
+    ) -> Result<Self> {
         let config_path = path.map(PathBuf::from).unwrap_or_else(default_config_path);
 
-        if !config_path.exists() {
-            return Ok(Config::default());
-        }
+        let mut config = if config_path.exists() {
+            let content = fs::read_to_string(&config_path)?;
+            let mut table: toml::Table = toml::from_str(&content)?;
+            // Older files are upgraded in memory; only payment-config writes them back
+            migrations::migrate(&mut table)?;
+            let unknown = migrations::unknown_keys(&table);
+            let mut config: Config = table.clone().try_into()?;
+            for key in unknown {
+                config.warnings.push(format!(
+                    "unknown config key {} in {}",
+                    key,
+                    config_path.display()
+                ));
+            }
+            match env {
+                Some(env) => config.select_env(env, lookup)?,
+                None => config.warn_unknown_active_env(&config_path),
+            }
+            // Values of the selected environment come from its [envs.<name>] table
+            let sections = match &config.selected_env {
+                Some(name) => table
+                    .get("envs")
+                    .and_then(|envs| envs.get(name))
+                    .and_then(toml::Value::as_table)
+                    .cloned()
+                    .unwrap_or_default(),
+                None => table,
+            };
+            for key in Self::valid_keys() {
+                let (section, field) = key.split_once('.').unwrap_or((key, ""));
+                if sections.get(section).and_then(|s| s.get(field)).is_some() {
+                    config.origins.insert(key.to_string(), ValueOrigin::File);
+                }
+            }
+            config
+        } else {
+            let mut config = Config::default();
+            if let Some(env) = env {
+                config.select_env(env, lookup)?;
+            }
+            config
+        };
 
-        let content = fs::read_to_string(&config_path)?;
-        let config: Config = toml::from_str(&content)?;
+        let networks_path = networks_path_for(&config_path);
+        if networks_path.exists() {
+            let content = fs::read_to_string(&networks_path)?;
+            config.networks_file_profiles = toml::from_str(&content)?;
+        }
+        config.source_path = Some(config_path);
         Ok(config)
     }
 
+    /// Swap the chosen environment's sections in as the effective config
+    fn select_env(
+        &mut self,
+        env: Option<&str>,
+        lookup: &impl Fn(&str) -> Option<String>,
+    ) -> Result<()> {
+        let name = match env {
+            Some(name) => Some(name.to_string()),
+            None => lookup(crate::PAYMENT_ENV_VAR)
+                .filter(|v| !v.is_empty())
+                .or_else(|| self.active_env.clone()),
+        };
+        let Some(name) = name else {
+            return Ok(());
+        };
+        let env = self.envs.get(&name).cloned().ok_or_else(|| {
+            Error::Config(format!(
+                "Unknown environment: {} (defined: {})",
+                name,
+                self.envs.keys().cloned().collect::<Vec<_>>().join(", ")
+            ))
+        })?;
+        let base = self.replace_sections(env);
+        self.base_sections = Some(Box::new(base));
+        self.selected_env = Some(name);
+        Ok(())
+    }
+
+    fn warn_unknown_active_env(&mut self, path: &Path) {
+        if let Some(name) = self
+            .active_env
+            .as_ref()
+            .filter(|n| !self.envs.contains_key(*n))
+        {
+            let warning = format!(
+                "active_env {} in {} is not defined under [envs]; \
+                 fix it with payment-config use-env <NAME> or use-env --none",
+                name,
+                path.display()
+            );
+            self.warnings.push(warning);
+        }
+    }
+
+    /// Problems found while loading the file that did not stop it, for the caller to report
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Name of the environment in effect, if any
+    pub fn env_name(&self) -> Option<&str> {
+        self.selected_env.as_deref()
+    }
+
+    /// The effective wallet, network, payment and signer sections
+    pub fn sections(&self) -> EnvConfig {
+        EnvConfig {
+            wallet: self.wallet.clone(),
+            network: self.network.clone(),
+            payment: self.payment.clone(),
+            signer: self.signer.clone(),
+        }
+    }
+
+    fn replace_sections(&mut self, env: EnvConfig) -> EnvConfig {
+        EnvConfig {
+            wallet: std::mem::replace(&mut self.wallet, env.wallet),
+            network: std::mem::replace(&mut self.network, env.network),
+            payment: std::mem::replace(&mut self.payment, env.payment),
+            signer: std::mem::replace(&mut self.signer, env.signer),
+        }
+    }
+
+    /// The config as written to disk: a selected environment's values go back to its table
+    fn file_layout(&self) -> Config {
+        let mut out = self.clone();
+        if let (Some(name), Some(base)) = (&self.selected_env, &self.base_sections) {
+            let env = out.replace_sections((**base).clone());
+            out.envs.insert(name.clone(), env);
+        }
+        out
+    }
+
+    /// Add or replace an environment
+    pub fn add_env(&mut self, name: &str, env: EnvConfig) -> Result<()> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Error::Config(format!(
+                "Invalid environment name: {} (use letters, digits, '-' and '_')",
+                name
+            )));
+        }
+        if self.selected_env.as_deref() == Some(name) {
+            self.replace_sections(env);
+        } else {
+            self.envs.insert(name.to_string(), env);
+        }
+        Ok(())
+    }
+
+    /// Remove an environment; the one currently in effect cannot be removed
+    pub fn remove_env(&mut self, name: &str) -> Result<()> {
+        if self.selected_env.as_deref() == Some(name) {
+            return Err(Error::Config(format!(
+                "Environment {} is in use; select another one first (payment-config use-env)",
+                name
+            )));
+        }
+        if self.envs.remove(name).is_none() {
+            return Err(Error::Config(format!("Unknown environment: {}", name)));
+        }
+        if self.active_env.as_deref() == Some(name) {
+            self.active_env = None;
+        }
+        Ok(())
+    }
+
+    /// Apply `PAYMENT_<SECTION>_<KEY>` environment variables over the loaded values,
+    /// reading each variable with `lookup` (`load_from` passes the process environment)
+    pub fn apply_env_overrides(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<()> {
+        for key in Self::valid_keys() {
+            let var = env_var_for_key(key);
+            if let Some(value) = lookup(&var) {
+                self.set(key, &value).map_err(|e| match e {
+                    Error::Config(msg) => Error::Config(format!("{}: {}", var, msg)),
+                    e => e,
+                })?;
+                self.origins.insert(key.to_string(), ValueOrigin::Env);
+            }
+        }
+        Ok(())
+    }
+
+    /// Override a value from a command-line flag
+    pub fn set_from_flag(&mut self, key: &str, value: &str) -> Result<()> {
+        self.set(key, value)?;
+        self.origins.insert(key.to_string(), ValueOrigin::Flag);
+        Ok(())
+    }
+
+    /// Where the effective value of `key` came from
+    pub fn origin(&self, key: &str) -> ValueOrigin {
+        self.origins
+            .get(key)
+            .copied()
+            .unwrap_or(ValueOrigin::Default)
+    }
+
     /// Save config to the default path
     pub fn save(&self) -> Result<()> {
         self.save_to(None)
@@ -87,20 +835,13 @@ impl Config {
 
     /// Save config to a specific path
     pub fn save_to(&self, path: Option<&Path>) -> Result<()> {
-        ensure_data_dir()?;
-
         let config_path = path.map(PathBuf::from).unwrap_or_else(default_config_path);
-
-        let content = toml::to_string_pretty(self)?;
-        fs::write(&config_path, content)?;
-
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            fs::set_permissions(&config_path, fs::Permissions::from_mode(0o600))?;
+        if let Some(dir) = config_path.parent() {
+            ensure_private_dir(dir)?;
         }
 
-        Ok(())
+        let content = toml::to_string_pretty(&self.file_layout())?;
+        write_private(&config_path, content)
     }
 
     /// Get the wallet path from config, resolving relative paths against the data directory
@@ -112,6 +853,67 @@ impl Config {
     pub fn password_path(&self) -> PathBuf {
         resolve_path(&self.wallet.password_file)
     }
+
+    /// Address book next to the config file (in the data directory for a config not
+    /// loaded from a file)
+    pub fn contacts_path(&self) -> PathBuf {
+        match &self.source_path {
+            Some(path) => path.with_file_name("contacts.toml"),
+            None => default_contacts_path(),
+        }
+    }
+
+    /// Get the secret store path if one is configured, resolving relative paths against the data directory
+    pub fn secret_store_path(&self) -> Option<PathBuf> {
+        self.wallet.secret_store.as_deref().map(resolve_path)
+    }
+
+    /// RPC endpoints in failover order: `rpc_url` first (unless it is also listed in
+    /// `rpc_urls`, where its position wins), then `rpc_urls`
+    pub fn rpc_endpoints(&self) -> Vec<RpcEndpoint> {
+        let mut endpoints = Vec::new();
+        if let Some(url) = &self.network.rpc_url {
+            if !self.network.rpc_urls.iter().any(|e| &e.url == url) {
+                endpoints.push(RpcEndpoint::new(url.clone()));
+            }
+        }
+        endpoints.extend(self.network.rpc_urls.iter().cloned());
+        endpoints
+    }
+
+    /// Load the wallet password through the secret store if configured, otherwise from the password file
+    pub fn wallet_password(&self) -> Result<String> {
+        match self.secret_store_path() {
+            Some(path) => SealedSecret::load(&path)?.open(&machine_passphrase()?),
+            None => Wallet::load_password(Some(&self.password_path())),
+        }
+    }
+}
+
+/// Upgrade the config file at `path` (default: config.toml) to `CONFIG_VERSION` in place,
+/// keeping the original next to it as `<file>.v<old>.bak`.
+///
+/// Returns the backup and a description of every change, or `None` when the file is
+/// missing or already current.
+pub fn upgrade_file(path: Option<&Path>) -> Result<Option<(PathBuf, Vec<String>)>> {
+    let path = path.map(PathBuf::from).unwrap_or_else(default_config_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let original = fs::read_to_string(&path)?;
+    let mut table: toml::Table = toml::from_str(&original)?;
+    let old_version = migrations::table_version(&table)?;
+    let notes = migrations::migrate(&mut table)?;
+    if notes.is_empty() {
+        return Ok(None);
+    }
+
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{}.bak", old_version));
+    let backup = PathBuf::from(backup);
+    write_private(&backup, &original)?;
+    write_private(&path, toml::to_string_pretty(&table)?)?;
+    Ok(Some((backup, notes)))
 }
 
 /// Resolve a path: absolute paths are used as-is, relative paths are resolved against the data directory
@@ -172,20 +974,19 @@ pub const NETWORK_PROFILES: &[NetworkProfile] = &[
 impl Config {
     /// Apply a network profile to the config
     pub fn apply_network_profile(&mut self, profile_name: &str) -> Result<()> {
-        let profile = NETWORK_PROFILES
-            .iter()
-            .find(|p| p.name == profile_name)
+        let ResolvedNetworkProfile { name, profile, .. } = self
+            .find_network_profile(profile_name)
             .ok_or_else(|| Error::Config(format!("Unknown network profile: {}", profile_name)))?;
 
-        self.network.name = Some(profile.name.to_string());
+        self.network.name = Some(name);
         self.network.chain_id = Some(profile.chain_id);
-        self.network.rpc_url = Some(profile.rpc_url.to_string());
+        self.network.rpc_url = Some(profile.rpc_url);
 
         if let Some(token) = profile.default_token {
-            self.payment.default_token = Some(token.to_string());
+            self.payment.default_token = Some(token);
         }
         if let Some(symbol) = profile.default_token_symbol {
-            self.payment.default_token_symbol = Some(symbol.to_string());
+            self.payment.default_token_symbol = Some(symbol);
         }
         if let Some(decimals) = profile.default_token_decimals {
             self.payment.default_token_decimals = Some(decimals);
@@ -194,73 +995,446 @@ impl Config {
         Ok(())
     }
 
-    /// Get a config value by key path (e.g., "network.rpc_url")
-    pub fn get(&self, key: &str) -> Option<String> {
-        match key {
-            "wallet.path" => Some(self.wallet.path.clone()),
-            "wallet.password_file" => Some(self.wallet.password_file.clone()),
-            "network.name" => self.network.name.clone(),
-            "network.chain_id" => self.network.chain_id.map(|v| v.to_string()),
-            "network.rpc_url" => self.network.rpc_url.clone(),
-            "payment.default_token" => self.payment.default_token.clone(),
-            "payment.default_token_symbol" => self.payment.default_token_symbol.clone(),
-            "payment.default_token_decimals" => {
-                self.payment.default_token_decimals.map(|v| v.to_string())
+    /// Config for talking to the network profile `name`.
+    ///
+    /// The active network keeps its configured endpoints and default token; any other
+    /// network uses its profile's RPC URL and default token only.
+    pub fn for_network(&self, name: &str) -> Result<Config> {
+        let mut config = self.clone();
+        if self.network.name.as_deref() != Some(name) {
+            config.network.rpc_urls.clear();
+            config.payment.default_token = None;
+            config.payment.default_token_symbol = None;
+            config.payment.default_token_decimals = None;
+            config.apply_network_profile(name)?;
+        }
+        Ok(config)
+    }
+
+    /// All network profiles: built-ins first (with user overrides applied), then user-defined ones.
+    ///
+    /// Precedence for a name defined in several places: config.toml > networks.toml > built-in.
+    pub fn network_profiles(&self) -> Vec<ResolvedNetworkProfile> {
+        let mut profiles: Vec<ResolvedNetworkProfile> = NETWORK_PROFILES
+            .iter()
+            .map(|p| ResolvedNetworkProfile {
+                name: p.name.to_string(),
+                source: ProfileSource::BuiltIn,
+                profile: UserNetworkProfile::from(p),
+            })
+            .collect();
+
+        let user_profiles = self
+            .networks_file_profiles
+            .iter()
+            .map(|(name, p)| (name, p, ProfileSource::NetworksFile))
+            .chain(
+                self.profiles
+                    .iter()
+                    .map(|(name, p)| (name, p, ProfileSource::Config)),
+            );
+        for (name, profile, source) in user_profiles {
+            let resolved = ResolvedNetworkProfile {
+                name: name.clone(),
+                source,
+                profile: profile.clone(),
+            };
+            match profiles.iter_mut().find(|p| &p.name == name) {
+                Some(existing) => *existing = resolved,
+                None => profiles.push(resolved),
             }
-            "payment.max_auto_payment" => self.payment.max_auto_payment.clone(),
-            _ => None,
         }
+
+        profiles
     }
 
-    /// Set a config value by key path (e.g., "network.rpc_url")
-    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
-        match key {
-            "wallet.path" => self.wallet.path = value.to_string(),
-            "wallet.password_file" => self.wallet.password_file = value.to_string(),
-            "network.name" => self.network.name = Some(value.to_string()),
-            "network.chain_id" => {
-                let chain_id = value
-                    .parse::<u64>()
-                    .map_err(|_| Error::Config(format!("Invalid chain_id: {}", value)))?;
-                self.network.chain_id = Some(chain_id);
+    /// Symbol of the active network's gas token, from its profile (default ETH)
+    pub fn native_symbol(&self) -> String {
+        self.network
+            .name
+            .as_deref()
+            .and_then(|name| self.find_network_profile(name))
+            .and_then(|p| p.profile.native_symbol)
+            .unwrap_or_else(|| DEFAULT_NATIVE_SYMBOL.to_string())
+    }
+
+    /// Look up a network profile by name across all sources
+    pub fn find_network_profile(&self, name: &str) -> Option<ResolvedNetworkProfile> {
+        self.network_profiles().into_iter().find(|p| p.name == name)
+    }
+
+    /// Add or replace a user-defined profile in config.toml
+    pub fn add_network_profile(&mut self, name: &str, profile: UserNetworkProfile) -> Result<()> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Error::Config(format!(
+                "Invalid profile name: {} (use letters, digits, '-' and '_')",
+                name
+            )));
+        }
+        validate_url(&profile.rpc_url)?;
+        if let Some(token) = &profile.default_token {
+            token
+                .parse::<alloy::primitives::Address>()
+                .map_err(|_| Error::Config(format!("Invalid token address: {}", token)))?;
+        }
+
+        self.profiles.insert(name.to_string(), profile);
+        Ok(())
+    }
+
+    /// Remove a user-defined profile, returning where it was defined.
+    ///
+    /// The caller saves config.toml or networks.toml depending on the source.
+    pub fn remove_network_profile(&mut self, name: &str) -> Result<ProfileSource> {
+        if self.profiles.remove(name).is_some() {
+            return Ok(ProfileSource::Config);
+        }
+        if self.networks_file_profiles.remove(name).is_some() {
+            return Ok(ProfileSource::NetworksFile);
+        }
+        if NETWORK_PROFILES.iter().any(|p| p.name == name) {
+            return Err(Error::Config(format!(
+                "{} is a built-in profile and cannot be removed",
+                name
+            )));
+        }
+        Err(Error::Config(format!("Unknown network profile: {}", name)))
+    }
+
+    /// Tokens registered for `network`, keyed by symbol.
+    ///
+    /// Seeded from the network profile's default token and the built-in tokens;
+    /// `[tokens.<network>.<symbol>]` entries in config.toml take precedence.
+    pub fn token_registry(&self, network: &str) -> BTreeMap<String, TokenEntry> {
+        let mut registry = BTreeMap::new();
+
+        if let Some(profile) = self.find_network_profile(network) {
+            let profile = profile.profile;
+            if let (Some(address), Some(symbol), Some(decimals)) = (
+                profile.default_token,
+                profile.default_token_symbol,
+                profile.default_token_decimals,
+            ) {
+                registry.insert(symbol, TokenEntry { address, decimals });
             }
-            "network.rpc_url" => self.network.rpc_url = Some(value.to_string()),
-            "payment.default_token" => self.payment.default_token = Some(value.to_string()),
-            "payment.default_token_symbol" => {
-                self.payment.default_token_symbol = Some(value.to_string())
+        }
+        for token in BUILTIN_TOKENS.iter().filter(|t| t.network == network) {
+            registry.insert(
+                token.symbol.to_string(),
+                TokenEntry {
+                    address: token.address.to_string(),
+                    decimals: token.decimals,
+                },
+            );
+        }
+        if let Some(tokens) = self.tokens.get(network) {
+            registry.extend(tokens.clone());
+        }
+
+        registry
+    }
+
+    /// Chainlink USD price feeds for `network`, keyed by token symbol (`ETH` for the gas token)
+    pub fn price_feeds(&self, network: &str) -> BTreeMap<String, PriceFeedEntry> {
+        let mut feeds: BTreeMap<String, PriceFeedEntry> = BUILTIN_PRICE_FEEDS
+            .iter()
+            .filter(|f| f.network == network)
+            .map(|f| {
+                (
+                    f.symbol.to_string(),
+                    PriceFeedEntry {
+                        address: f.address.to_string(),
+                        max_age_secs: Some(f.max_age_secs),
+                    },
+                )
+            })
+            .collect();
+        if let Some(user) = self.price_feeds.get(network) {
+            feeds.extend(user.clone());
+        }
+        feeds
+    }
+
+    /// Resolve a token given by symbol (looked up in the active network's registry)
+    /// or by contract address.
+    ///
+    /// An address must be registered, or be the default token with its decimals
+    /// configured: amounts are never converted with guessed decimals. Use
+    /// [`crate::payments::resolve_token`] to read an unregistered token's decimals
+    /// from its contract instead.
+    pub fn resolve_token(&self, token: &str) -> Result<ResolvedToken> {
+        use alloy::primitives::Address;
+
+        let network = self.network.name.as_deref();
+        let registry = network.map(|n| self.token_registry(n)).unwrap_or_default();
+        let entry_address = |entry: &TokenEntry| {
+            entry
+                .address
+                .parse::<Address>()
+                .map_err(|_| Error::Config(format!("Invalid token address: {}", entry.address)))
+        };
+
+        if token.starts_with("0x") {
+            let address: Address = token
+                .parse()
+                .map_err(|_| Error::InvalidArgument(format!("Invalid token address: {}", token)))?;
+            if let Some(token) = self.registered_token(address)? {
+                return Ok(token);
             }
-            "payment.default_token_decimals" => {
-                let decimals = value
-                    .parse::<u8>()
-                    .map_err(|_| Error::Config(format!("Invalid decimals: {}", value)))?;
-                self.payment.default_token_decimals = Some(decimals);
+            return Err(Error::InvalidArgument(format!(
+                "Token {} is not registered on {}, so its decimals are unknown; register it \
+                 with payment-config add-token <SYMBOL> --address {} --decimals <N> \
+                 (a [tokens.{}.<SYMBOL>] entry)",
+                token,
+                network.unwrap_or("the active network"),
+                token,
+                network.unwrap_or("<network>")
+            )));
+        }
+
+        let network = network.ok_or_else(|| {
+            Error::MissingConfig(format!("network.name (needed to look up token {})", token))
+        })?;
+        let (symbol, entry) = registry
+            .iter()
+            .find(|(symbol, _)| symbol.eq_ignore_ascii_case(token))
+            .ok_or_else(|| {
+                let known: Vec<_> = registry.keys().map(String::as_str).collect();
+                Error::InvalidArgument(format!(
+                    "Token {} is not registered on {} (known: {})",
+                    token,
+                    network,
+                    if known.is_empty() {
+                        "none".to_string()
+                    } else {
+                        known.join(", ")
+                    }
+                ))
+            })?;
+        Ok(ResolvedToken {
+            address: entry_address(entry)?,
+            symbol: Some(symbol.clone()),
+            decimals: entry.decimals,
+        })
+    }
+
+    /// The token at `address` if the active network registers it, or it is the
+    /// default token with its decimals configured
+    pub fn registered_token(
+        &self,
+        address: alloy::primitives::Address,
+    ) -> Result<Option<ResolvedToken>> {
+        if let Some(network) = &self.network.name {
+            for (symbol, entry) in self.token_registry(network) {
+                let entry_address = entry
+                    .address
+                    .parse::<alloy::primitives::Address>()
+                    .map_err(|_| {
+                        Error::Config(format!("Invalid token address: {}", entry.address))
+                    })?;
+                if entry_address == address {
+                    return Ok(Some(ResolvedToken {
+                        address,
+                        symbol: Some(symbol),
+                        decimals: entry.decimals,
+                    }));
+                }
             }
-            "payment.max_auto_payment" => self.payment.max_auto_payment = Some(value.to_string()),
-            _ => return Err(Error::Config(format!("Unknown config key: {}", key))),
         }
+        Ok(self
+            .default_token()
+            .filter(|t| t.address == address && self.payment.default_token_decimals.is_some()))
+    }
+
+    /// The configured default payment token, if it is set to a valid address
+    pub fn default_token(&self) -> Option<ResolvedToken> {
+        let address = self.payment.default_token.as_ref()?.parse().ok()?;
+        Some(ResolvedToken {
+            address,
+            symbol: self.payment.default_token_symbol.clone(),
+            decimals: self.payment.default_token_decimals.unwrap_or(6),
+        })
+    }
+
+    /// Add or replace a token in the registry for `network`
+    pub fn add_token(&mut self, network: &str, symbol: &str, entry: TokenEntry) -> Result<()> {
+        if symbol.is_empty()
+            || !symbol
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '.')
+        {
+            return Err(Error::Config(format!("Invalid token symbol: {}", symbol)));
+        }
+        entry
+            .address
+            .parse::<alloy::primitives::Address>()
+            .map_err(|_| Error::Config(format!("Invalid token address: {}", entry.address)))?;
+
+        self.tokens
+            .entry(network.to_string())
+            .or_default()
+            .insert(symbol.to_string(), entry);
+        Ok(())
+    }
+
+    /// Remove a token added to config.toml
+    pub fn remove_token(&mut self, network: &str, symbol: &str) -> Result<()> {
+        let tokens = self.tokens.get_mut(network);
+        let removed = tokens.and_then(|t| {
+            let key = t.keys().find(|k| k.eq_ignore_ascii_case(symbol))?.clone();
+            t.remove(&key)
+        });
+        if removed.is_none() {
+            return Err(Error::Config(format!(
+                "Token {} is not defined in config.toml for {} (built-in tokens cannot be removed)",
+                symbol, network
+            )));
+        }
+        if self.tokens.get(network).is_some_and(BTreeMap::is_empty) {
+            self.tokens.remove(network);
+        }
+        Ok(())
+    }
+
+    /// Write the networks.toml profiles to `path`
+    pub fn save_networks_file(&self, path: &Path) -> Result<()> {
+        fs::write(path, toml::to_string_pretty(&self.networks_file_profiles)?)?;
+        Ok(())
+    }
+
+    /// Get a config value by key path (e.g., "network.rpc_url").
+    ///
+    /// Any path into the serialized config works, including `profiles.<name>.rpc_url`
+    /// and `tokens.<network>.<symbol>.address`. Lists are joined with commas.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let mut value = toml::Value::try_from(self).ok()?;
+        for segment in key.split('.') {
+            value = value.as_table_mut()?.remove(segment)?;
+        }
+        match value {
+            toml::Value::String(s) => Some(s),
+            toml::Value::Array(items) => Some(
+                items
+                    .iter()
+                    .map(|item| match item {
+                        toml::Value::String(s) => s.clone(),
+                        // RPC endpoints with their own timeout
+                        toml::Value::Table(t) => t
+                            .get("url")
+                            .and_then(toml::Value::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                        other => other.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            toml::Value::Table(_) => None,
+            other => Some(other.to_string()),
+        }
+    }
+
+    /// Set a config value by key path (e.g., "network.rpc_url"), validating it by the key's kind
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let spec = key_spec(key)?;
+        let value = match spec.kind {
+            ValueKind::UrlList => {
+                // Comma-separated URLs; keep the timeout of endpoints that stay in the list
+                let endpoints = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|url| !url.is_empty())
+                    .map(|url| {
+                        let url = validate_url(url)?;
+                        Ok(self
+                            .network
+                            .rpc_urls
+                            .iter()
+                            .find(|e| e.url == url)
+                            .cloned()
+                            .unwrap_or_else(|| RpcEndpoint::new(url)))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                toml::Value::try_from(endpoints)?
+            }
+            kind => kind.parse(key, value)?,
+        };
+        self.update_table(key, Some(value))
+    }
+
+    /// Clear a value so the default applies again
+    pub fn unset(&mut self, key: &str) -> Result<()> {
+        key_spec(key)?;
+        self.update_table(key, None)?;
+        self.origins.remove(key);
         Ok(())
     }
 
-    /// Get list of all valid config keys
-    pub fn valid_keys() -> &'static [&'static str] {
-        &[
-            "wallet.path",
-            "wallet.password_file",
-            "network.name",
-            "network.chain_id",
-            "network.rpc_url",
-            "payment.default_token",
-            "payment.default_token_symbol",
-            "payment.default_token_decimals",
-            "payment.max_auto_payment",
-        ]
+    /// Round-trip through the serialized form to set or remove `section.field`
+    fn update_table(&mut self, key: &str, value: Option<toml::Value>) -> Result<()> {
+        let (section, field) = key
+            .split_once('.')
+            .ok_or_else(|| Error::Config(format!("Unknown config key: {}", key)))?;
+        let mut table = toml::Table::try_from(&*self)?;
+        let section = table
+            .entry(section)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| Error::Config(format!("Unknown config key: {}", key)))?;
+        match value {
+            Some(value) => section.insert(field.to_string(), value),
+            None => section.remove(field),
+        };
+
+        let updated: Config = table.try_into()?;
+        *self = Config {
+            networks_file_profiles: std::mem::take(&mut self.networks_file_profiles),
+            selected_env: self.selected_env.take(),
+            base_sections: self.base_sections.take(),
+            origins: std::mem::take(&mut self.origins),
+            source_path: self.source_path.take(),
+            warnings: std::mem::take(&mut self.warnings),
+            ..updated
+        };
+        Ok(())
+    }
+
+    /// Get list of all valid config keys: every field of the wallet, network, payment
+    /// and signer sections
+    pub fn valid_keys() -> Vec<&'static str> {
+        static KEYS: OnceLock<Vec<String>> = OnceLock::new();
+        let keys = KEYS.get_or_init(|| {
+            let sections = [
+                ("wallet", struct_fields::<WalletConfig>()),
+                ("network", struct_fields::<NetworkConfig>()),
+                ("payment", struct_fields::<PaymentConfig>()),
+                ("signer", struct_fields::<SignerConfig>()),
+            ];
+            debug_assert_eq!(
+                struct_fields::<EnvConfig>(),
+                sections.map(|(section, _)| section)
+            );
+            sections
+                .iter()
+                .flat_map(|(section, fields)| {
+                    fields
+                        .iter()
+                        .map(move |field| format!("{}.{}", section, field))
+                })
+                .collect()
+        });
+        keys.iter().map(String::as_str).collect()
     }
 
     /// Check if network configuration is complete for making payments
     pub fn check_network_config(&self) -> std::result::Result<(), MissingConfigPrompt> {
         let mut missing_fields = Vec::new();
 
-        if self.network.rpc_url.is_none() {
+        if self.network.rpc_url.is_none() && self.network.rpc_urls.is_empty() {
             missing_fields.push("network.rpc_url".to_string());
         }
         if self.network.chain_id.is_none() {
@@ -284,6 +1458,7 @@ impl Config {
                         "ethereum-mainnet".to_string(),
                     ],
                     default: Some("base-sepolia".to_string()),
+                    answer: None,
                 }],
                 hint: "Run: payment-config use-network <network-name>".to_string(),
             })
@@ -291,6 +1466,179 @@ impl Config {
     }
 }
 
+/// Well-known tokens beyond the profiles' default tokens
+pub const BUILTIN_TOKENS: &[BuiltinToken] = &[
+    BuiltinToken {
+        network: "base-sepolia",
+        symbol: "EURC",
+        address: "0x808456652fdb597867f38412077A9182bf77359F",
+        decimals: 6,
+    },
+    BuiltinToken {
+        network: "base-mainnet",
+        symbol: "EURC",
+        address: "0x60a3E35Cc302bFA44Cb288Bc5a4F316Fdb1adb42",
+        decimals: 6,
+    },
+    BuiltinToken {
+        network: "ethereum-sepolia",
+        symbol: "USDC",
+        address: "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238",
+        decimals: 6,
+    },
+    BuiltinToken {
+        network: "ethereum-mainnet",
+        symbol: "EURC",
+        address: "0x1aBaEA1f7C830bD89Acc67eC4af516284b1bC33c",
+        decimals: 6,
+    },
+    BuiltinToken {
+        network: "ethereum-mainnet",
+        symbol: "DAI",
+        address: "0x6B175474E89094C44Da98b954EedeAC495271d0F",
+        decimals: 18,
+    },
+];
+
+/// Max age of feeds with a 1 h heartbeat or shorter (ETH, DAI), plus margin
+const HOURLY_FEED_MAX_AGE_SECS: u64 = 3_900;
+
+/// Max age of USDC feeds, whose 24 h heartbeat is their only update while the peg holds
+const DAILY_FEED_MAX_AGE_SECS: u64 = 90_000;
+
+/// Chainlink `<SYMBOL> / USD` aggregators on the built-in networks
+pub const BUILTIN_PRICE_FEEDS: &[BuiltinPriceFeed] = &[
+    BuiltinPriceFeed {
+        network: "base-mainnet",
+        symbol: "ETH",
+        address: "0x71041dddad3595F9CEd3DcCFBe3D1F4b0a16Bb70",
+        max_age_secs: HOURLY_FEED_MAX_AGE_SECS,
+    },
+    BuiltinPriceFeed {
+        network: "base-mainnet",
+        symbol: "USDC",
+        address: "0x7e860098F58bBFC8648a4311b374B1D669a2bc6B",
+        max_age_secs: DAILY_FEED_MAX_AGE_SECS,
+    },
+    BuiltinPriceFeed {
+        network: "ethereum-mainnet",
+        symbol: "ETH",
+        address: "0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419",
+        max_age_secs: HOURLY_FEED_MAX_AGE_SECS,
+    },
+    BuiltinPriceFeed {
+        network: "ethereum-mainnet",
+        symbol: "USDC",
+        address: "0x8fFfFfd4AfB6115b954Bd326cbe7B4BA576818f6",
+        max_age_secs: DAILY_FEED_MAX_AGE_SECS,
+    },
+    BuiltinPriceFeed {
+        network: "ethereum-mainnet",
+        symbol: "DAI",
+        address: "0xAed0c38402a5d19df6E4c03F4E2DceD6e29c1ee9",
+        max_age_secs: HOURLY_FEED_MAX_AGE_SECS,
+    },
+];
+
+pub struct BuiltinPriceFeed {
+    pub network: &'static str,
+    pub symbol: &'static str,
+    pub address: &'static str,
+    /// Oldest answer accepted, from the feed's heartbeat
+    pub max_age_secs: u64,
+}
+
+/// A price feed in the registry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceFeedEntry {
+    pub address: String,
+    /// Oldest answer accepted, in seconds (default: `payment.max_price_age_secs`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_secs: Option<u64>,
+}
+
+pub struct BuiltinToken {
+    pub network: &'static str,
+    pub symbol: &'static str,
+    pub address: &'static str,
+    pub decimals: u8,
+}
+
+/// A token in the registry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenEntry {
+    pub address: String,
+    pub decimals: u8,
+}
+
+/// A token resolved from a symbol or contract address
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedToken {
+    pub address: alloy::primitives::Address,
+    pub symbol: Option<String>,
+    pub decimals: u8,
+}
+
+/// Where a network profile was defined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileSource {
+    BuiltIn,
+    Config,
+    NetworksFile,
+}
+
+impl std::fmt::Display for ProfileSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileSource::BuiltIn => write!(f, "built-in"),
+            ProfileSource::Config => write!(f, "config.toml"),
+            ProfileSource::NetworksFile => write!(f, "networks.toml"),
+        }
+    }
+}
+
+/// A user-defined network profile (`[profiles.<name>]` in config.toml or `[<name>]` in networks.toml)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserNetworkProfile {
+    pub chain_id: u64,
+    pub rpc_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_token_symbol: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_token_decimals: Option<u8>,
+    /// Symbol of the gas token (default ETH), e.g. POL or xDAI
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub native_symbol: Option<String>,
+}
+
+impl From<&NetworkProfile> for UserNetworkProfile {
+    fn from(p: &NetworkProfile) -> Self {
+        Self {
+            chain_id: p.chain_id,
+            rpc_url: p.rpc_url.to_string(),
+            default_token: p.default_token.map(str::to_string),
+            default_token_symbol: p.default_token_symbol.map(str::to_string),
+            default_token_decimals: p.default_token_decimals,
+            native_symbol: None,
+        }
+    }
+}
+
+/// A network profile together with its name and source
+#[derive(Debug, Clone)]
+pub struct ResolvedNetworkProfile {
+    pub name: String,
+    pub source: ProfileSource,
+    pub profile: UserNetworkProfile,
+}
+
+/// Path of the networks.toml that sits next to `config_path`
+pub fn networks_path_for(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("networks.toml")
+}
+
 /// Structured prompt for missing configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MissingConfigPrompt {
@@ -304,10 +1652,15 @@ pub struct MissingConfigPrompt {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfigQuestion {
     pub field: String,
+    #[serde(default)]
     pub question: String,
+    #[serde(default)]
     pub examples: Vec<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub default: Option<String>,
+    /// Filled in by the caller when answering the questions from a file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer: Option<String>,
 }
 
 #[cfg(test)]
@@ -315,12 +1668,28 @@ mod tests {
     use super::*;
     use tempfile::tempdir;
 
+    /// Environment lookup with no variables set
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
     #[test]
     fn test_default_config() {
         let config = Config::default();
         assert_eq!(config.wallet.path, "wallet.json");
         assert_eq!(config.wallet.password_file, "password.txt");
         assert!(config.network.chain_id.is_none());
+        assert_eq!(config.signer.backend, SignerBackend::Local);
+    }
+
+    #[test]
+    fn test_set_signer_backend() {
+        let mut config = Config::default();
+        config.set("signer.backend", "remote").unwrap();
+        config.set("signer.url", "http://127.0.0.1:9000").unwrap();
+        assert_eq!(config.signer.backend, SignerBackend::Remote);
+        assert_eq!(config.get("signer.backend"), Some("remote".to_string()));
+        assert!(config.set("signer.backend", "ledger").is_err());
     }
 
     #[test]
@@ -339,6 +1708,421 @@ mod tests {
         assert_eq!(loaded.network.chain_id, Some(12345));
     }
 
+    #[test]
+    fn test_every_key_has_a_spec() {
+        let keys = Config::valid_keys();
+        assert!(keys.contains(&"network.rpc_timeout_secs"));
+        for key in &keys {
+            assert!(key_spec(key).is_ok(), "{} has no entry in KEY_SPECS", key);
+        }
+        assert_eq!(keys.len(), KEY_SPECS.len());
+
+        // Everything the default config writes is a settable key
+        let table = toml::Table::try_from(Config::default().file_layout()).unwrap();
+        for section in ["wallet", "network", "payment", "signer"] {
+            for field in table[section].as_table().unwrap().keys() {
+                let key = format!("{}.{}", section, field);
+                assert!(keys.contains(&key.as_str()), "{} is not a valid key", key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_old_file_is_upgraded_only_on_request() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        let original = "# my settings\n[network]\nchain_id = 84532\n";
+        fs::write(&config_path, original).unwrap();
+
+        // Loading migrates in memory and leaves the file alone
+        let loaded = Config::load_from(Some(&config_path)).unwrap();
+        assert_eq!(loaded.config_version, CONFIG_VERSION);
+        assert_eq!(loaded.network.chain_id, Some(84532));
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), original);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let (backup, notes) = upgrade_file(Some(&config_path)).unwrap().unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), original);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&backup).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert_eq!(notes.len(), 1);
+        let upgraded = fs::read_to_string(&config_path).unwrap();
+        assert!(upgraded.starts_with("config_version = 1\n"), "{}", upgraded);
+        assert!(upgrade_file(Some(&config_path)).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_user_profiles_merge_with_builtins() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            r#"
+[profiles.devnet]
+chain_id = 31337
+rpc_url = "http://127.0.0.1:8545"
+
+[profiles.base-sepolia]
+chain_id = 84532
+rpc_url = "https://base-sepolia.example.com"
+"#,
+        )
+        .unwrap();
+        fs::write(
+            networks_path_for(&config_path),
+            r#"
+[arbitrum-one]
+chain_id = 42161
+rpc_url = "https://arb1.arbitrum.io/rpc"
+default_token = "0xaf88d065e77c8cC2239327C5EDb3A432268e5831"
+default_token_symbol = "USDC"
+default_token_decimals = 6
+
+[devnet]
+chain_id = 1
+rpc_url = "http://shadowed"
+"#,
+        )
+        .unwrap();
+
+        let mut config = Config::load_from(Some(&config_path)).unwrap();
+        let profiles = config.network_profiles();
+        assert_eq!(profiles.len(), NETWORK_PROFILES.len() + 2);
+
+        // config.toml overrides networks.toml and built-ins, keeping built-in order
+        let base = &profiles[0];
+        assert_eq!(base.name, "base-sepolia");
+        assert_eq!(base.source, ProfileSource::Config);
+        assert_eq!(base.profile.rpc_url, "https://base-sepolia.example.com");
+        let devnet = config.find_network_profile("devnet").unwrap();
+        assert_eq!(devnet.source, ProfileSource::Config);
+        assert_eq!(devnet.profile.chain_id, 31337);
+
+        config.apply_network_profile("arbitrum-one").unwrap();
+        assert_eq!(config.network.chain_id, Some(42161));
+        assert_eq!(config.payment.default_token_symbol.as_deref(), Some("USDC"));
+
+        // networks.toml profiles are not copied into config.toml
+        config.save_to(Some(&config_path)).unwrap();
+        let saved = fs::read_to_string(&config_path).unwrap();
+        assert!(saved.contains("[profiles.devnet]"));
+        assert!(!saved.contains("arbitrum-one]"));
+    }
+
+    #[test]
+    fn test_add_and_remove_network_profile() {
+        let mut config = Config::default();
+        let profile = UserNetworkProfile {
+            chain_id: 31337,
+            rpc_url: "http://127.0.0.1:8545".to_string(),
+            default_token: None,
+            default_token_symbol: None,
+            default_token_decimals: None,
+            native_symbol: Some("POL".to_string()),
+        };
+        config
+            .add_network_profile("devnet", profile.clone())
+            .unwrap();
+        assert_eq!(config.native_symbol(), "ETH");
+        config.apply_network_profile("devnet").unwrap();
+        assert_eq!(config.native_symbol(), "POL");
+        config.apply_network_profile("base-sepolia").unwrap();
+        assert!(config
+            .add_network_profile("bad name", profile.clone())
+            .is_err());
+        assert!(config
+            .add_network_profile(
+                "devnet",
+                UserNetworkProfile {
+                    rpc_url: "ftp://x".to_string(),
+                    ..profile
+                }
+            )
+            .is_err());
+
+        assert_eq!(
+            config.remove_network_profile("devnet").unwrap(),
+            ProfileSource::Config
+        );
+        assert!(config.remove_network_profile("base-mainnet").is_err());
+        assert!(config.remove_network_profile("devnet").is_err());
+    }
+
+    #[test]
+    fn test_rpc_endpoints_order() {
+        let config: Config = toml::from_str(
+            r#"
+[network]
+rpc_url = "https://primary.example"
+rpc_urls = [
+    "https://fallback-1.example",
+    { url = "https://fallback-2.example", timeout_secs = 5 },
+]
+"#,
+        )
+        .unwrap();
+        let urls: Vec<_> = config.rpc_endpoints().into_iter().map(|e| e.url).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://primary.example",
+                "https://fallback-1.example",
+                "https://fallback-2.example"
+            ]
+        );
+        assert_eq!(config.network.rpc_urls[1].timeout_secs, Some(5));
+
+        // set keeps the timeout of endpoints that remain listed
+        let mut config = config;
+        config
+            .set(
+                "network.rpc_urls",
+                "https://fallback-2.example, https://primary.example",
+            )
+            .unwrap();
+        assert_eq!(config.network.rpc_urls[0].timeout_secs, Some(5));
+        let urls: Vec<_> = config.rpc_endpoints().into_iter().map(|e| e.url).collect();
+        assert_eq!(
+            urls,
+            ["https://fallback-2.example", "https://primary.example"]
+        );
+    }
+
+    #[test]
+    fn test_token_registry() {
+        let mut config: Config = toml::from_str(
+            r#"
+[network]
+name = "base-sepolia"
+
+[tokens.base-sepolia.TEST]
+address = "0x000000000000000000000000000000000000dEaD"
+decimals = 18
+"#,
+        )
+        .unwrap();
+
+        let usdc = config.resolve_token("usdc").unwrap();
+        assert_eq!(usdc.symbol.as_deref(), Some("USDC"));
+        assert_eq!(usdc.decimals, 6);
+        assert_eq!(config.resolve_token("EURC").unwrap().decimals, 6);
+        assert_eq!(config.resolve_token("TEST").unwrap().decimals, 18);
+
+        // Addresses resolve to registered decimals
+        let by_address = config
+            .resolve_token("0x000000000000000000000000000000000000dead")
+            .unwrap();
+        assert_eq!(by_address.symbol.as_deref(), Some("TEST"));
+        assert_eq!(by_address.decimals, 18);
+
+        // Unregistered addresses are refused rather than given guessed decimals
+        let unknown = "0x0000000000000000000000000000000000000bad";
+        assert!(matches!(
+            config.resolve_token(unknown),
+            Err(Error::InvalidArgument(_))
+        ));
+        config.payment.default_token = Some(unknown.to_string());
+        assert!(config.resolve_token(unknown).is_err());
+        config.payment.default_token_decimals = Some(8);
+        assert_eq!(config.resolve_token(unknown).unwrap().decimals, 8);
+        config.payment.default_token = None;
+        config.payment.default_token_decimals = None;
+
+        // DAI is registered on ethereum-mainnet only
+        assert!(matches!(
+            config.resolve_token("DAI"),
+            Err(Error::InvalidArgument(_))
+        ));
+        config.network.name = Some("ethereum-mainnet".to_string());
+        assert_eq!(config.resolve_token("DAI").unwrap().decimals, 18);
+
+        config.remove_token("base-sepolia", "test").unwrap();
+        assert!(config.tokens.is_empty());
+        assert!(config.remove_token("base-sepolia", "USDC").is_err());
+    }
+
+    #[test]
+    fn test_get_set_unset_by_key_path() {
+        let mut config = Config::default();
+
+        // Every key round-trips through set and get
+        config.set("network.chain_id", "8453").unwrap();
+        config
+            .set("network.rpc_url", "https://mainnet.base.org")
+            .unwrap();
+        config
+            .set("network.rpc_urls", "https://a.example,https://b.example")
+            .unwrap();
+        config.set("payment.max_auto_payment", "10.50").unwrap();
+        config.set("signer.backend", "remote").unwrap();
+        assert_eq!(config.get("network.chain_id").as_deref(), Some("8453"));
+        assert_eq!(
+            config.get("network.rpc_urls").as_deref(),
+            Some("https://a.example,https://b.example")
+        );
+        assert_eq!(config.get("signer.backend").as_deref(), Some("remote"));
+
+        // Addresses are stored checksummed; a bad checksum is rejected
+        config
+            .set(
+                "payment.default_token",
+                "0x036cbd53842c5426634e7929541ec2318f3dcf7e",
+            )
+            .unwrap();
+        assert_eq!(
+            config.payment.default_token.as_deref(),
+            Some("0x036CbD53842c5426634e7929541eC2318f3dCF7e")
+        );
+        assert!(config
+            .set(
+                "payment.default_token",
+                "0x036CBD53842c5426634e7929541eC2318f3dCF7e"
+            )
+            .is_err());
+
+        // Type-aware validation
+        assert!(config.set("network.rpc_url", "sepolia.base.org").is_err());
+        assert!(config.set("network.chain_id", "-1").is_err());
+        assert!(config.set("payment.default_token_decimals", "300").is_err());
+        assert!(config.set("payment.max_auto_payment", "ten").is_err());
+        assert!(config.set("network.rpcurl", "https://x.example").is_err());
+
+        // Unset restores the default
+        config.set("signer.timeout_secs", "5").unwrap();
+        config.unset("signer.timeout_secs").unwrap();
+        assert_eq!(config.signer.timeout_secs, 30);
+        config.unset("network.rpc_url").unwrap();
+        assert_eq!(config.network.rpc_url, None);
+        assert_eq!(config.network.chain_id, Some(8453));
+
+        // Nested paths can be read
+        config
+            .add_token(
+                "base-sepolia",
+                "TEST",
+                TokenEntry {
+                    address: "0x000000000000000000000000000000000000dEaD".to_string(),
+                    decimals: 18,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            config.get("tokens.base-sepolia.TEST.decimals").as_deref(),
+            Some("18")
+        );
+    }
+
+    #[test]
+    fn test_named_environments() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            r#"
+            [network]
+            name = "base-sepolia"
+            chain_id = 84532
+            rpc_url = "https://sepolia.base.org"
+
+            [envs.production.wallet]
+            path = "wallet-production.json"
+
+            [envs.production.network]
+            name = "base-mainnet"
+            chain_id = 8453
+            rpc_url = "https://mainnet.base.org"
+            "#,
+        )
+        .unwrap();
+
+        let mut config = Config::load_file_env(Some(&config_path), Some("production")).unwrap();
+        assert_eq!(config.env_name(), Some("production"));
+        assert_eq!(config.network.chain_id, Some(8453));
+        assert!(config.wallet_path().ends_with("wallet-production.json"));
+        assert_eq!(config.origin("network.chain_id"), ValueOrigin::File);
+        assert_eq!(config.origin("payment.default_token"), ValueOrigin::Default);
+
+        // Edits go to the selected environment only
+        config.set("payment.max_auto_payment", "100").unwrap();
+        config.save_to(Some(&config_path)).unwrap();
+
+        let base = Config::load_env_with(Some(&config_path), None, no_env).unwrap();
+        assert_eq!(base.env_name(), None);
+        assert_eq!(base.network.chain_id, Some(84532));
+        assert_eq!(base.payment.max_auto_payment, None);
+        assert_eq!(
+            base.envs["production"].payment.max_auto_payment.as_deref(),
+            Some("100")
+        );
+
+        // active_env picks the environment when none is given
+        let mut base = base;
+        base.active_env = Some("production".to_string());
+        base.save_to(Some(&config_path)).unwrap();
+        let config = Config::load_env_with(Some(&config_path), None, no_env).unwrap();
+        assert_eq!(config.network.chain_id, Some(8453));
+
+        // PAYMENT_ENV beats active_env, and --env beats both
+        let env = |var: &str| (var == "PAYMENT_ENV").then(|| "staging".to_string());
+        assert!(Config::load_env_with(Some(&config_path), None, env).is_err());
+        let config = Config::load_env_with(Some(&config_path), Some("production"), env).unwrap();
+        assert_eq!(config.env_name(), Some("production"));
+
+        assert!(Config::load_file_env(Some(&config_path), Some("staging")).is_err());
+
+        // An unknown active_env breaks loading, except for the commands that fix it
+        let mut base = Config::load_file_unselected(Some(&config_path)).unwrap();
+        assert_eq!(base.env_name(), None);
+        base.active_env = Some("staging".to_string());
+        base.save_to(Some(&config_path)).unwrap();
+        assert!(Config::load_env_with(Some(&config_path), None, no_env).is_err());
+        let base = Config::load_file_unselected(Some(&config_path)).unwrap();
+        assert_eq!(base.network.chain_id, Some(84532));
+        assert_eq!(base.warnings().len(), 1);
+        assert!(base.warnings()[0].starts_with("active_env staging"));
+    }
+
+    #[test]
+    fn test_value_origins() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            "[network]\nchain_id = 84532\nrpc_url = \"https://sepolia.base.org\"\n",
+        )
+        .unwrap();
+
+        let mut config = Config::load_file(Some(&config_path)).unwrap();
+        config
+            .apply_env_overrides(|var| {
+                (var == "PAYMENT_SIGNER_TIMEOUT_SECS").then(|| "7".to_string())
+            })
+            .unwrap();
+
+        assert_eq!(
+            env_var_for_key("network.rpc_url"),
+            "PAYMENT_NETWORK_RPC_URL"
+        );
+        assert_eq!(config.signer.timeout_secs, 7);
+        assert_eq!(config.origin("signer.timeout_secs"), ValueOrigin::Env);
+        assert_eq!(config.origin("network.chain_id"), ValueOrigin::File);
+        assert_eq!(config.origin("wallet.path"), ValueOrigin::Default);
+
+        config.set_from_flag("network.chain_id", "8453").unwrap();
+        assert_eq!(config.origin("network.chain_id"), ValueOrigin::Flag);
+
+        // Overrides keep the config tied to the file it was loaded from
+        assert_eq!(config.contacts_path(), dir.path().join("contacts.toml"));
+
+        // Overrides are not part of the file contents
+        let file_only = Config::load_file(Some(&config_path)).unwrap();
+        assert_eq!(file_only.signer.timeout_secs, 30);
+    }
+
     #[test]
     fn test_apply_network_profile() {
         let mut config = Config::default();
@@ -351,4 +2135,23 @@ mod tests {
             Some("https://sepolia.base.org".to_string())
         );
     }
+
+    #[test]
+    fn test_for_network_does_not_leak_active_network() {
+        let mut config = Config::default();
+        config.apply_network_profile("base-sepolia").unwrap();
+        config.network.rpc_url = Some("https://custom.example".to_string());
+
+        let active = config.for_network("base-sepolia").unwrap();
+        assert_eq!(
+            active.network.rpc_url.as_deref(),
+            Some("https://custom.example")
+        );
+
+        // ethereum-sepolia has no default token; base-sepolia's USDC must not carry over
+        let other = config.for_network("ethereum-sepolia").unwrap();
+        assert_eq!(other.network.chain_id, Some(11155111));
+        assert!(other.default_token().is_none());
+        assert!(config.for_network("nope").is_err());
+    }
 }
@@ -0,0 +1,229 @@
+use crate::config::checksum_address;
+use crate::error::{Error, Result};
+use crate::{default_contacts_path, ensure_private_dir, write_private};
+use alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Leading and trailing hex digits compared when looking for lookalike addresses
+const LOOKALIKE_DIGITS: usize = 4;
+
+/// A labeled counterparty (`[<label>]` table in contacts.toml)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contact {
+    /// EIP-55 checksummed address
+    pub address: String,
+    /// Token used when paying this contact without --token (registry symbol or address)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// A payment recipient, with the contact it was resolved from
+#[derive(Debug, Clone)]
+pub struct Recipient {
+    pub address: Address,
+    pub label: Option<String>,
+    pub contact: Option<Contact>,
+}
+
+/// The address book stored in contacts.toml in the data directory
+#[derive(Debug, Clone, Default)]
+pub struct ContactBook {
+    path: PathBuf,
+    contacts: BTreeMap<String, Contact>,
+}
+
+impl ContactBook {
+    /// Load the address book from the data directory
+    pub fn load() -> Result<Self> {
+        Self::load_from(None)
+    }
+
+    /// Load the address book from `path` (an empty book if the file does not exist)
+    pub fn load_from(path: Option<&Path>) -> Result<Self> {
+        let path = path
+            .map(PathBuf::from)
+            .unwrap_or_else(default_contacts_path);
+        let contacts = if path.exists() {
+            toml::from_str(&fs::read_to_string(&path)?)?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self { path, contacts })
+    }
+
+    /// Write the address book back to the file it was loaded from
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            ensure_private_dir(dir)?;
+        }
+        write_private(&self.path, toml::to_string_pretty(&self.contacts)?)
+    }
+
+    /// All contacts, ordered by label
+    pub fn contacts(&self) -> &BTreeMap<String, Contact> {
+        &self.contacts
+    }
+
+    /// Look up a contact by label (with or without the leading `@`)
+    pub fn get(&self, label: &str) -> Option<&Contact> {
+        self.contacts.get(label.strip_prefix('@').unwrap_or(label))
+    }
+
+    /// Add or replace a contact; the address is stored checksummed
+    pub fn add(&mut self, label: &str, mut contact: Contact) -> Result<()> {
+        let label = label.strip_prefix('@').unwrap_or(label);
+        if label.is_empty()
+            || !label
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        {
+            return Err(Error::Config(format!(
+                "Invalid contact label: {} (use letters, digits, '-', '_' and '.')",
+                label
+            )));
+        }
+        contact.address = checksum_address(&contact.address)?;
+        self.contacts.insert(label.to_string(), contact);
+        Ok(())
+    }
+
+    /// Remove a contact, returning it
+    pub fn remove(&mut self, label: &str) -> Result<Contact> {
+        let label = label.strip_prefix('@').unwrap_or(label);
+        self.contacts
+            .remove(label)
+            .ok_or_else(|| Error::Config(format!("Unknown contact: @{}", label)))
+    }
+
+    /// Resolve a recipient: `@label` from the address book, or a raw address.
+    ///
+    /// A raw address that belongs to a contact is reported with its label.
+    pub fn resolve(&self, to: &str) -> Result<Recipient> {
+        if let Some(label) = to.strip_prefix('@') {
+            let contact = self.contacts.get(label).ok_or_else(|| {
+                Error::InvalidArgument(format!(
+                    "Unknown contact: @{} (see payment-config contacts list)",
+                    label
+                ))
+            })?;
+            let address = contact.address.parse().map_err(|_| {
+                Error::Config(format!(
+                    "Contact @{} has an invalid address: {}",
+                    label, contact.address
+                ))
+            })?;
+            return Ok(Recipient {
+                address,
+                label: Some(label.to_string()),
+                contact: Some(contact.clone()),
+            });
+        }
+
+        let address: Address = to
+            .parse()
+            .map_err(|_| Error::InvalidArgument(format!("Invalid recipient address: {}", to)))?;
+        let known = self
+            .contacts
+            .iter()
+            .find(|(_, c)| c.address.parse::<Address>().ok() == Some(address));
+        Ok(Recipient {
+            address,
+            label: known.map(|(label, _)| label.clone()),
+            contact: known.map(|(_, contact)| contact.clone()),
+        })
+    }
+
+    /// A contact whose address starts and ends like `address` without being it.
+    ///
+    /// Address poisoning plants such lookalikes in a wallet's history, hoping the
+    /// victim copies the wrong one.
+    pub fn lookalike(&self, address: &Address) -> Option<(&str, &Contact)> {
+        let hex = format!("{:x}", address);
+        self.contacts
+            .iter()
+            .find(|(_, contact)| {
+                let Ok(other) = contact.address.parse::<Address>() else {
+                    return false;
+                };
+                let other = format!("{:x}", other);
+                other != hex
+                    && other[..LOOKALIKE_DIGITS] == hex[..LOOKALIKE_DIGITS]
+                    && other[other.len() - LOOKALIKE_DIGITS..]
+                        == hex[hex.len() - LOOKALIKE_DIGITS..]
+            })
+            .map(|(label, contact)| (label.as_str(), contact))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const VENDOR: &str = "0x52908400098527886E0F7030069857D2E4169EE7";
+
+    fn contact(address: &str) -> Contact {
+        Contact {
+            address: address.to_string(),
+            token: Some("USDC".to_string()),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_contacts_round_trip_and_resolve() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("contacts.toml");
+
+        let mut book = ContactBook::load_from(Some(&path)).unwrap();
+        assert!(book.contacts().is_empty());
+        book.add("@vendor-a", contact(&VENDOR.to_lowercase()))
+            .unwrap();
+        assert!(book.add("bad label", contact(VENDOR)).is_err());
+        assert!(book
+            .add(
+                "typo",
+                contact("0x52908400098527886e0F7030069857D2E4169EE7")
+            )
+            .is_err());
+        book.save().unwrap();
+
+        let book = ContactBook::load_from(Some(&path)).unwrap();
+        assert_eq!(book.get("vendor-a").unwrap().address, VENDOR);
+
+        let recipient = book.resolve("@vendor-a").unwrap();
+        assert_eq!(recipient.address.to_checksum(None), VENDOR);
+        assert_eq!(recipient.label.as_deref(), Some("vendor-a"));
+
+        // Raw addresses of known contacts are labeled too
+        let recipient = book.resolve(&VENDOR.to_lowercase()).unwrap();
+        assert_eq!(recipient.label.as_deref(), Some("vendor-a"));
+
+        assert!(book.resolve("@nobody").is_err());
+        assert!(book.resolve("0x1234").is_err());
+    }
+
+    #[test]
+    fn test_lookalike() {
+        let mut book = ContactBook::default();
+        book.add("vendor-a", contact(VENDOR)).unwrap();
+
+        let exact: Address = VENDOR.parse().unwrap();
+        assert!(book.lookalike(&exact).is_none());
+
+        let poisoned: Address = "0x5290000000000000000000000000000000009ee7"
+            .parse()
+            .unwrap();
+        assert_eq!(book.lookalike(&poisoned).unwrap().0, "vendor-a");
+
+        let unrelated: Address = "0x000000000000000000000000000000000000dEaD"
+            .parse()
+            .unwrap();
+        assert!(book.lookalike(&unrelated).is_none());
+    }
+}
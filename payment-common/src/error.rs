@@ -36,12 +36,29 @@ pub enum Error {
 
     #[error("Invalid argument: {0}")]
     InvalidArgument(String),
+
+    #[error("Agent error: {0}")]
+    Agent(String),
+
+    #[error("Signer error: {0}")]
+    Signer(String),
+
+    #[error("Secret store error: {0}")]
+    SecretStore(String),
+
+    #[error("Network error: {0}")]
+    Network(String),
+
+    #[error("Transaction failed: {0}")]
+    Transaction(String),
 }
 
 impl Error {
     /// Get the exit code for this error
     pub fn exit_code(&self) -> i32 {
         match self {
+            Error::Transaction(_) => 2,
+            Error::Network(_) => 3,
             Error::MissingConfig(_) => 10,
             Error::Config(_) => 11,
             Error::WalletNotFound(_) => 12,
@@ -0,0 +1,136 @@
+use crate::config::ResolvedToken;
+use crate::error::{Error, Result};
+use crate::payments::IERC20;
+use alloy::consensus::{SignableTransaction, TxEip1559};
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{address, Address, TxKind, U256};
+use alloy::providers::Provider;
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+
+/// Gas of a native transfer
+pub const NATIVE_TRANSFER_GAS: u64 = 21_000;
+
+/// Gas of a typical ERC-20 `transfer` to an address with no balance yet, used when
+/// the transfer cannot be estimated (e.g. the wallet holds none of the token)
+pub const TYPICAL_TOKEN_TRANSFER_GAS: u64 = 65_000;
+
+/// OP-stack predeploy that prices the L1 data of L2 transactions
+pub const GAS_PRICE_ORACLE: Address = address!("0x420000000000000000000000000000000000000F");
+
+/// Recipient of the estimated transfer: an address that holds no tokens, so the
+/// estimate includes creating the recipient's balance
+const ESTIMATE_RECIPIENT: Address = Address::repeat_byte(0x11);
+
+sol! {
+    #[sol(rpc)]
+    contract IGasPriceOracle {
+        function getL1Fee(bytes memory data) external view returns (uint256);
+    }
+}
+
+/// Native currency one transfer spends on gas at current fees
+#[derive(Debug, Clone)]
+pub struct TransferCost {
+    pub gas: u64,
+    /// Whether `gas` was estimated for this wallet (otherwise a typical value)
+    pub estimated: bool,
+    /// Gas price in wei
+    pub gas_price: u128,
+    /// L1 data fee in wei, on OP-stack networks (Base, Optimism, ...)
+    pub l1_fee: Option<U256>,
+}
+
+impl TransferCost {
+    /// Execution gas plus the L1 data fee, in wei
+    pub fn total(&self) -> U256 {
+        U256::from(self.gas) * U256::from(self.gas_price) + self.l1_fee.unwrap_or_default()
+    }
+}
+
+/// Cost of a `transfer` of `token` from `owner` (a native transfer without a token).
+///
+/// A token transfer is estimated when `token_balance` is non-zero; a transfer of more
+/// than the balance reverts, so otherwise `TYPICAL_TOKEN_TRANSFER_GAS` is assumed.
+pub async fn transfer_cost<P: Provider>(
+    provider: &P,
+    owner: Address,
+    token: Option<&ResolvedToken>,
+    token_balance: Option<U256>,
+) -> Result<TransferCost> {
+    let network = |what: &str, e: &dyn std::fmt::Display| {
+        Error::Network(format!("Failed to get {}: {}", what, e))
+    };
+    let gas_price = provider
+        .get_gas_price()
+        .await
+        .map_err(|e| network("gas price", &e))?;
+
+    let (to, input) = match token {
+        Some(token) => (
+            token.address,
+            IERC20::transferCall::new((ESTIMATE_RECIPIENT, U256::from(1))).abi_encode(),
+        ),
+        None => (ESTIMATE_RECIPIENT, Vec::new()),
+    };
+    let (gas, estimated) = match token {
+        None => (NATIVE_TRANSFER_GAS, true),
+        Some(_) if token_balance.is_some_and(|b| !b.is_zero()) => {
+            let tx = TransactionRequest::default()
+                .with_from(owner)
+                .with_to(to)
+                .with_input(input.clone());
+            match provider.estimate_gas(tx).await {
+                Ok(gas) => (gas, true),
+                Err(_) => (TYPICAL_TOKEN_TRANSFER_GAS, false),
+            }
+        }
+        Some(_) => (TYPICAL_TOKEN_TRANSFER_GAS, false),
+    };
+
+    let l1_fee = l1_data_fee(provider, to, input, gas, gas_price).await?;
+    Ok(TransferCost {
+        gas,
+        estimated,
+        gas_price,
+        l1_fee,
+    })
+}
+
+/// L1 data fee the network's GasPriceOracle charges for the transaction, or `None`
+/// when the network has no oracle (not an OP-stack chain)
+async fn l1_data_fee<P: Provider>(
+    provider: &P,
+    to: Address,
+    input: Vec<u8>,
+    gas: u64,
+    gas_price: u128,
+) -> Result<Option<U256>> {
+    let failed =
+        |e: &dyn std::fmt::Display| Error::Network(format!("Failed to get the L1 data fee: {}", e));
+    let code = provider
+        .get_code_at(GAS_PRICE_ORACLE)
+        .await
+        .map_err(|e| failed(&e))?;
+    if code.is_empty() {
+        return Ok(None);
+    }
+
+    // The oracle prices the unsigned transaction; its signature is added on chain
+    let chain_id = provider.get_chain_id().await.map_err(|e| failed(&e))?;
+    let tx = TxEip1559 {
+        chain_id,
+        gas_limit: gas,
+        max_fee_per_gas: gas_price,
+        to: TxKind::Call(to),
+        input: input.into(),
+        ..Default::default()
+    };
+    let fee = IGasPriceOracle::new(GAS_PRICE_ORACLE, provider)
+        .getL1Fee(tx.encoded_for_signing().into())
+        .call()
+        .await
+        .map_err(|e| failed(&e))?;
+    Ok(Some(fee))
+}
@@ -1,25 +1,73 @@
+#[cfg(unix)]
+pub mod agent;
+pub mod allowances;
+pub mod checks;
 pub mod config;
+pub mod contacts;
 pub mod error;
+pub mod gas;
+mod migrations;
+pub mod names;
+pub mod payments;
+pub mod pricing;
+pub mod rpc;
+pub mod secret_store;
+pub mod signer;
 pub mod wallet;
 
 pub use config::Config;
+pub use contacts::ContactBook;
 pub use error::{Error, Result};
+pub use signer::PaymentSigner;
 pub use wallet::{Wallet, WalletInfo};
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-/// Get the default data directory (parent of the directory containing the executable).
+/// Environment variable that overrides the data directory
+pub const PAYMENT_HOME_ENV: &str = "PAYMENT_HOME";
+
+/// Environment variable that selects a named environment from config.toml (like `--env`)
+pub const PAYMENT_ENV_VAR: &str = "PAYMENT_ENV";
+
+/// Get the data directory holding config.toml, wallet.json and password.txt.
 ///
-/// Given the installed layout where binaries live in `scripts/`, this returns the
-/// skill root directory (e.g., `~/.openclaw/skills/payment/`).
+/// In order:
+/// - `PAYMENT_HOME`, if set
+/// - the skill root when the binary lives in a `scripts/` directory
+///   (e.g., `~/.openclaw/skills/payment/scripts/pay` gives `~/.openclaw/skills/payment/`)
+/// - `$XDG_CONFIG_HOME/payment` (`~/.config/payment`, or `%APPDATA%\payment` on Windows)
 pub fn default_data_dir() -> PathBuf {
-    std::env::current_exe()
-        .ok()
-        .and_then(|exe| exe.parent().map(|p| p.to_path_buf()))
-        .and_then(|dir| dir.parent().map(|p| p.to_path_buf()))
+    if let Some(home) = std::env::var_os(PAYMENT_HOME_ENV).filter(|h| !h.is_empty()) {
+        return PathBuf::from(home);
+    }
+    skill_root()
+        .or_else(user_config_dir)
         .unwrap_or_else(|| PathBuf::from("."))
 }
 
+/// Skill root for the installed layout where binaries live in `<root>/scripts/`
+fn skill_root() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let dir = exe.parent()?;
+    if dir.file_name()? != "scripts" {
+        return None;
+    }
+    dir.parent().map(Path::to_path_buf)
+}
+
+/// Per-user config directory for binaries installed outside a skill (cargo, Nix, containers)
+fn user_config_dir() -> Option<PathBuf> {
+    let non_empty = |name: &str| std::env::var_os(name).filter(|v| !v.is_empty());
+
+    let base = non_empty("XDG_CONFIG_HOME").map(PathBuf::from);
+    #[cfg(windows)]
+    let base = base.or_else(|| non_empty("APPDATA").map(PathBuf::from));
+    let base =
+        base.or_else(|| non_empty("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(base.join("payment"))
+}
+
 /// Get the default wallet path (data_dir/wallet.json)
 pub fn default_wallet_path() -> PathBuf {
     default_data_dir().join("wallet.json")
@@ -35,16 +83,58 @@ pub fn default_config_path() -> PathBuf {
     default_data_dir().join("config.toml")
 }
 
+/// Get the address book path (data_dir/contacts.toml)
+pub fn default_contacts_path() -> PathBuf {
+    default_data_dir().join("contacts.toml")
+}
+
+/// Get the payment agent socket path (`PAYMENT_AGENT_SOCK`, or data_dir/agent.sock)
+pub fn default_agent_socket_path() -> PathBuf {
+    std::env::var_os("PAYMENT_AGENT_SOCK")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| default_data_dir().join("agent.sock"))
+}
+
 /// Ensure the data directory exists with proper permissions
 pub fn ensure_data_dir() -> Result<PathBuf> {
     let dir = default_data_dir();
-    if !dir.exists() {
-        std::fs::create_dir_all(&dir)?;
+    ensure_private_dir(&dir)?;
+    Ok(dir)
+}
+
+/// Write `contents` to `path`, readable by the owner only on Unix.
+///
+/// A new file is created with mode 0600, so it is never readable by others, and an
+/// existing one is narrowed to 0600 before it is overwritten.
+pub fn write_private(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(contents.as_ref())?;
+    Ok(())
+}
+
+/// Create `dir` (owner-only on Unix) if it does not exist yet
+pub fn ensure_private_dir(dir: &Path) -> Result<()> {
+    if !dir.as_os_str().is_empty() && !dir.exists() {
+        std::fs::create_dir_all(dir)?;
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
-            std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+            std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
         }
     }
-    Ok(dir)
+    Ok(())
 }
@@ -0,0 +1,270 @@
+use crate::config::{
+    struct_fields, Config, PriceFeedEntry, TokenEntry, UserNetworkProfile, CONFIG_VERSION,
+};
+use crate::error::{Error, Result};
+use toml::{Table, Value};
+
+/// One schema upgrade, from `to - 1` to `to`
+struct Migration {
+    to: u32,
+    apply: fn(&mut Table) -> Vec<String>,
+}
+
+/// Every migration in order; append new ones and bump `CONFIG_VERSION`
+const MIGRATIONS: &[Migration] = &[Migration {
+    to: 1,
+    apply: add_config_version,
+}];
+
+/// Version 1 introduced `config_version` itself; nothing else changed
+fn add_config_version(_table: &mut Table) -> Vec<String> {
+    Vec::new()
+}
+
+/// Version a config table was written with (files without `config_version` are version 0)
+pub fn table_version(table: &Table) -> Result<u32> {
+    match table.get("config_version") {
+        None => Ok(0),
+        Some(Value::Integer(v)) if *v >= 0 => Ok(*v as u32),
+        Some(other) => Err(Error::Config(format!(
+            "config_version must be a non-negative integer, not {}",
+            other
+        ))),
+    }
+}
+
+/// Upgrade `table` to `CONFIG_VERSION`, returning a description of every change.
+///
+/// Works on the raw TOML so settings `Config` no longer has a field for can still be
+/// moved to their new place. An empty list means the table was already current.
+pub fn migrate(table: &mut Table) -> Result<Vec<String>> {
+    let version = table_version(table)?;
+    if version > CONFIG_VERSION {
+        return Err(Error::Config(format!(
+            "config_version {} is newer than this build supports ({}); upgrade the payment tools",
+            version, CONFIG_VERSION
+        )));
+    }
+    if version == CONFIG_VERSION {
+        return Ok(Vec::new());
+    }
+
+    let mut notes = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.to > version) {
+        notes.extend((migration.apply)(table));
+    }
+    notes.push(format!(
+        "config_version: set to {} (was {})",
+        CONFIG_VERSION, version
+    ));
+
+    // Keep config_version as the first line of the file
+    let rest = std::mem::take(table);
+    table.insert(
+        "config_version".to_string(),
+        Value::Integer(CONFIG_VERSION as i64),
+    );
+    table.extend(rest.into_iter().filter(|(k, _)| k != "config_version"));
+    Ok(notes)
+}
+
+/// Keys of an `rpc_urls` table entry (`RpcEndpoint` deserializes untagged, so its
+/// fields cannot be read from serde)
+const RPC_ENDPOINT_KEYS: &[&str] = &["url", "timeout_secs"];
+
+/// Keys in `table` that `Config` does not know about, each with a suggestion when one is close
+pub fn unknown_keys(table: &Table) -> Vec<String> {
+    let mut unknown = Vec::new();
+    let sections: Vec<&str> = Config::valid_keys()
+        .iter()
+        .filter_map(|k| k.split_once('.').map(|(section, _)| section))
+        .collect();
+    let top_level: Vec<&str> = [
+        "config_version",
+        "active_env",
+        "profiles",
+        "tokens",
+        "price_feeds",
+        "envs",
+    ]
+    .into_iter()
+    .chain(sections.iter().copied())
+    .collect();
+
+    for (key, value) in table {
+        if !top_level.contains(&key.as_str()) {
+            unknown.push(describe(key, &top_level, ""));
+            continue;
+        }
+        let Value::Table(inner) = value else {
+            continue;
+        };
+        match key.as_str() {
+            "profiles" => {
+                for (name, profile) in inner {
+                    check_table(
+                        profile,
+                        struct_fields::<UserNetworkProfile>(),
+                        &format!("profiles.{}.", name),
+                        &mut unknown,
+                    );
+                }
+            }
+            "tokens" | "price_feeds" => {
+                let known = if key == "tokens" {
+                    struct_fields::<TokenEntry>()
+                } else {
+                    struct_fields::<PriceFeedEntry>()
+                };
+                for (network, entries) in inner {
+                    let Value::Table(entries) = entries else {
+                        continue;
+                    };
+                    for (symbol, entry) in entries {
+                        let prefix = format!("{}.{}.{}.", key, network, symbol);
+                        check_table(entry, known, &prefix, &mut unknown);
+                    }
+                }
+            }
+            "envs" => {
+                for (name, env) in inner {
+                    let Value::Table(env) = env else {
+                        continue;
+                    };
+                    let prefix = format!("envs.{}.", name);
+                    for (section, value) in env {
+                        if sections.contains(&section.as_str()) {
+                            check_section(section, value, &prefix, &mut unknown);
+                        } else {
+                            unknown.push(describe(section, &sections, &prefix));
+                        }
+                    }
+                }
+            }
+            section => check_section(section, value, "", &mut unknown),
+        }
+    }
+    unknown
+}
+
+/// Check the fields of a wallet/network/payment/signer section
+fn check_section(section: &str, value: &Value, prefix: &str, unknown: &mut Vec<String>) {
+    let fields: Vec<&str> = Config::valid_keys()
+        .into_iter()
+        .filter_map(|k| k.strip_prefix(section)?.strip_prefix('.'))
+        .collect();
+    let prefix = format!("{}{}.", prefix, section);
+    check_table(value, &fields, &prefix, unknown);
+    if let Some(Value::Array(endpoints)) = value.get("rpc_urls") {
+        let prefix = format!("{}rpc_urls.", prefix);
+        for endpoint in endpoints {
+            check_table(endpoint, RPC_ENDPOINT_KEYS, &prefix, unknown);
+        }
+    }
+}
+
+fn check_table(value: &Value, known: &[&str], prefix: &str, unknown: &mut Vec<String>) {
+    if let Value::Table(table) = value {
+        for key in table.keys().filter(|k| !known.contains(&k.as_str())) {
+            unknown.push(describe(key, known, prefix));
+        }
+    }
+}
+
+fn describe(key: &str, known: &[&str], prefix: &str) -> String {
+    match known.iter().find(|k| edit_distance(key, k) <= 2) {
+        Some(suggestion) => format!("{}{} (did you mean {}{}?)", prefix, key, prefix, suggestion),
+        None => format!("{}{}", prefix, key),
+    }
+}
+
+/// Levenshtein distance, for "did you mean" suggestions on short key names
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let next = (diagonal + usize::from(ca != *cb))
+                .min(row[j] + 1)
+                .min(row[j + 1] + 1);
+            diagonal = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_version_0() {
+        let mut table: Table = toml::from_str(
+            r#"
+            [network]
+            chain_id = 84532
+            rpc_url = "https://sepolia.base.org"
+            "#,
+        )
+        .unwrap();
+
+        let notes = migrate(&mut table).unwrap();
+        assert_eq!(notes, vec!["config_version: set to 1 (was 0)".to_string()]);
+        assert_eq!(table_version(&table).unwrap(), CONFIG_VERSION);
+        assert_eq!(table.keys().next().unwrap(), "config_version");
+        let config: Config = table.clone().try_into().unwrap();
+        assert_eq!(config.network.chain_id, Some(84532));
+
+        // Already current: nothing to do
+        assert!(migrate(&mut table).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let mut table: Table = toml::from_str("config_version = 999").unwrap();
+        assert!(migrate(&mut table).is_err());
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let table: Table = toml::from_str(
+            r#"
+            config_version = 1
+            colour = "blue"
+
+            [network]
+            rpcurl = "https://sepolia.base.org"
+            chain_id = 84532
+
+            [profiles.devnet]
+            chain_id = 31337
+            rpc_url = "http://127.0.0.1:8545"
+            token = "0x0"
+
+            [tokens.base-sepolia.TEST]
+            address = "0x000000000000000000000000000000000000dEaD"
+            decimals = 18
+
+            [price_feeds.base-sepolia.TEST]
+            address = "0x000000000000000000000000000000000000dEaD"
+            max_age_secs = 3900
+            max_age_sec = 3900
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            unknown_keys(&table),
+            vec![
+                "colour".to_string(),
+                "network.rpcurl (did you mean network.rpc_url?)".to_string(),
+                "profiles.devnet.token".to_string(),
+                "price_feeds.base-sepolia.TEST.max_age_sec (did you mean price_feeds.base-sepolia.TEST.max_age_secs?)"
+                    .to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::rpc;
+use alloy::primitives::{address, hex, keccak256, Address, B256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::sol;
+use std::sync::Arc;
+
+/// ENS registry on Ethereum mainnet
+pub const ENS_REGISTRY: Address = address!("00000000000C2E074eC69A0dFb2997BA6C7d2e1e");
+
+/// Basenames registry on Base mainnet
+pub const BASENAMES_REGISTRY: Address = address!("B94704422c2a1E396835A571837Aa5AE53285a95");
+
+sol! {
+    #[sol(rpc)]
+    contract INameRegistry {
+        function resolver(bytes32 node) external view returns (address);
+    }
+
+    #[sol(rpc)]
+    contract INameResolver {
+        function addr(bytes32 node) external view returns (address);
+        function name(bytes32 node) external view returns (string);
+    }
+}
+
+/// A name registry: ENS for `.eth` names, Basenames for `.base.eth` names
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Registry {
+    Ens,
+    Basenames,
+}
+
+impl Registry {
+    /// Registries in the order reverse lookups try them
+    pub const ALL: [Registry; 2] = [Registry::Ens, Registry::Basenames];
+
+    /// Registry a name is registered in
+    pub fn for_name(name: &str) -> Self {
+        if name.ends_with(".base.eth") {
+            Registry::Basenames
+        } else {
+            Registry::Ens
+        }
+    }
+
+    fn address(self) -> Address {
+        match self {
+            Registry::Ens => ENS_REGISTRY,
+            Registry::Basenames => BASENAMES_REGISTRY,
+        }
+    }
+
+    /// Network profile the registry is deployed on
+    pub fn network(self) -> &'static str {
+        match self {
+            Registry::Ens => "ethereum-mainnet",
+            Registry::Basenames => "base-mainnet",
+        }
+    }
+
+    /// Node holding the reverse record of `address` (ENSIP-19 coin type for Base)
+    fn reverse_node(self, address: Address) -> B256 {
+        let suffix = match self {
+            Registry::Ens => "addr.reverse",
+            Registry::Basenames => "80002105.reverse",
+        };
+        namehash(&format!("{}.{}", hex::encode(address), suffix))
+    }
+}
+
+/// Whether a recipient looks like a name rather than an address
+pub fn is_name(value: &str) -> bool {
+    !value.starts_with("0x") && value.to_ascii_lowercase().ends_with(".eth")
+}
+
+/// Lowercase a name and reject ones this resolver cannot hash correctly.
+///
+/// Full ENSIP-15 normalization (emoji, non-Latin scripts) is not implemented, so
+/// non-ASCII names are refused rather than risk resolving a different name.
+pub fn normalize(name: &str) -> Result<String> {
+    let name = name.trim().to_ascii_lowercase();
+    if !name.is_ascii() {
+        return Err(Error::InvalidArgument(format!(
+            "Only ASCII names are supported: {}",
+            name
+        )));
+    }
+    if name.split('.').any(str::is_empty) {
+        return Err(Error::InvalidArgument(format!("Invalid name: {}", name)));
+    }
+    Ok(name)
+}
+
+/// ENS namehash of a normalized name
+pub fn namehash(name: &str) -> B256 {
+    let mut node = B256::ZERO;
+    if name.is_empty() {
+        return node;
+    }
+    for label in name.rsplit('.') {
+        node = keccak256([node.as_slice(), keccak256(label.as_bytes()).as_slice()].concat());
+    }
+    node
+}
+
+/// A name and the address it belongs to, with a warning if its records disagree
+#[derive(Debug, Clone, PartialEq)]
+pub struct NameCheck {
+    pub name: String,
+    pub address: Address,
+    pub warning: Option<String>,
+}
+
+/// Resolves names on mainnet registries, whatever network payments are made on.
+///
+/// Uses the configured endpoints when the active network is the registry's network,
+/// otherwise the network profile's RPC URL.
+pub struct NameResolver {
+    config: Config,
+    on_failover: Option<rpc::FailoverFn>,
+}
+
+impl NameResolver {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.clone(),
+            on_failover: None,
+        }
+    }
+
+    /// Call `f` when an RPC endpoint fails and a lookup moves on to the next one
+    pub fn on_failover(mut self, f: impl Fn(&rpc::Failover) + Send + Sync + 'static) -> Self {
+        self.on_failover = Some(Arc::new(f));
+        self
+    }
+
+    fn provider(&self, registry: Registry) -> Result<impl Provider> {
+        let config = self.config.for_network(registry.network())?;
+        let on_failover = self.on_failover.clone();
+        let client = rpc::client_from_config(&config, move |failover| {
+            if let Some(f) = &on_failover {
+                f(failover);
+            }
+        })?;
+        Ok(ProviderBuilder::new().connect_client(client))
+    }
+
+    /// Resolver contract for `node`, if one is set
+    async fn resolver<P: Provider>(
+        provider: &P,
+        registry: Registry,
+        node: B256,
+    ) -> Result<Option<Address>> {
+        let resolver = INameRegistry::new(registry.address(), provider)
+            .resolver(node)
+            .call()
+            .await
+            .map_err(|e| Error::Network(format!("Name registry lookup failed: {}", e)))?;
+        Ok((!resolver.is_zero()).then_some(resolver))
+    }
+
+    /// Address a name points to
+    pub async fn resolve(&self, name: &str) -> Result<Address> {
+        let name = normalize(name)?;
+        let registry = Registry::for_name(&name);
+        let provider = self.provider(registry)?;
+        let node = namehash(&name);
+
+        let not_found =
+            || Error::InvalidArgument(format!("{} does not resolve to an address", name));
+        let resolver = Self::resolver(&provider, registry, node)
+            .await?
+            .ok_or_else(not_found)?;
+        let address = INameResolver::new(resolver, &provider)
+            .addr(node)
+            .call()
+            .await
+            .map_err(|e| Error::Network(format!("Resolving {} failed: {}", name, e)))?;
+        if address.is_zero() {
+            return Err(not_found());
+        }
+        Ok(address)
+    }
+
+    /// Name an address's reverse record claims, from the first registry that has one
+    pub async fn lookup(&self, address: Address) -> Result<Option<String>> {
+        let mut error = None;
+        for registry in Registry::ALL {
+            match self.lookup_in(registry, address).await {
+                Ok(Some(name)) => return Ok(Some(name)),
+                Ok(None) => {}
+                Err(e) => error = Some(e),
+            }
+        }
+        error.map_or(Ok(None), Err)
+    }
+
+    async fn lookup_in(&self, registry: Registry, address: Address) -> Result<Option<String>> {
+        let provider = self.provider(registry)?;
+        let node = registry.reverse_node(address);
+        let Some(resolver) = Self::resolver(&provider, registry, node).await? else {
+            return Ok(None);
+        };
+        let name = INameResolver::new(resolver, &provider)
+            .name(node)
+            .call()
+            .await
+            .map_err(|e| Error::Network(format!("Reverse lookup failed: {}", e)))?;
+        Ok((!name.is_empty()).then_some(name))
+    }
+
+    /// Resolve `name`, warning if the address's reverse record in the name's own
+    /// registry names someone else
+    pub async fn resolve_checked(&self, name: &str) -> Result<NameCheck> {
+        let name = normalize(name)?;
+        let registry = Registry::for_name(&name);
+        let address = self.resolve(&name).await?;
+        let warning = match self.lookup_in(registry, address).await {
+            Ok(Some(reverse)) if !reverse.eq_ignore_ascii_case(&name) => Some(format!(
+                "{} resolves to {}, but that address's reverse record is {}",
+                name, address, reverse
+            )),
+            _ => None,
+        };
+        Ok(NameCheck {
+            name,
+            address,
+            warning,
+        })
+    }
+
+    /// Primary name of `address`, warning if the name does not resolve back to it
+    pub async fn primary_name(&self, address: Address) -> Result<Option<NameCheck>> {
+        let Some(name) = self.lookup(address).await? else {
+            return Ok(None);
+        };
+        let warning = match self.resolve(&name).await {
+            Ok(forward) if forward == address => None,
+            Ok(forward) => Some(format!(
+                "{} claims the name {}, but {} resolves to {}",
+                address, name, name, forward
+            )),
+            Err(e) => Some(format!(
+                "{} claims the name {}, but it could not be verified: {}",
+                address, name, e
+            )),
+        };
+        Ok(Some(NameCheck {
+            name,
+            address,
+            warning,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::b256;
+
+    #[test]
+    fn test_namehash() {
+        assert_eq!(namehash(""), B256::ZERO);
+        assert_eq!(
+            namehash("eth"),
+            b256!("93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae")
+        );
+        assert_eq!(
+            namehash("foo.eth"),
+            b256!("de9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f")
+        );
+    }
+
+    #[test]
+    fn test_names() {
+        assert!(is_name("Vitalik.eth"));
+        assert!(is_name("jesse.base.eth"));
+        assert!(!is_name("0x52908400098527886E0F7030069857D2E4169EE7"));
+        assert!(!is_name("@vendor-a"));
+
+        assert_eq!(normalize(" Vitalik.ETH").unwrap(), "vitalik.eth");
+        assert!(normalize("a..eth").is_err());
+        assert!(normalize("café.eth").is_err());
+
+        assert_eq!(Registry::for_name("jesse.base.eth"), Registry::Basenames);
+        assert_eq!(Registry::for_name("vitalik.eth"), Registry::Ens);
+    }
+}
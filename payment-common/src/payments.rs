@@ -0,0 +1,816 @@
+use crate::config::{ResolvedToken, SignerBackend};
+use crate::gas::NATIVE_TRANSFER_GAS;
+use crate::names::{self, NameResolver};
+use crate::pricing::{self, Price};
+use crate::signer::{self, SignerEvent};
+use crate::{rpc, Config, ContactBook, Error};
+use alloy::network::TransactionBuilder;
+use alloy::primitives::utils::format_units;
+use alloy::primitives::{Address, TxHash, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Decimals of the chain's gas token (its symbol comes from the network profile)
+pub const NATIVE_DECIMALS: u8 = 18;
+
+// ERC-20 transfer function
+sol! {
+    #[sol(rpc)]
+    contract IERC20 {
+        function transfer(address to, uint256 amount) external returns (bool);
+        function balanceOf(address account) external view returns (uint256);
+        function decimals() external view returns (uint8);
+    }
+}
+
+/// Resolve a token like [`Config::resolve_token`], reading the decimals of an
+/// unregistered token address from its contract
+pub async fn resolve_token<P: Provider>(
+    config: &Config,
+    token: &str,
+    provider: &P,
+) -> crate::Result<ResolvedToken> {
+    let address = match token.parse::<Address>() {
+        Ok(address) if token.starts_with("0x") => address,
+        _ => return config.resolve_token(token),
+    };
+    if let Some(token) = config.registered_token(address)? {
+        return Ok(token);
+    }
+    let decimals = IERC20::new(address, provider)
+        .decimals()
+        .call()
+        .await
+        .map_err(|e| {
+            Error::InvalidArgument(format!(
+                "Token {} is not registered and its decimals could not be read ({}); \
+                 register it with payment-config add-token <SYMBOL> --address {} --decimals <N>",
+                token, e, token
+            ))
+        })?;
+    Ok(ResolvedToken {
+        address,
+        symbol: None,
+        decimals,
+    })
+}
+
+/// Why a payment failed, with the exit code `pay` reports for it
+#[derive(thiserror::Error, Debug)]
+pub enum PaymentError {
+    #[error("Insufficient balance: {0}")]
+    InsufficientBalance(String),
+
+    #[error("Transaction failed: {0}")]
+    TransactionFailed(String),
+
+    #[error("Network error: {0}")]
+    Network(String),
+
+    #[error("Missing configuration: {0}")]
+    MissingConfig(String),
+
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    #[error("Wallet not found: {0}")]
+    WalletNotFound(String),
+
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("{0}")]
+    Other(String),
+}
+
+impl PaymentError {
+    /// Get the exit code for this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            PaymentError::TransactionFailed(_) => 2,
+            PaymentError::Network(_) => 3,
+            PaymentError::MissingConfig(_) => 10,
+            PaymentError::InvalidConfig(_) => 11,
+            PaymentError::WalletNotFound(_) => 12,
+            PaymentError::InvalidArgument(_) => 20,
+            _ => 1,
+        }
+    }
+}
+
+impl From<Error> for PaymentError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::MissingConfig(msg) => PaymentError::MissingConfig(msg),
+            Error::Config(msg) => PaymentError::InvalidConfig(msg),
+            Error::WalletNotFound(msg) => PaymentError::WalletNotFound(msg),
+            Error::InvalidArgument(msg) => PaymentError::InvalidArgument(msg),
+            // A remote signer or payment-agent that cannot be reached
+            Error::Network(msg) | Error::Signer(msg) => PaymentError::Network(msg),
+            Error::Transaction(msg) => PaymentError::TransactionFailed(msg),
+            e => PaymentError::Other(e.to_string()),
+        }
+    }
+}
+
+/// How much a payment sends
+#[derive(Debug, Clone, PartialEq)]
+pub enum Amount {
+    /// Human-readable token units (e.g. "1.5" for 1.5 USDC)
+    Units(String),
+    /// Smallest token units (e.g. 1500000 for 1.5 USDC)
+    Raw(U256),
+    /// US dollars, converted at the token's current price feed (see `price_feeds`)
+    Usd(String),
+}
+
+/// A step of [`PaymentRequest::send`], reported to its `on_progress` callback
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum PaymentEvent {
+    /// Resolving an ENS name or Basename
+    Resolving(String),
+    /// Something the payer should check; also returned in [`PaymentReceipt::warnings`]
+    Warning(String),
+    /// Sender and recipient, with the recipient's contact label or name
+    Parties {
+        from: Address,
+        to: Address,
+        contact: Option<String>,
+        name: Option<String>,
+    },
+    /// RPC endpoints the payment is sent through
+    Connecting(Vec<String>),
+    /// An RPC endpoint failed and the request moved on to the next one
+    RpcFailover(rpc::Failover),
+    /// How the signer was picked
+    Signer(SignerEvent),
+    /// A USD amount converted at the token's price
+    UsdConversion {
+        usd: String,
+        price: String,
+        symbol: String,
+        amount: String,
+    },
+    /// The amount to send; `symbol` is `None` for native payments
+    Amount {
+        amount: String,
+        symbol: Option<String>,
+        raw: U256,
+        decimals: u8,
+        usd_value: Option<String>,
+    },
+    /// Gas price read from the network, in wei
+    GasPrice(u128),
+    /// Balance checked, about to sign; `token` is `None` for native payments
+    Sending {
+        raw: U256,
+        to: Address,
+        token: Option<Address>,
+    },
+    Sent(TxHash),
+    Waiting,
+    Confirmed(u64),
+}
+
+type ProgressFn = Box<dyn FnMut(&PaymentEvent) + Send>;
+
+/// A payment to make with the configured wallet and network
+///
+/// ```no_run
+/// # async fn example(config: &payment_common::Config) -> Result<(), payment_common::payments::PaymentError> {
+/// use payment_common::payments::{Amount, PaymentRequest};
+///
+/// let receipt = PaymentRequest::new("alice.eth", Amount::Units("1.5".to_string()))
+///     .token("USDC")
+///     .send(config)
+///     .await?;
+/// println!("{}", receipt.tx_hash);
+/// # Ok(())
+/// # }
+/// ```
+pub struct PaymentRequest {
+    to: String,
+    amount: Amount,
+    token: Option<String>,
+    wallet: Option<PathBuf>,
+    password: Option<String>,
+    password_file: Option<PathBuf>,
+    gas_price: Option<u128>,
+    wait: bool,
+    reverse_lookup: bool,
+    contacts: Option<ContactBook>,
+    on_progress: Option<ProgressFn>,
+}
+
+impl fmt::Debug for PaymentRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PaymentRequest")
+            .field("to", &self.to)
+            .field("amount", &self.amount)
+            .field("token", &self.token)
+            .field("wallet", &self.wallet)
+            .field("gas_price", &self.gas_price)
+            .field("wait", &self.wait)
+            .field("reverse_lookup", &self.reverse_lookup)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PaymentRequest {
+    /// Pay `amount` to `to`: an address, ENS name or Basename, or @label from the address book
+    pub fn new(to: impl Into<String>, amount: Amount) -> Self {
+        Self {
+            to: to.into(),
+            amount,
+            token: None,
+            wallet: None,
+            password: None,
+            password_file: None,
+            gas_price: None,
+            wait: true,
+            reverse_lookup: false,
+            contacts: None,
+            on_progress: None,
+        }
+    }
+
+    /// Token registry symbol or contract address (default: the contact's preferred token,
+    /// then the configured default token, then native ETH)
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Wallet keystore to sign with (default: the configured wallet)
+    pub fn wallet(mut self, path: impl Into<PathBuf>) -> Self {
+        self.wallet = Some(path.into());
+        self
+    }
+
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    pub fn password_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.password_file = Some(path.into());
+        self
+    }
+
+    /// Gas price in wei (default: the network's current gas price)
+    pub fn gas_price(mut self, wei: u128) -> Self {
+        self.gas_price = Some(wei);
+        self
+    }
+
+    /// When paying an address, report its primary name (reverse record, checked forward)
+    /// as the receipt's `name`
+    pub fn reverse_lookup(mut self) -> Self {
+        self.reverse_lookup = true;
+        self
+    }
+
+    /// Address book for @labels and address poisoning checks (default: contacts.toml
+    /// next to the config file)
+    pub fn contacts(mut self, contacts: ContactBook) -> Self {
+        self.contacts = Some(contacts);
+        self
+    }
+
+    /// Call `f` with each step of the payment as it happens
+    pub fn on_progress(mut self, f: impl FnMut(&PaymentEvent) + Send + 'static) -> Self {
+        self.on_progress = Some(Box::new(f));
+        self
+    }
+
+    /// Return once the transaction is sent instead of waiting for its receipt
+    pub fn no_wait(mut self) -> Self {
+        self.wait = false;
+        self
+    }
+
+    /// Resolve the recipient, check the balance, then sign and send the transfer
+    /// on `config`'s network
+    pub async fn send(self, config: &Config) -> Result<PaymentReceipt, PaymentError> {
+        let PaymentRequest {
+            to,
+            amount,
+            token,
+            wallet,
+            password,
+            password_file,
+            gas_price,
+            wait,
+            reverse_lookup,
+            contacts,
+            on_progress,
+        } = self;
+        let progress = Progress(on_progress.map(|f| Arc::new(Mutex::new(f))));
+
+        if config.check_network_config().is_err() {
+            return Err(PaymentError::MissingConfig(
+                "Network configuration is incomplete. Run: payment-config use-network <network-name>"
+                    .to_string(),
+            ));
+        }
+
+        // Get RPC endpoints (rpc_url, then the rpc_urls failover list)
+        let client = rpc::client_from_config(config, progress.failover()).map_err(|e| match e {
+            Error::MissingConfig(_) => {
+                PaymentError::MissingConfig("RPC URL not configured".to_string())
+            }
+            e => PaymentError::InvalidConfig(e.to_string()),
+        })?;
+
+        let wallet_path = wallet.unwrap_or_else(|| config.wallet_path());
+        if config.signer.backend == SignerBackend::Local && !wallet_path.exists() {
+            return Err(PaymentError::WalletNotFound(
+                wallet_path.display().to_string(),
+            ));
+        }
+
+        // Resolve the recipient (@label from the address book, a name, or a raw address)
+        let mut warnings = Vec::new();
+        let (to, name) = if names::is_name(&to) {
+            progress.emit(PaymentEvent::Resolving(to.clone()));
+            let check = NameResolver::new(config)
+                .on_failover(progress.failover())
+                .resolve_checked(&to)
+                .await
+                .map_err(|e| match e {
+                    Error::InvalidArgument(_) | Error::Network(_) => PaymentError::from(e),
+                    e => PaymentError::InvalidConfig(e.to_string()),
+                })?;
+            warnings.extend(check.warning);
+            (check.address.to_string(), Some(check.name))
+        } else {
+            (to, None)
+        };
+        // A broken address book only blocks payments that need it
+        let contacts_path = config.contacts_path();
+        let contacts = match contacts
+            .map_or_else(|| ContactBook::load_from(Some(&contacts_path)), Ok)
+        {
+            Ok(contacts) => contacts,
+            Err(e) if !to.starts_with('@') => {
+                warnings.push(format!(
+                    "Could not read {} ({}); contact labels and address poisoning checks are skipped",
+                    contacts_path.display(),
+                    e
+                ));
+                ContactBook::default()
+            }
+            Err(e) => {
+                return Err(PaymentError::InvalidConfig(format!(
+                    "{}: {}",
+                    contacts_path.display(),
+                    e
+                )))
+            }
+        };
+        let recipient = contacts.resolve(&to).map_err(|e| match e {
+            Error::InvalidArgument(msg) => PaymentError::InvalidArgument(msg),
+            e => PaymentError::InvalidConfig(e.to_string()),
+        })?;
+        let to_address = recipient.address;
+        if recipient.label.is_none() {
+            if let Some((label, contact)) = contacts.lookalike(&to_address) {
+                warnings.push(format!(
+                    "{} looks like contact @{} ({}) but is a different address; \
+                     check for address poisoning",
+                    to_address, label, contact.address
+                ));
+            }
+        }
+        for warning in &warnings {
+            progress.emit(PaymentEvent::Warning(warning.clone()));
+        }
+
+        let urls = config.rpc_endpoints().into_iter().map(|e| e.url).collect();
+        progress.emit(PaymentEvent::Connecting(urls));
+        let provider = ProviderBuilder::new().connect_client(client);
+
+        // Verify chain ID if specified
+        if let Some(expected_chain_id) = config.network.chain_id {
+            let actual_chain_id = provider
+                .get_chain_id()
+                .await
+                .map_err(|e| PaymentError::Network(format!("Failed to get chain ID: {}", e)))?;
+            if actual_chain_id != expected_chain_id {
+                return Err(PaymentError::InvalidConfig(format!(
+                    "Chain ID mismatch: expected {}, got {}",
+                    expected_chain_id, actual_chain_id
+                )));
+            }
+        }
+
+        // Get token (requested symbol or address > contact's preferred token > config default)
+        let preferred_token = recipient.contact.as_ref().and_then(|c| c.token.clone());
+        let token: Option<ResolvedToken> = match token.or(preferred_token) {
+            Some(token) => Some(resolve_token(config, &token, &provider).await?),
+            None => config.default_token(),
+        };
+        let token_address = token.as_ref().map(|t| t.address);
+
+        // Without a token the payment is a native transfer
+        let decimals = token.as_ref().map_or(NATIVE_DECIMALS, |t| t.decimals);
+
+        // Pick the signing backend
+        let signer = signer::load_signer(
+            config,
+            &wallet_path,
+            password,
+            password_file.as_deref(),
+            |event| progress.emit(PaymentEvent::Signer(event.clone())),
+        )
+        .await?;
+        let from_address = signer.address();
+
+        progress.emit(PaymentEvent::Parties {
+            from: from_address,
+            to: to_address,
+            contact: recipient.label.clone(),
+            name: name.clone(),
+        });
+
+        // Price the token: required for USD amounts, otherwise only reported as usd_value
+        let symbol = match &token {
+            Some(token) => token.symbol.clone(),
+            None => Some(config.native_symbol()),
+        };
+        let usd = matches!(amount, Amount::Usd(_));
+        let price = match &symbol {
+            Some(symbol) => match pricing::current_price(&provider, config, symbol).await {
+                Ok(price) => price,
+                Err(e) if usd => return Err(e.into()),
+                Err(e) => {
+                    let warning = format!("No USD value for {}: {}", symbol, e);
+                    progress.warn(&mut warnings, warning);
+                    None
+                }
+            },
+            None if usd => {
+                return Err(PaymentError::InvalidArgument(
+                    "USD amounts need a token with a known symbol".to_string(),
+                ))
+            }
+            None => None,
+        };
+
+        // Convert the amount to blockchain units
+        let (amount, human_amount) = match amount {
+            Amount::Usd(usd) => {
+                let symbol = symbol.as_deref().unwrap_or_default();
+                let network = config.network.name.as_deref();
+                let price = price.as_ref().ok_or_else(|| {
+                    PaymentError::MissingConfig(format!(
+                        "No USD price feed for {} on {}. Add one under [price_feeds.{}.{}] in config.toml",
+                        symbol,
+                        network.unwrap_or("this network"),
+                        network.unwrap_or("<network>"),
+                        symbol
+                    ))
+                })?;
+                let amount = price.amount_for_usd(&usd, decimals)?;
+                let human = format_amount(amount, decimals);
+                progress.emit(PaymentEvent::UsdConversion {
+                    usd,
+                    price: price.usd(),
+                    symbol: symbol.to_string(),
+                    amount: human.clone(),
+                });
+                (amount, human)
+            }
+            Amount::Units(human) => {
+                let amount = human_to_raw(&human, decimals).map_err(|e| {
+                    PaymentError::InvalidArgument(format!("Invalid amount '{}': {}", human, e))
+                })?;
+                (amount, human)
+            }
+            Amount::Raw(amount) => (amount, format_amount(amount, decimals)),
+        };
+        let usd_value = price.as_ref().map(|p| p.usd_value(amount, decimals));
+
+        progress.emit(PaymentEvent::Amount {
+            amount: human_amount.clone(),
+            symbol: symbol.clone().filter(|_| token.is_some()),
+            raw: amount,
+            decimals,
+            usd_value: usd_value.clone(),
+        });
+
+        let gas_price_wei = match gas_price {
+            Some(wei) => wei,
+            None => {
+                let price = provider.get_gas_price().await.map_err(|e| {
+                    PaymentError::Network(format!("Failed to get gas price: {}", e))
+                })?;
+                progress.emit(PaymentEvent::GasPrice(price));
+                price
+            }
+        };
+
+        // Build transaction
+        let tx = if let Some(token_addr) = token_address {
+            // ERC-20 transfer
+            // Check token balance first
+            let token_contract = IERC20::new(token_addr, &provider);
+            let balance = token_contract
+                .balanceOf(from_address)
+                .call()
+                .await
+                .map_err(|e| {
+                    PaymentError::Network(format!("Failed to get token balance: {}", e))
+                })?;
+
+            if balance < amount {
+                return Err(PaymentError::InsufficientBalance(format!(
+                    "Token balance {} is less than amount {}",
+                    balance, amount
+                )));
+            }
+
+            TransactionRequest::default()
+                .with_from(from_address)
+                .with_to(token_addr)
+                .with_input(IERC20::transferCall::new((to_address, amount)).abi_encode())
+                .with_gas_price(gas_price_wei)
+        } else {
+            // Native ETH transfer
+            // Check ETH balance first
+            let balance = provider
+                .get_balance(from_address)
+                .await
+                .map_err(|e| PaymentError::Network(format!("Failed to get balance: {}", e)))?;
+
+            let gas_limit = U256::from(NATIVE_TRANSFER_GAS);
+            let total_cost = amount + (gas_limit * U256::from(gas_price_wei));
+
+            if balance < total_cost {
+                return Err(PaymentError::InsufficientBalance(format!(
+                    "Balance {} is less than amount + gas ({})",
+                    balance, total_cost
+                )));
+            }
+
+            TransactionRequest::default()
+                .with_from(from_address)
+                .with_to(to_address)
+                .with_value(amount)
+                .with_gas_price(gas_price_wei)
+        };
+
+        progress.emit(PaymentEvent::Sending {
+            raw: amount,
+            to: to_address,
+            token: token_address,
+        });
+
+        // Fill nonce and gas limit, sign with the configured backend and broadcast
+        let pending_tx = signer::send_transaction(&provider, signer.as_ref(), tx).await?;
+
+        let tx_hash = *pending_tx.tx_hash();
+        progress.emit(PaymentEvent::Sent(tx_hash));
+
+        let mut block_number = None;
+        let mut gas_usd_value = None;
+        if wait {
+            progress.emit(PaymentEvent::Waiting);
+            let receipt = pending_tx
+                .get_receipt()
+                .await
+                .map_err(|e| PaymentError::TransactionFailed(e.to_string()))?;
+
+            if !receipt.status() {
+                return Err(PaymentError::TransactionFailed(
+                    "Transaction reverted".to_string(),
+                ));
+            }
+            progress.emit(PaymentEvent::Confirmed(
+                receipt.block_number.unwrap_or_default(),
+            ));
+            block_number = receipt.block_number;
+
+            let gas_cost = U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price);
+            gas_usd_value = match gas_price_usd(&provider, config, token.is_none(), &price).await {
+                Ok(Some(eth)) => Some(eth.usd_value(gas_cost, NATIVE_DECIMALS)),
+                Ok(None) => None,
+                Err(e) => {
+                    let warning = format!("No USD value for gas: {}", e);
+                    progress.warn(&mut warnings, warning);
+                    None
+                }
+            };
+        }
+
+        // Name the recipient for the record once the payment is out of the way
+        let name = match name {
+            Some(name) => Some(name),
+            None if reverse_lookup => {
+                match NameResolver::new(config)
+                    .on_failover(progress.failover())
+                    .primary_name(to_address)
+                    .await
+                {
+                    Ok(Some(check)) if check.warning.is_none() => Some(check.name),
+                    Ok(Some(check)) => {
+                        if let Some(warning) = check.warning {
+                            progress.warn(&mut warnings, warning);
+                        }
+                        None
+                    }
+                    Ok(None) => None,
+                    Err(e) => {
+                        progress.warn(&mut warnings, format!("Could not look up name: {}", e));
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
+        Ok(PaymentReceipt {
+            tx_hash,
+            confirmed: wait,
+            from: from_address,
+            to: to_address,
+            contact: recipient.label,
+            name,
+            amount: human_amount,
+            raw_amount: amount,
+            usd_value,
+            token,
+            network: config.network.name.clone(),
+            block_number,
+            gas_usd_value,
+            warnings,
+        })
+    }
+}
+
+/// A sent payment
+#[derive(Debug, Clone)]
+pub struct PaymentReceipt {
+    pub tx_hash: TxHash,
+    /// Whether the transaction was mined successfully (false when sent with `no_wait`)
+    pub confirmed: bool,
+    pub from: Address,
+    pub to: Address,
+    /// Address book label the recipient was given as
+    pub contact: Option<String>,
+    /// ENS name or Basename the recipient was resolved from, or with `reverse_lookup`
+    /// its verified primary name
+    pub name: Option<String>,
+    /// Human-readable amount
+    pub amount: String,
+    pub raw_amount: U256,
+    pub usd_value: Option<String>,
+    /// Token transferred (`None` for native ETH)
+    pub token: Option<ResolvedToken>,
+    pub network: Option<String>,
+    pub block_number: Option<u64>,
+    /// USD value of the gas the confirmed transaction paid
+    pub gas_usd_value: Option<String>,
+    pub warnings: Vec<String>,
+}
+
+/// The caller's progress callback, if any, shared with the RPC transports
+#[derive(Clone)]
+struct Progress(Option<Arc<Mutex<ProgressFn>>>);
+
+impl Progress {
+    fn emit(&self, event: PaymentEvent) {
+        if let Some(f) = &self.0 {
+            let mut f = f.lock().unwrap_or_else(|e| e.into_inner());
+            f(&event);
+        }
+    }
+
+    fn warn(&self, warnings: &mut Vec<String>, warning: String) {
+        self.emit(PaymentEvent::Warning(warning.clone()));
+        warnings.push(warning);
+    }
+
+    /// Failover callback reporting [`PaymentEvent::RpcFailover`]
+    fn failover(&self) -> impl Fn(&rpc::Failover) + Send + Sync + 'static {
+        let progress = self.clone();
+        move |failover| progress.emit(PaymentEvent::RpcFailover(failover.clone()))
+    }
+}
+
+/// Price of the gas token: reuse the payment's price for native payments
+async fn gas_price_usd<P: Provider>(
+    provider: &P,
+    config: &Config,
+    native: bool,
+    price: &Option<Price>,
+) -> crate::Result<Option<Price>> {
+    if native {
+        return Ok(price.clone());
+    }
+    pricing::current_price(provider, config, &config.native_symbol()).await
+}
+
+/// Format raw units as a human-readable amount without trailing zeros
+fn format_amount(raw: U256, decimals: u8) -> String {
+    let formatted = format_units(raw, decimals).unwrap_or_else(|_| raw.to_string());
+    match formatted.split_once('.') {
+        Some((integer, fraction)) if fraction.trim_end_matches('0').is_empty() => {
+            integer.to_string()
+        }
+        Some(_) => formatted.trim_end_matches('0').to_string(),
+        None => formatted,
+    }
+}
+
+/// Convert human-readable amount to raw blockchain units
+fn human_to_raw(human: &str, decimals: u8) -> Result<U256, String> {
+    let decimals = decimals as usize;
+    let human = human.trim();
+
+    if human.is_empty() {
+        return Err("Amount cannot be empty".to_string());
+    }
+
+    // Handle both integer and decimal inputs
+    let (integer_part, decimal_part) = if let Some(pos) = human.find('.') {
+        let (int_str, dec_str) = human.split_at(pos);
+        let int_part = if int_str.is_empty() { "0" } else { int_str };
+        (int_part.to_string(), dec_str[1..].to_string()) // Skip the '.'
+    } else {
+        (human.to_string(), String::new())
+    };
+
+    // Validate parts are numeric
+    if !integer_part.chars().all(|c| c.is_ascii_digit()) || integer_part.is_empty() {
+        return Err("Invalid integer part".to_string());
+    }
+    if !decimal_part.chars().all(|c| c.is_ascii_digit()) {
+        return Err("Invalid decimal part".to_string());
+    }
+
+    // Pad or truncate decimal part to match decimals
+    let decimal_padded = if decimal_part.len() < decimals {
+        format!("{:0<width$}", decimal_part, width = decimals)
+    } else if decimal_part.len() > decimals {
+        // Truncate (could also error here)
+        decimal_part[..decimals].to_string()
+    } else {
+        decimal_part
+    };
+
+    // Combine integer and decimal parts
+    let raw_str = format!("{}{}", integer_part, decimal_padded);
+
+    // Remove leading zeros but keep at least one digit
+    let raw_trimmed = raw_str.trim_start_matches('0');
+    let raw_final = if raw_trimmed.is_empty() {
+        "0"
+    } else {
+        raw_trimmed
+    };
+
+    raw_final
+        .parse::<U256>()
+        .map_err(|e| format!("Failed to parse amount: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_human_to_raw() {
+        // Test with 6 decimals (USDC)
+        assert_eq!(human_to_raw("1", 6).unwrap(), U256::from(1_000_000u64));
+        assert_eq!(human_to_raw("1.0", 6).unwrap(), U256::from(1_000_000u64));
+        assert_eq!(human_to_raw("1.5", 6).unwrap(), U256::from(1_500_000u64));
+        assert_eq!(human_to_raw("0.5", 6).unwrap(), U256::from(500_000u64));
+        assert_eq!(human_to_raw(".5", 6).unwrap(), U256::from(500_000u64));
+        assert_eq!(human_to_raw("0.000001", 6).unwrap(), U256::from(1u64));
+        assert_eq!(human_to_raw("100", 6).unwrap(), U256::from(100_000_000u64));
+        assert_eq!(human_to_raw("0", 6).unwrap(), U256::from(0u64));
+        assert_eq!(human_to_raw("0.0", 6).unwrap(), U256::from(0u64));
+        assert_eq!(
+            human_to_raw("1.123456", 6).unwrap(),
+            U256::from(1_123_456u64)
+        );
+        // Test truncation of extra decimals
+        assert_eq!(
+            human_to_raw("1.1234567", 6).unwrap(),
+            U256::from(1_123_456u64)
+        );
+    }
+    #[test]
+    fn test_format_amount() {
+        assert_eq!(format_amount(U256::from(2_500_250u64), 6), "2.50025");
+        assert_eq!(format_amount(U256::from(2_000_000u64), 6), "2");
+        assert_eq!(format_amount(U256::ZERO, 18), "0");
+    }
+}
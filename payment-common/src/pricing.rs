@@ -0,0 +1,294 @@
+use crate::config::{Config, PriceFeedEntry};
+use crate::error::{Error, Result};
+use alloy::eips::BlockId;
+use alloy::primitives::utils::parse_units;
+use alloy::primitives::{Address, U256};
+use alloy::providers::Provider;
+use alloy::sol;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Default for `payment.max_price_age_secs`: most feeds update at least hourly
+pub const DEFAULT_MAX_PRICE_AGE_SECS: u64 = 3_900;
+
+sol! {
+    #[sol(rpc)]
+    contract AggregatorV3Interface {
+        function decimals() external view returns (uint8);
+        function description() external view returns (string);
+        function latestRoundData() external view returns (
+            uint80 roundId,
+            int256 answer,
+            uint256 startedAt,
+            uint256 updatedAt,
+            uint80 answeredInRound
+        );
+    }
+}
+
+/// A USD price read from a Chainlink aggregator
+#[derive(Debug, Clone, PartialEq)]
+pub struct Price {
+    pub symbol: String,
+    pub feed: Address,
+    /// Price in USD scaled by `10^decimals`
+    pub answer: U256,
+    pub decimals: u8,
+    pub updated_at: u64,
+}
+
+impl Price {
+    /// The price as a decimal string at the feed's precision (e.g. "3012.55", "0.9999")
+    pub fn usd(&self) -> String {
+        let places = (self.decimals as usize).max(2);
+        let mut price = format_scaled(self.answer, self.decimals, places);
+        // Keep at least the cents
+        let min_len = price.len() - places + 2;
+        while price.len() > min_len && price.ends_with('0') {
+            price.pop();
+        }
+        price
+    }
+
+    /// USD value of `raw` token units, rounded to the nearest cent
+    pub fn usd_value(&self, raw: U256, token_decimals: u8) -> String {
+        let scale = pow10(token_decimals as usize + self.decimals as usize);
+        let cents = (raw
+            .saturating_mul(self.answer)
+            .saturating_mul(U256::from(100))
+            + scale / U256::from(2))
+            / scale;
+        format_scaled(cents, 2, 2)
+    }
+
+    /// Raw token units worth `usd` dollars (e.g. "2.50"), rounded to the nearest unit
+    pub fn amount_for_usd(&self, usd: &str, token_decimals: u8) -> Result<U256> {
+        let invalid = |reason: &str| {
+            Error::InvalidArgument(format!("Invalid USD amount '{}': {}", usd, reason))
+        };
+        let cents_part = usd.split_once('.').map_or("", |(_, c)| c);
+        if usd.starts_with('-') || cents_part.len() > 2 {
+            return Err(invalid(
+                "expected a positive amount with at most 2 decimals",
+            ));
+        }
+        let cents: U256 = parse_units(usd, 2)
+            .map_err(|e| invalid(&e.to_string()))?
+            .into();
+        if cents.is_zero() {
+            return Err(invalid("must be greater than zero"));
+        }
+        let numerator = cents * pow10(token_decimals as usize + self.decimals as usize);
+        let denominator = self.answer * U256::from(100);
+        Ok((numerator + denominator / U256::from(2)) / denominator)
+    }
+}
+
+/// Read the `<symbol> / USD` price from `feed`.
+///
+/// The answer must be positive and no older than `max_age_secs` relative to
+/// `reference_time` (now, or the block timestamp for historical reads), and the feed's
+/// description must name the symbol so a misconfigured address is caught.
+pub async fn fetch_price<P: Provider>(
+    provider: &P,
+    symbol: &str,
+    feed: Address,
+    block: BlockId,
+    reference_time: u64,
+    max_age_secs: u64,
+) -> Result<Price> {
+    let aggregator = AggregatorV3Interface::new(feed, provider);
+    let failed = |e: alloy::contract::Error| {
+        Error::Network(format!("Price feed {} for {} failed: {}", feed, symbol, e))
+    };
+
+    let description = aggregator
+        .description()
+        .block(block)
+        .call()
+        .await
+        .map_err(failed)?;
+    let expected = format!("{} / USD", symbol.to_ascii_uppercase());
+    if !description.eq_ignore_ascii_case(&expected) {
+        return Err(Error::Config(format!(
+            "Price feed {} is '{}', not '{}'",
+            feed, description, expected
+        )));
+    }
+    let decimals = aggregator
+        .decimals()
+        .block(block)
+        .call()
+        .await
+        .map_err(failed)?;
+    let round = aggregator
+        .latestRoundData()
+        .block(block)
+        .call()
+        .await
+        .map_err(failed)?;
+
+    let answer = U256::try_from(round.answer)
+        .ok()
+        .filter(|a| !a.is_zero())
+        .ok_or_else(|| {
+            Error::Network(format!(
+                "Price feed {} returned an invalid price: {}",
+                feed, round.answer
+            ))
+        })?;
+    let updated_at = u64::try_from(round.updatedAt).unwrap_or(u64::MAX);
+    let age = reference_time.saturating_sub(updated_at);
+    if age > max_age_secs {
+        return Err(Error::Network(format!(
+            "{} price is stale: last updated {}s ago (max {}s, see the feed's max_age_secs)",
+            symbol, age, max_age_secs
+        )));
+    }
+
+    Ok(Price {
+        symbol: symbol.to_string(),
+        feed,
+        answer,
+        decimals,
+        updated_at,
+    })
+}
+
+/// Current price of `symbol` on the active network, if a feed is configured for it
+pub async fn current_price<P: Provider>(
+    provider: &P,
+    config: &Config,
+    symbol: &str,
+) -> Result<Option<Price>> {
+    let Some(feed) = feed_address(config, symbol)? else {
+        return Ok(None);
+    };
+    fetch_price(
+        provider,
+        symbol,
+        feed,
+        BlockId::latest(),
+        now(),
+        max_price_age(config, symbol),
+    )
+    .await
+    .map(Some)
+}
+
+/// Feed address for `symbol` on the active network
+pub fn feed_address(config: &Config, symbol: &str) -> Result<Option<Address>> {
+    let Some(entry) = feed_entry(config, symbol) else {
+        return Ok(None);
+    };
+    entry
+        .address
+        .parse()
+        .map(Some)
+        .map_err(|_| Error::Config(format!("Invalid price feed address: {}", entry.address)))
+}
+
+/// Oldest answer accepted from the `symbol` feed: its own `max_age_secs`, then
+/// `payment.max_price_age_secs`, then the default
+pub fn max_price_age(config: &Config, symbol: &str) -> u64 {
+    feed_entry(config, symbol)
+        .and_then(|entry| entry.max_age_secs)
+        .or(config.payment.max_price_age_secs)
+        .unwrap_or(DEFAULT_MAX_PRICE_AGE_SECS)
+}
+
+fn feed_entry(config: &Config, symbol: &str) -> Option<PriceFeedEntry> {
+    let network = config.network.name.as_deref()?;
+    config
+        .price_feeds(network)
+        .into_iter()
+        .find(|(s, _)| s.eq_ignore_ascii_case(symbol))
+        .map(|(_, entry)| entry)
+}
+
+/// Current Unix time in seconds
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Sum of USD values as returned by `Price::usd_value`
+pub fn sum_usd<'a>(values: impl IntoIterator<Item = &'a str>) -> String {
+    let cents = values
+        .into_iter()
+        .filter_map(|v| parse_units(v, 2).ok())
+        .map(|v| v.get_absolute())
+        .fold(U256::ZERO, |total, v| total + v);
+    format_scaled(cents, 2, 2)
+}
+
+fn pow10(exponent: usize) -> U256 {
+    U256::from(10).pow(U256::from(exponent))
+}
+
+/// Format `value / 10^decimals` with exactly `places` decimals (rounded down)
+fn format_scaled(value: U256, decimals: u8, places: usize) -> String {
+    let decimals = decimals as usize;
+    let scaled = if decimals >= places {
+        value / pow10(decimals - places)
+    } else {
+        value * pow10(places - decimals)
+    };
+    let digits = format!("{:0>width$}", scaled, width = places + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - places);
+    format!("{}.{}", integer, fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eth_price() -> Price {
+        Price {
+            symbol: "ETH".to_string(),
+            feed: Address::ZERO,
+            // $3012.55 with 8 decimals
+            answer: U256::from(301_255_000_000u64),
+            decimals: 8,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_usd_value() {
+        let price = eth_price();
+        assert_eq!(price.usd(), "3012.55");
+        // 0.5 ETH
+        let half = U256::from(500_000_000_000_000_000u64);
+        assert_eq!(price.usd_value(half, 18), "1506.28");
+        assert_eq!(price.usd_value(U256::ZERO, 18), "0.00");
+        assert_eq!(sum_usd(["1506.27", "2.50", "0.03"]), "1508.80");
+    }
+
+    #[test]
+    fn test_amount_for_usd() {
+        let price = eth_price();
+        // $3012.55 buys exactly 1 ETH
+        assert_eq!(
+            price.amount_for_usd("3012.55", 18).unwrap(),
+            U256::from(1_000_000_000_000_000_000u64)
+        );
+
+        let usdc = Price {
+            symbol: "USDC".to_string(),
+            answer: U256::from(99_990_000u64),
+            ..eth_price()
+        };
+        assert_eq!(usdc.usd(), "0.9999");
+        // $2.50 at $0.9999 is 2.500250 USDC, and back
+        assert_eq!(usdc.usd_value(U256::from(2_500_250u64), 6), "2.50");
+        assert_eq!(
+            usdc.amount_for_usd("2.50", 6).unwrap(),
+            U256::from(2_500_250u64)
+        );
+        assert!(usdc.amount_for_usd("2.505", 6).is_err());
+        assert!(usdc.amount_for_usd("-1", 6).is_err());
+        assert!(usdc.amount_for_usd("0", 6).is_err());
+    }
+}
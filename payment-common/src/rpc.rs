@@ -0,0 +1,297 @@
+use crate::config::{Config, RpcEndpoint};
+use crate::error::{Error, Result};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::client::RpcClient;
+use alloy::rpc::json_rpc::{RequestPacket, ResponsePacket};
+use alloy::transports::http::{reqwest, Http};
+use alloy::transports::{TransportError, TransportFut};
+use serde::Serialize;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower::Service;
+
+/// Request timeout for endpoints that do not configure one
+pub const DEFAULT_RPC_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Methods that broadcast a transaction and must never be sent to a second endpoint
+const BROADCAST_METHODS: &[&str] = &[
+    "eth_sendRawTransaction",
+    "eth_sendRawTransactionSync",
+    "eth_sendTransaction",
+];
+
+struct Endpoint {
+    url: String,
+    transport: Http<reqwest::Client>,
+}
+
+/// An endpoint that failed a read request, which moves on to the next endpoint
+#[derive(Debug, Clone)]
+pub struct Failover {
+    pub url: String,
+    pub error: String,
+}
+
+impl fmt::Display for Failover {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RPC endpoint {} failed: {}", self.url, self.error)
+    }
+}
+
+/// Callback told about each [`Failover`]
+pub type FailoverFn = Arc<dyn Fn(&Failover) + Send + Sync>;
+
+/// HTTP transport over an ordered list of RPC endpoints.
+///
+/// Requests go to the endpoint that last answered. When it fails at the transport
+/// level (connection error, timeout, HTTP error status), read requests move on to
+/// the next endpoint. Broadcasts are sent to the active endpoint only, so a signed
+/// transaction is never submitted twice; their errors are returned as-is.
+#[derive(Clone)]
+pub struct FailoverTransport {
+    endpoints: Arc<Vec<Endpoint>>,
+    active: Arc<AtomicUsize>,
+    on_failover: Option<FailoverFn>,
+}
+
+impl FailoverTransport {
+    /// Build a transport over `endpoints`, using `default_timeout` where an endpoint sets none
+    pub fn new(endpoints: &[RpcEndpoint], default_timeout: Duration) -> Result<Self> {
+        if endpoints.is_empty() {
+            return Err(Error::MissingConfig("network.rpc_url".to_string()));
+        }
+
+        let endpoints = endpoints
+            .iter()
+            .map(|endpoint| {
+                let url = endpoint
+                    .url
+                    .parse()
+                    .map_err(|_| Error::Config(format!("Invalid RPC URL: {}", endpoint.url)))?;
+                let timeout = endpoint
+                    .timeout_secs
+                    .map(Duration::from_secs)
+                    .unwrap_or(default_timeout);
+                let client = reqwest::Client::builder()
+                    .timeout(timeout)
+                    .build()
+                    .map_err(|e| Error::Network(format!("Failed to build HTTP client: {}", e)))?;
+                Ok(Endpoint {
+                    url: endpoint.url.clone(),
+                    transport: Http::with_client(client, url),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            endpoints: Arc::new(endpoints),
+            active: Arc::new(AtomicUsize::new(0)),
+            on_failover: None,
+        })
+    }
+
+    /// Call `f` whenever an endpoint fails and the request moves on to the next one
+    pub fn on_failover(mut self, f: impl Fn(&Failover) + Send + Sync + 'static) -> Self {
+        self.on_failover = Some(Arc::new(f));
+        self
+    }
+
+    /// URL of the endpoint requests are currently sent to
+    pub fn active_url(&self) -> &str {
+        &self.endpoints[self.active.load(Ordering::Relaxed)].url
+    }
+
+    async fn dispatch(
+        self,
+        request: RequestPacket,
+    ) -> std::result::Result<ResponsePacket, TransportError> {
+        let start = self.active.load(Ordering::Relaxed);
+
+        if request
+            .method_names()
+            .any(|method| BROADCAST_METHODS.contains(&method))
+        {
+            let mut transport = self.endpoints[start].transport.clone();
+            return transport.call(request).await;
+        }
+
+        let count = self.endpoints.len();
+        let mut last_error = None;
+        for offset in 0..count {
+            let index = (start + offset) % count;
+            let endpoint = &self.endpoints[index];
+            let mut transport = endpoint.transport.clone();
+            match transport.call(request.clone()).await {
+                Ok(response) => {
+                    self.active.store(index, Ordering::Relaxed);
+                    return Ok(response);
+                }
+                Err(e) => {
+                    if let Some(on_failover) = self.on_failover.as_ref().filter(|_| count > 1) {
+                        on_failover(&Failover {
+                            url: endpoint.url.clone(),
+                            error: e.to_string(),
+                        });
+                    }
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.expect("at least one endpoint"))
+    }
+}
+
+impl Service<RequestPacket> for FailoverTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        Box::pin(self.clone().dispatch(request))
+    }
+}
+
+/// Build an RPC client over the configured endpoints, telling `on_failover` about
+/// endpoints that fail
+pub fn client_from_config(
+    config: &Config,
+    on_failover: impl Fn(&Failover) + Send + Sync + 'static,
+) -> Result<RpcClient> {
+    let endpoints = config.rpc_endpoints();
+    let timeout = config
+        .network
+        .rpc_timeout_secs
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_RPC_TIMEOUT);
+    Ok(RpcClient::new(
+        FailoverTransport::new(&endpoints, timeout)?.on_failover(on_failover),
+        false,
+    ))
+}
+
+/// Result of probing one RPC endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointHealth {
+    pub url: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Ask a single endpoint for its chain ID and measure how long it takes
+pub async fn check_endpoint(endpoint: &RpcEndpoint, default_timeout: Duration) -> EndpointHealth {
+    let mut health = EndpointHealth {
+        url: endpoint.url.clone(),
+        ok: false,
+        chain_id: None,
+        latency_ms: None,
+        error: None,
+    };
+
+    let client = match FailoverTransport::new(std::slice::from_ref(endpoint), default_timeout) {
+        Ok(transport) => RpcClient::new(transport, false),
+        Err(e) => {
+            health.error = Some(e.to_string());
+            return health;
+        }
+    };
+    let provider = ProviderBuilder::new().connect_client(client);
+
+    let started = Instant::now();
+    match provider.get_chain_id().await {
+        Ok(chain_id) => {
+            health.ok = true;
+            health.chain_id = Some(chain_id);
+            health.latency_ms = Some(started.elapsed().as_millis() as u64);
+        }
+        Err(e) => health.error = Some(e.to_string()),
+    }
+    health
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Serve `eth_chainId` (and count every request) on a local port
+    fn spawn_rpc(requests: Arc<AtomicUsize>) -> String {
+        test_support::server::spawn_json_rpc(move |request| {
+            requests.fetch_add(1, Ordering::SeqCst);
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": "0x14a34",
+            })
+        })
+    }
+
+    /// A URL on which nothing is listening
+    fn dead_url() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_reads_fail_over_to_next_endpoint() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let endpoints = [
+            RpcEndpoint::new(dead_url()),
+            RpcEndpoint::new(spawn_rpc(Arc::clone(&requests))),
+        ];
+        let failed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let transport = FailoverTransport::new(&endpoints, Duration::from_secs(5))
+            .unwrap()
+            .on_failover({
+                let failed = Arc::clone(&failed);
+                move |failover| failed.lock().unwrap().push(failover.url.clone())
+            });
+        let provider =
+            ProviderBuilder::new().connect_client(RpcClient::new(transport.clone(), false));
+
+        assert_eq!(provider.get_chain_id().await.unwrap(), 84532);
+        assert_eq!(transport.active_url(), endpoints[1].url);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert_eq!(*failed.lock().unwrap(), [endpoints[0].url.clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_is_never_retried() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let endpoints = [
+            RpcEndpoint::new(dead_url()),
+            RpcEndpoint::new(spawn_rpc(Arc::clone(&requests))),
+        ];
+        let transport = FailoverTransport::new(&endpoints, Duration::from_secs(5)).unwrap();
+        let client = RpcClient::new(transport, false);
+
+        let result: std::result::Result<String, _> =
+            client.request("eth_sendRawTransaction", ("0x01",)).await;
+        assert!(result.is_err());
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_check_endpoint() {
+        let url = spawn_rpc(Arc::new(AtomicUsize::new(0)));
+        let health = check_endpoint(&RpcEndpoint::new(url), Duration::from_secs(5)).await;
+        assert!(health.ok);
+        assert_eq!(health.chain_id, Some(84532));
+
+        let health = check_endpoint(&RpcEndpoint::new(dead_url()), Duration::from_secs(5)).await;
+        assert!(!health.ok);
+        assert!(health.error.is_some());
+    }
+}
@@ -0,0 +1,213 @@
+use crate::error::{Error, Result};
+use crate::write_private;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use alloy::primitives::hex;
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::IsTerminal;
+use std::path::Path;
+
+/// Environment variable holding the machine passphrase
+pub const PASSPHRASE_ENV: &str = "PAYMENT_PASSPHRASE";
+
+/// Environment variable naming a file descriptor to read the machine passphrase from
+pub const PASSPHRASE_FD_ENV: &str = "PAYMENT_PASSPHRASE_FD";
+
+const SEALED_SECRET_VERSION: u32 = 1;
+
+/// Argon2id cost parameters (OWASP recommended minimum)
+const ARGON2_M_COST_KIB: u32 = 19_456;
+const ARGON2_T_COST: u32 = 2;
+const ARGON2_P_COST: u32 = 1;
+
+/// Argon2id key derivation parameters stored alongside the ciphertext
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub algorithm: String,
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    pub salt: String,
+}
+
+/// A secret encrypted with AES-256-GCM under a key derived from a passphrase with Argon2id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealedSecret {
+    pub version: u32,
+    pub kdf: KdfParams,
+    pub cipher: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+impl SealedSecret {
+    /// Encrypt `secret` under `passphrase`
+    pub fn seal(secret: &str, passphrase: &str) -> Result<Self> {
+        let mut rng = rand::thread_rng();
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 12];
+        rng.fill_bytes(&mut salt);
+        rng.fill_bytes(&mut nonce);
+
+        let kdf = KdfParams {
+            algorithm: "argon2id".to_string(),
+            m_cost: ARGON2_M_COST_KIB,
+            t_cost: ARGON2_T_COST,
+            p_cost: ARGON2_P_COST,
+            salt: hex::encode(salt),
+        };
+        let cipher = Aes256Gcm::new(&derive_key(&kdf, passphrase)?);
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), secret.as_bytes())
+            .map_err(|_| Error::SecretStore("Encryption failed".to_string()))?;
+
+        Ok(Self {
+            version: SEALED_SECRET_VERSION,
+            kdf,
+            cipher: "aes-256-gcm".to_string(),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    /// Decrypt the secret with `passphrase`
+    pub fn open(&self, passphrase: &str) -> Result<String> {
+        if self.version != SEALED_SECRET_VERSION || self.cipher != "aes-256-gcm" {
+            return Err(Error::SecretStore(format!(
+                "Unsupported secret store format (version {}, cipher {})",
+                self.version, self.cipher
+            )));
+        }
+
+        let nonce = decode_hex("nonce", &self.nonce)?;
+        if nonce.len() != 12 {
+            return Err(Error::SecretStore("Invalid nonce length".to_string()));
+        }
+        let ciphertext = decode_hex("ciphertext", &self.ciphertext)?;
+
+        let cipher = Aes256Gcm::new(&derive_key(&self.kdf, passphrase)?);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| {
+                Error::SecretStore("Wrong passphrase or corrupted secret store".to_string())
+            })?;
+
+        String::from_utf8(plaintext)
+            .map_err(|_| Error::SecretStore("Secret is not valid UTF-8".to_string()))
+    }
+
+    /// Load a sealed secret from a JSON file
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Err(Error::SecretStore(format!(
+                "Secret store not found: {}",
+                path.display()
+            )));
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Save the sealed secret as JSON with owner-only permissions
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_private(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+fn derive_key(kdf: &KdfParams, passphrase: &str) -> Result<Key<Aes256Gcm>> {
+    if kdf.algorithm != "argon2id" {
+        return Err(Error::SecretStore(format!(
+            "Unsupported KDF: {}",
+            kdf.algorithm
+        )));
+    }
+    let params = Params::new(kdf.m_cost, kdf.t_cost, kdf.p_cost, Some(32))
+        .map_err(|e| Error::SecretStore(format!("Invalid argon2 parameters: {}", e)))?;
+    let salt = decode_hex("salt", &kdf.salt)?;
+
+    let mut key = Key::<Aes256Gcm>::default();
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|e| Error::SecretStore(format!("Key derivation failed: {}", e)))?;
+    Ok(key)
+}
+
+fn decode_hex(field: &str, value: &str) -> Result<Vec<u8>> {
+    hex::decode(value).map_err(|_| Error::SecretStore(format!("Invalid {} encoding", field)))
+}
+
+/// Get the machine passphrase that protects the secret store.
+///
+/// Sources, in order: `PAYMENT_PASSPHRASE`, the file descriptor named by
+/// `PAYMENT_PASSPHRASE_FD`, or an interactive prompt when stdin is a terminal.
+pub fn machine_passphrase() -> Result<String> {
+    if let Some(passphrase) = secret_from_env(PASSPHRASE_ENV, PASSPHRASE_FD_ENV, "passphrase")? {
+        return Ok(passphrase);
+    }
+
+    if std::io::stdin().is_terminal() {
+        return rpassword::prompt_password("Secret store passphrase: ")
+            .map_err(|e| Error::SecretStore(format!("Failed to read passphrase: {}", e)));
+    }
+
+    Err(Error::SecretStore(format!(
+        "No passphrase available. Set {} or {}, or run interactively",
+        PASSPHRASE_ENV, PASSPHRASE_FD_ENV
+    )))
+}
+
+/// Read a secret from the `value_env` variable, or the first line of the file descriptor
+/// named by `fd_env`; `None` when neither is set
+pub fn secret_from_env(value_env: &str, fd_env: &str, what: &str) -> Result<Option<String>> {
+    if let Ok(value) = std::env::var(value_env) {
+        return Ok(Some(value));
+    }
+
+    if let Ok(fd) = std::env::var(fd_env) {
+        let fd: u32 = fd
+            .parse()
+            .map_err(|_| Error::SecretStore(format!("Invalid {}: {}", fd_env, fd)))?;
+        let content = fs::read_to_string(format!("/dev/fd/{}", fd)).map_err(|e| {
+            Error::SecretStore(format!("Failed to read {} from fd {}: {}", what, fd, e))
+        })?;
+        return Ok(Some(content.lines().next().unwrap_or("").to_string()));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_seal_and_open() {
+        let sealed = SealedSecret::seal("wallet-password", "machine passphrase").unwrap();
+        assert_eq!(sealed.kdf.algorithm, "argon2id");
+        assert!(!sealed.ciphertext.contains(&hex::encode("wallet-password")));
+        assert_eq!(
+            sealed.open("machine passphrase").unwrap(),
+            "wallet-password"
+        );
+        assert!(matches!(
+            sealed.open("wrong passphrase"),
+            Err(Error::SecretStore(_))
+        ));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("password.enc");
+
+        SealedSecret::seal("secret", "pass")
+            .unwrap()
+            .save(&path)
+            .unwrap();
+        let loaded = SealedSecret::load(&path).unwrap();
+        assert_eq!(loaded.open("pass").unwrap(), "secret");
+    }
+}
@@ -0,0 +1,510 @@
+use crate::config::{Config, SignerBackend, SignerConfig};
+use crate::error::{Error, Result};
+use crate::wallet::Wallet;
+use alloy::consensus::{SignableTransaction, TxEnvelope, TypedTransaction};
+use alloy::eips::eip2718::Decodable2718;
+use alloy::network::{Ethereum, EthereumWallet, NetworkWallet};
+use alloy::primitives::{eip191_hash_message, Address, Bytes, Signature, B256};
+use alloy::providers::fillers::{FillProvider, TxFiller};
+use alloy::providers::{PendingTransactionBuilder, Provider};
+use alloy::rpc::client::RpcClient;
+use alloy::rpc::types::TransactionRequest;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::Signer;
+use alloy::transports::http::{reqwest, Http};
+pub use alloy_dyn_abi::TypedData;
+use serde_json::json;
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+/// A backend that can sign transactions and messages for the payment wallet
+#[async_trait::async_trait]
+pub trait PaymentSigner: Send + Sync {
+    /// Address whose key this signer uses
+    fn address(&self) -> Address;
+
+    /// Sign a fully populated transaction (nonce, gas, chain ID already filled)
+    async fn sign_transaction(&self, tx: TypedTransaction) -> Result<TxEnvelope>;
+
+    /// Sign a message with the EIP-191 `personal_sign` prefix
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature>;
+
+    /// Sign EIP-712 typed data
+    async fn sign_typed_data(&self, typed_data: &TypedData) -> Result<Signature>;
+}
+
+/// Signer backed by the local encrypted keystore file
+pub struct KeystoreSigner {
+    signer: PrivateKeySigner,
+    wallet: EthereumWallet,
+}
+
+impl KeystoreSigner {
+    /// Decrypt the keystore at `wallet_path` with `password`
+    pub fn decrypt(wallet_path: &Path, password: &str) -> Result<Self> {
+        let private_key = eth_keystore::decrypt_key(wallet_path, password)
+            .map_err(|e| Error::Wallet(format!("Failed to decrypt wallet: {}", e)))?;
+        let signer = PrivateKeySigner::from_slice(&private_key)
+            .map_err(|e| Error::Wallet(format!("Invalid private key: {}", e)))?;
+        Ok(Self::from(signer))
+    }
+}
+
+impl From<PrivateKeySigner> for KeystoreSigner {
+    fn from(signer: PrivateKeySigner) -> Self {
+        Self {
+            wallet: EthereumWallet::from(signer.clone()),
+            signer,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl PaymentSigner for KeystoreSigner {
+    fn address(&self) -> Address {
+        self.signer.address()
+    }
+
+    async fn sign_transaction(&self, tx: TypedTransaction) -> Result<TxEnvelope> {
+        sign_with_wallet(&self.wallet, self.signer.address(), tx).await
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature> {
+        self.signer
+            .sign_message(message)
+            .await
+            .map_err(signing_error)
+    }
+
+    async fn sign_typed_data(&self, typed_data: &TypedData) -> Result<Signature> {
+        self.signer
+            .sign_dynamic_typed_data(typed_data)
+            .await
+            .map_err(signing_error)
+    }
+}
+
+#[cfg(unix)]
+#[async_trait::async_trait]
+impl PaymentSigner for crate::agent::AgentSigner {
+    fn address(&self) -> Address {
+        Signer::address(self)
+    }
+
+    async fn sign_transaction(&self, tx: TypedTransaction) -> Result<TxEnvelope> {
+        let address = PaymentSigner::address(self);
+        sign_with_wallet(&EthereumWallet::from(self.clone()), address, tx).await
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature> {
+        Signer::sign_message(self, message)
+            .await
+            .map_err(signing_error)
+    }
+
+    async fn sign_typed_data(&self, typed_data: &TypedData) -> Result<Signature> {
+        self.sign_dynamic_typed_data(typed_data)
+            .await
+            .map_err(signing_error)
+    }
+}
+
+fn signing_error(e: alloy::signers::Error) -> Error {
+    Error::Signer(format!("Failed to sign: {}", e))
+}
+
+async fn sign_with_wallet(
+    wallet: &EthereumWallet,
+    from: Address,
+    tx: TypedTransaction,
+) -> Result<TxEnvelope> {
+    NetworkWallet::<Ethereum>::sign_transaction_from(wallet, from, tx)
+        .await
+        .map_err(|e| Error::Signer(format!("Failed to sign transaction: {}", e)))
+}
+
+/// Signer that forwards transactions to a remote signing service speaking
+/// the Web3Signer `eth1` JSON-RPC API (`eth_accounts`, `eth_signTransaction`)
+pub struct RemoteSigner {
+    client: RpcClient,
+    url: String,
+    address: Address,
+}
+
+impl RemoteSigner {
+    /// Connect to the signing service and check that it holds `address`.
+    ///
+    /// When `address` is `None`, the first account reported by the service is used.
+    pub async fn connect(url: &str, address: Option<Address>, timeout: Duration) -> Result<Self> {
+        let parsed = url
+            .parse()
+            .map_err(|_| Error::Config(format!("Invalid signer URL: {}", url)))?;
+        let http_client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| Error::Signer(format!("Failed to build HTTP client: {}", e)))?;
+        let client = RpcClient::new(Http::with_client(http_client, parsed), false);
+
+        let accounts: Vec<Address> = client
+            .request_noparams("eth_accounts")
+            .await
+            .map_err(|e| Error::Signer(format!("Signer at {} is unreachable: {}", url, e)))?;
+
+        let address = match address {
+            Some(address) if accounts.contains(&address) => address,
+            Some(address) => {
+                return Err(Error::Signer(format!(
+                    "Signer at {} does not hold a key for {}",
+                    url, address
+                )))
+            }
+            None => *accounts
+                .first()
+                .ok_or_else(|| Error::Signer(format!("Signer at {} reports no accounts", url)))?,
+        };
+
+        Ok(Self {
+            client,
+            url: url.to_string(),
+            address,
+        })
+    }
+
+    /// URL of the signing service
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Check that a signature returned by the service was made by our key over `hash`
+    fn check_signature(&self, raw: &Bytes, hash: B256) -> Result<Signature> {
+        let signature = Signature::try_from(raw.as_ref()).map_err(|e| {
+            Error::Signer(format!("Remote signer returned invalid signature: {}", e))
+        })?;
+        let signer = signature
+            .recover_address_from_prehash(&hash)
+            .map_err(|e| Error::Signer(format!("Invalid remote signature: {}", e)))?;
+        if signer != self.address {
+            return Err(Error::Signer(format!(
+                "Remote signature is from {}, expected {}",
+                signer, self.address
+            )));
+        }
+        Ok(signature)
+    }
+}
+
+#[async_trait::async_trait]
+impl PaymentSigner for RemoteSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(&self, tx: TypedTransaction) -> Result<TxEnvelope> {
+        let (params, expected_hash) = match &tx {
+            TypedTransaction::Legacy(t) => {
+                let mut params = json!({
+                    "from": self.address,
+                    "gas": format!("{:#x}", t.gas_limit),
+                    "gasPrice": format!("{:#x}", t.gas_price),
+                    "value": format!("{:#x}", t.value),
+                    "data": t.input,
+                    "nonce": format!("{:#x}", t.nonce),
+                });
+                if let Some(to) = t.to.to() {
+                    params["to"] = json!(to);
+                }
+                if let Some(chain_id) = t.chain_id {
+                    params["chainId"] = json!(format!("{:#x}", chain_id));
+                }
+                (params, t.signature_hash())
+            }
+            TypedTransaction::Eip1559(t) => {
+                let mut params = json!({
+                    "from": self.address,
+                    "gas": format!("{:#x}", t.gas_limit),
+                    "maxFeePerGas": format!("{:#x}", t.max_fee_per_gas),
+                    "maxPriorityFeePerGas": format!("{:#x}", t.max_priority_fee_per_gas),
+                    "value": format!("{:#x}", t.value),
+                    "data": t.input,
+                    "nonce": format!("{:#x}", t.nonce),
+                    "chainId": format!("{:#x}", t.chain_id),
+                });
+                if let Some(to) = t.to.to() {
+                    params["to"] = json!(to);
+                }
+                (params, t.signature_hash())
+            }
+            _ => {
+                return Err(Error::Signer(
+                    "Remote signer supports only legacy and EIP-1559 transactions".to_string(),
+                ))
+            }
+        };
+
+        let raw: Bytes = self
+            .client
+            .request("eth_signTransaction", (params,))
+            .await
+            .map_err(|e| Error::Signer(format!("Remote signing failed: {}", e)))?;
+
+        let envelope = TxEnvelope::decode_2718(&mut raw.as_ref())
+            .map_err(|e| Error::Signer(format!("Remote signer returned invalid tx: {}", e)))?;
+
+        // Never broadcast something other than what we asked the service to sign
+        if envelope.signature_hash() != expected_hash {
+            return Err(Error::Signer(
+                "Remote signer returned a different transaction than requested".to_string(),
+            ));
+        }
+        let signer = envelope
+            .signature()
+            .recover_address_from_prehash(&expected_hash)
+            .map_err(|e| Error::Signer(format!("Invalid remote signature: {}", e)))?;
+        if signer != self.address {
+            return Err(Error::Signer(format!(
+                "Remote signature is from {}, expected {}",
+                signer, self.address
+            )));
+        }
+
+        Ok(envelope)
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature> {
+        let raw: Bytes = self
+            .client
+            .request("eth_sign", (self.address, Bytes::copy_from_slice(message)))
+            .await
+            .map_err(|e| Error::Signer(format!("Remote signing failed: {}", e)))?;
+        self.check_signature(&raw, eip191_hash_message(message))
+    }
+
+    async fn sign_typed_data(&self, typed_data: &TypedData) -> Result<Signature> {
+        let hash = typed_data
+            .eip712_signing_hash()
+            .map_err(|e| Error::InvalidArgument(format!("Invalid typed data: {}", e)))?;
+        let raw: Bytes = self
+            .client
+            .request("eth_signTypedData", (self.address, typed_data))
+            .await
+            .map_err(|e| Error::Signer(format!("Remote signing failed: {}", e)))?;
+        self.check_signature(&raw, hash)
+    }
+}
+
+/// Fill the nonce and gas limit of `tx`, sign it with `signer` and broadcast it
+pub async fn send_transaction<F: TxFiller, P: Provider>(
+    provider: &FillProvider<F, P>,
+    signer: &dyn PaymentSigner,
+    tx: TransactionRequest,
+) -> Result<PendingTransactionBuilder<Ethereum>> {
+    let tx = provider
+        .fill(tx)
+        .await
+        .map_err(|e| Error::Network(format!("Failed to prepare transaction: {}", e)))?
+        .try_into_request()
+        .map_err(|_| Error::Transaction("Transaction was unexpectedly signed".to_string()))?
+        .build_typed_tx()
+        .map_err(|_| Error::Transaction("Transaction is missing required fields".to_string()))?;
+    let envelope = signer
+        .sign_transaction(tx)
+        .await
+        .map_err(|e| Error::Transaction(e.to_string()))?;
+    provider
+        .send_tx_envelope(envelope)
+        .await
+        .map_err(|e| Error::Transaction(format!("Failed to send transaction: {}", e)))
+}
+
+/// Build the configured remote signer, if the `[signer]` section selects one
+pub async fn remote_signer_from_config(config: &SignerConfig) -> Result<Option<RemoteSigner>> {
+    if config.backend != SignerBackend::Remote {
+        return Ok(None);
+    }
+
+    let url = config
+        .url
+        .as_deref()
+        .ok_or_else(|| Error::MissingConfig("signer.url".to_string()))?;
+    let address = config
+        .address
+        .as_deref()
+        .map(|a| {
+            a.parse::<Address>()
+                .map_err(|_| Error::Config(format!("Invalid signer.address: {}", a)))
+        })
+        .transpose()?;
+
+    RemoteSigner::connect(url, address, Duration::from_secs(config.timeout_secs))
+        .await
+        .map(Some)
+}
+
+/// How [`load_signer`] picked the signer, reported to its callback
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum SignerEvent {
+    /// Signing with the remote signer at this URL
+    Remote(String),
+    /// Signing with the running payment-agent
+    Agent,
+    /// A payment-agent is running with another wallet, so it is not used
+    AgentMismatch { agent: Address, wallet: Address },
+    /// Decrypting the keystore, which takes a moment
+    Decrypting,
+}
+
+impl SignerEvent {
+    /// Whether the event is something the user should check rather than progress
+    pub fn is_warning(&self) -> bool {
+        matches!(self, Self::AgentMismatch { .. })
+    }
+}
+
+impl fmt::Display for SignerEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Remote(url) => write!(f, "Using remote signer at {}", url),
+            Self::Agent => write!(f, "Using payment-agent for signing"),
+            Self::AgentMismatch { agent, wallet } => write!(
+                f,
+                "payment-agent holds {} but wallet is {}; ignoring agent",
+                agent, wallet
+            ),
+            Self::Decrypting => write!(f, "Decrypting wallet..."),
+        }
+    }
+}
+
+/// Select the signer for the payment wallet, telling `on_event` which one was picked.
+///
+/// Order: the remote signer if `[signer]` selects one, then a running
+/// `payment-agent` holding the same wallet (unless a password was given),
+/// then the keystore at `wallet_path` decrypted with the resolved password.
+pub async fn load_signer(
+    config: &Config,
+    wallet_path: &Path,
+    password: Option<String>,
+    password_file: Option<&Path>,
+    mut on_event: impl FnMut(&SignerEvent),
+) -> Result<Box<dyn PaymentSigner>> {
+    if let Some(remote) = remote_signer_from_config(&config.signer).await? {
+        on_event(&SignerEvent::Remote(remote.url().to_string()));
+        return Ok(Box::new(remote));
+    }
+
+    if !wallet_path.exists() {
+        return Err(Error::WalletNotFound(wallet_path.display().to_string()));
+    }
+
+    if password.is_none() && password_file.is_none() {
+        if let Some(agent) = agent_signer(wallet_path, &mut on_event) {
+            return Ok(agent);
+        }
+    }
+
+    let password = match (password, password_file) {
+        (Some(password), _) => password,
+        (None, Some(path)) => Wallet::load_password(Some(path))?,
+        (None, None) if config.secret_store_path().is_some() || config.password_path().exists() => {
+            config.wallet_password()?
+        }
+        (None, None) => {
+            return Err(Error::InvalidArgument(
+                "No password provided. Use --password, --password-file, configure wallet.password_file, or start payment-agent".to_string(),
+            ))
+        }
+    };
+
+    on_event(&SignerEvent::Decrypting);
+    Ok(Box::new(KeystoreSigner::decrypt(wallet_path, &password)?))
+}
+
+/// Connect to a running payment-agent that holds the key for this wallet
+#[cfg(unix)]
+fn agent_signer(
+    wallet_path: &Path,
+    on_event: &mut impl FnMut(&SignerEvent),
+) -> Option<Box<dyn PaymentSigner>> {
+    let signer = crate::agent::AgentSigner::connect(None).ok()?;
+    let address = PaymentSigner::address(&signer);
+
+    // Only use the agent if it unlocked the same wallet we were asked to sign with
+    let wallet_address: Address = Wallet::get_address(Some(wallet_path)).ok()?.parse().ok()?;
+    if wallet_address != address {
+        on_event(&SignerEvent::AgentMismatch {
+            agent: address,
+            wallet: wallet_address,
+        });
+        return None;
+    }
+
+    on_event(&SignerEvent::Agent);
+    Some(Box::new(signer))
+}
+
+#[cfg(not(unix))]
+fn agent_signer(
+    _wallet_path: &Path,
+    _on_event: &mut impl FnMut(&SignerEvent),
+) -> Option<Box<dyn PaymentSigner>> {
+    None
+}
+
+/// Message bytes to sign or verify: UTF-8 text, or `0x`-prefixed hex when `hex` is set
+pub fn message_bytes(message: &str, hex: bool) -> Result<Vec<u8>> {
+    if !hex {
+        return Ok(message.as_bytes().to_vec());
+    }
+    alloy::primitives::hex::decode(message)
+        .map_err(|_| Error::InvalidArgument(format!("Invalid hex message: {}", message)))
+}
+
+/// Read EIP-712 typed data JSON from `path` (`-` reads stdin)
+pub fn load_typed_data(path: &Path) -> Result<TypedData> {
+    let json = if path == Path::new("-") {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(path)?
+    };
+    serde_json::from_str(&json)
+        .map_err(|e| Error::InvalidArgument(format!("Invalid EIP-712 typed data: {}", e)))
+}
+
+/// Parse a 65-byte hex signature
+pub fn parse_signature(signature: &str) -> Result<Signature> {
+    let raw = alloy::primitives::hex::decode(signature)
+        .map_err(|_| Error::InvalidArgument(format!("Invalid signature: {}", signature)))?;
+    Signature::try_from(raw.as_slice())
+        .map_err(|e| Error::InvalidArgument(format!("Invalid signature: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_keystore_signer_signs_messages() {
+        let signer = KeystoreSigner::from(PrivateKeySigner::random());
+        let message = message_bytes("0x68656c6c6f", true).unwrap();
+        assert_eq!(message, b"hello");
+
+        let signature = signer.sign_message(&message).await.unwrap();
+        let raw = alloy::primitives::hex::encode_prefixed(signature.as_bytes());
+        let parsed = parse_signature(&raw).unwrap();
+        assert_eq!(
+            parsed.recover_address_from_msg(&message).unwrap(),
+            signer.address()
+        );
+    }
+
+    #[test]
+    fn test_message_bytes_rejects_bad_hex() {
+        assert_eq!(message_bytes("0xzz", false).unwrap(), b"0xzz");
+        assert!(matches!(
+            message_bytes("0xzz", true),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+}
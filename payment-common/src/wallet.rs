@@ -1,5 +1,6 @@
+use crate::checks::{all_passed, check_private_file, Check};
 use crate::error::{Error, Result};
-use crate::{default_password_path, default_wallet_path, ensure_data_dir};
+use crate::{default_password_path, default_wallet_path, ensure_private_dir, write_private};
 use alloy::signers::local::PrivateKeySigner;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
@@ -22,6 +23,23 @@ pub struct EncryptedWallet {
     pub version: u32,
 }
 
+/// Minimum accepted scrypt cost parameter (N) in a keystore
+const MIN_SCRYPT_N: u64 = 1 << 13;
+
+/// Minimum accepted PBKDF2 iteration count in a keystore
+const MIN_PBKDF2_ITERATIONS: u64 = 262_144;
+
+/// Result of verifying a wallet keystore
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletVerification {
+    pub path: PathBuf,
+    /// Address derived from the decrypted key (if decryption succeeded)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    pub ok: bool,
+    pub checks: Vec<Check>,
+}
+
 /// Wallet operations
 pub struct Wallet;
 
@@ -49,11 +67,40 @@ impl Wallet {
         output_path: Option<&Path>,
         password_file: Option<&Path>,
     ) -> Result<WalletInfo> {
-        ensure_data_dir()?;
+        Self::save_signer(
+            PrivateKeySigner::random(),
+            password,
+            output_path,
+            password_file,
+        )
+    }
+
+    /// Import an existing hex private key into a new keystore, like `create`
+    pub fn import(
+        private_key: &str,
+        password: Option<&str>,
+        output_path: Option<&Path>,
+        password_file: Option<&Path>,
+    ) -> Result<WalletInfo> {
+        let signer: PrivateKeySigner = private_key
+            .trim()
+            .parse()
+            .map_err(|_| Error::Wallet("Invalid private key".to_string()))?;
+        Self::save_signer(signer, password, output_path, password_file)
+    }
 
+    fn save_signer(
+        signer: PrivateKeySigner,
+        password: Option<&str>,
+        output_path: Option<&Path>,
+        password_file: Option<&Path>,
+    ) -> Result<WalletInfo> {
         let wallet_path = output_path
             .map(PathBuf::from)
             .unwrap_or_else(default_wallet_path);
+        if let Some(dir) = wallet_path.parent() {
+            ensure_private_dir(dir)?;
+        }
 
         // Check if wallet already exists
         if wallet_path.exists() {
@@ -66,8 +113,6 @@ impl Wallet {
             None => (Self::generate_password(), true),
         };
 
-        // Generate new private key signer
-        let signer = PrivateKeySigner::random();
         let address = format!("{}", signer.address());
 
         // Get the private key bytes
@@ -96,26 +141,14 @@ impl Wallet {
         let mut keystore: serde_json::Value = serde_json::from_str(&keystore_content)?;
         // Store address without 0x prefix (standard keystore format)
         keystore["address"] = serde_json::Value::String(address[2..].to_lowercase());
-        fs::write(&wallet_path, serde_json::to_string_pretty(&keystore)?)?;
-
-        // Set restrictive permissions on the wallet file
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            fs::set_permissions(&wallet_path, fs::Permissions::from_mode(0o600))?;
-        }
+        write_private(&wallet_path, serde_json::to_string_pretty(&keystore)?)?;
 
         // Save password to file if auto-generated
         if should_save_password {
             let pw_path = password_file
                 .map(PathBuf::from)
                 .unwrap_or_else(default_password_path);
-            fs::write(&pw_path, &password_str)?;
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                fs::set_permissions(&pw_path, fs::Permissions::from_mode(0o600))?;
-            }
+            write_private(&pw_path, &password_str)?;
         }
 
         Ok(WalletInfo {
@@ -153,6 +186,77 @@ impl Wallet {
         Ok(address)
     }
 
+    /// Verify a keystore: format and KDF parameters, the MAC (by decrypting with
+    /// `password`), that the stored address matches the decrypted key, and that the
+    /// wallet and password files are private to the owner
+    pub fn verify(
+        wallet_path: Option<&Path>,
+        password: &str,
+        password_file: Option<&Path>,
+    ) -> Result<WalletVerification> {
+        let path = wallet_path
+            .map(PathBuf::from)
+            .unwrap_or_else(default_wallet_path);
+
+        if !path.exists() {
+            return Err(Error::WalletNotFound(path.display().to_string()));
+        }
+
+        let content = fs::read_to_string(&path)?;
+        let keystore: serde_json::Value = serde_json::from_str(&content)?;
+        let mut checks = vec![
+            check_keystore_format(&keystore),
+            check_kdf_params(&keystore),
+        ];
+
+        // Decrypting recomputes the MAC over the ciphertext
+        let derived = match eth_keystore::decrypt_key(&path, password) {
+            Ok(key) => match PrivateKeySigner::from_slice(&key) {
+                Ok(signer) => {
+                    checks.push(Check::pass("mac", "Password decrypts the keystore"));
+                    Some(signer.address())
+                }
+                Err(e) => {
+                    checks.push(Check::fail(
+                        "mac",
+                        format!("Keystore decrypts to an invalid private key: {}", e),
+                        None,
+                    ));
+                    None
+                }
+            },
+            Err(eth_keystore::KeystoreError::MacMismatch) => {
+                checks.push(Check::fail(
+                    "mac",
+                    "MAC mismatch: wrong password or corrupted keystore",
+                    Some("Check wallet.password_file points to this wallet's password".to_string()),
+                ));
+                None
+            }
+            Err(e) => {
+                checks.push(Check::fail(
+                    "mac",
+                    format!("Failed to decrypt keystore: {}", e),
+                    None,
+                ));
+                None
+            }
+        };
+
+        checks.push(check_stored_address(&keystore, derived));
+        checks.push(check_private_file("wallet_permissions", &path));
+        if let Some(pw_path) = password_file.filter(|p| p.exists()) {
+            checks.push(check_private_file("password_file_permissions", pw_path));
+        }
+
+        Ok(WalletVerification {
+            path,
+            address: derived.map(|a| a.to_string()),
+            ok: all_passed(&checks),
+            checks,
+        })
+    }
+
     /// Check if a wallet exists at the given path
     pub fn exists(wallet_path: Option<&Path>) -> bool {
         let path = wallet_path
@@ -179,6 +283,139 @@ impl Wallet {
     }
 }
 
+fn keystore_crypto(keystore: &serde_json::Value) -> Option<&serde_json::Value> {
+    keystore.get("crypto").or_else(|| keystore.get("Crypto"))
+}
+
+fn check_keystore_format(keystore: &serde_json::Value) -> Check {
+    let version = keystore.get("version").and_then(|v| v.as_u64());
+    if version != Some(3) {
+        return Check::fail(
+            "keystore_format",
+            format!("Unsupported keystore version: {:?}", version),
+            None,
+        );
+    }
+    let Some(crypto) = keystore_crypto(keystore) else {
+        return Check::fail("keystore_format", "Keystore has no crypto section", None);
+    };
+    let cipher = crypto.get("cipher").and_then(|v| v.as_str()).unwrap_or("");
+    if cipher != "aes-128-ctr" {
+        return Check::fail(
+            "keystore_format",
+            format!("Unsupported cipher: {}", cipher),
+            None,
+        );
+    }
+    for field in ["ciphertext", "mac"] {
+        if crypto.get(field).and_then(|v| v.as_str()).is_none() {
+            return Check::fail(
+                "keystore_format",
+                format!("Keystore is missing crypto.{}", field),
+                None,
+            );
+        }
+    }
+    Check::pass("keystore_format", "Web3 Secret Storage v3, aes-128-ctr")
+}
+
+fn check_kdf_params(keystore: &serde_json::Value) -> Check {
+    let crypto = keystore_crypto(keystore);
+    let kdf = crypto
+        .and_then(|c| c.get("kdf"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let params = crypto.and_then(|c| c.get("kdfparams"));
+    let param = |name: &str| params.and_then(|p| p.get(name)).and_then(|v| v.as_u64());
+
+    if param("dklen") != Some(32) {
+        return Check::fail("kdf_params", "kdfparams.dklen must be 32", None);
+    }
+    let salt_len = params
+        .and_then(|p| p.get("salt"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.len() / 2)
+        .unwrap_or(0);
+    if salt_len < 16 {
+        return Check::fail(
+            "kdf_params",
+            format!("KDF salt is too short ({} bytes)", salt_len),
+            None,
+        );
+    }
+
+    let weak_fix = Some("Re-encrypt the key into a new keystore with create-wallet".to_string());
+    match kdf {
+        "scrypt" => {
+            let (n, r, p) = (param("n"), param("r"), param("p"));
+            match (n, r, p) {
+                (Some(n), Some(r), Some(p)) if n >= MIN_SCRYPT_N && r >= 8 && p >= 1 => {
+                    Check::pass("kdf_params", format!("scrypt n={} r={} p={}", n, r, p))
+                }
+                (Some(n), Some(r), Some(p)) => Check::fail(
+                    "kdf_params",
+                    format!(
+                        "scrypt parameters are too weak (n={} r={} p={}, need n>={} r>=8)",
+                        n, r, p, MIN_SCRYPT_N
+                    ),
+                    weak_fix,
+                ),
+                _ => Check::fail("kdf_params", "scrypt parameters are incomplete", None),
+            }
+        }
+        "pbkdf2" => match param("c") {
+            Some(c) if c >= MIN_PBKDF2_ITERATIONS => {
+                Check::pass("kdf_params", format!("pbkdf2 c={}", c))
+            }
+            Some(c) => Check::fail(
+                "kdf_params",
+                format!(
+                    "pbkdf2 iteration count {} is below {}",
+                    c, MIN_PBKDF2_ITERATIONS
+                ),
+                weak_fix,
+            ),
+            None => Check::fail("kdf_params", "pbkdf2 parameters are incomplete", None),
+        },
+        other => Check::fail("kdf_params", format!("Unsupported KDF: {}", other), None),
+    }
+}
+
+fn check_stored_address(
+    keystore: &serde_json::Value,
+    derived: Option<alloy::primitives::Address>,
+) -> Check {
+    let Some(derived) = derived else {
+        return Check::fail(
+            "address",
+            "Cannot compare addresses without decrypting the keystore",
+            None,
+        );
+    };
+    let Some(stored) = keystore.get("address").and_then(|v| v.as_str()) else {
+        return Check::fail(
+            "address",
+            format!("Keystore has no address field (key is {})", derived),
+            None,
+        );
+    };
+
+    let stored_hex = stored.trim_start_matches("0x").to_lowercase();
+    let derived_hex = format!("{:x}", derived);
+    if stored_hex == derived_hex {
+        Check::pass("address", format!("Stored address matches key {}", derived))
+    } else {
+        Check::fail(
+            "address",
+            format!(
+                "Stored address 0x{} does not match the key's address {}",
+                stored_hex, derived
+            ),
+            Some("Do not use this wallet; the keystore address field has been altered".to_string()),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -222,6 +459,27 @@ mod tests {
         assert!(matches!(result, Err(Error::WalletExists(_))));
     }
 
+    #[test]
+    fn test_import_wallet() {
+        let dir = tempdir().unwrap();
+        let wallet_path = dir.path().join("test_wallet.json");
+        // Well-known Hardhat/Anvil account #0
+        let key = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+        let info = Wallet::import(key, Some("password"), Some(&wallet_path), None).unwrap();
+        assert_eq!(info.address, "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let report = Wallet::verify(Some(&wallet_path), "password", None).unwrap();
+        assert_eq!(report.address, Some(info.address));
+
+        let result = Wallet::import(
+            "0x1234",
+            Some("password"),
+            Some(&dir.path().join("b.json")),
+            None,
+        );
+        assert!(matches!(result, Err(Error::Wallet(_))));
+    }
+
     #[test]
     fn test_get_address() {
         let dir = tempdir().unwrap();
@@ -232,4 +490,35 @@ mod tests {
 
         assert_eq!(address.to_lowercase(), info.address.to_lowercase());
     }
+
+    #[test]
+    fn test_verify_wallet() {
+        let dir = tempdir().unwrap();
+        let wallet_path = dir.path().join("test_wallet.json");
+        let info = Wallet::create(Some("password"), Some(&wallet_path), None).unwrap();
+
+        let report = Wallet::verify(Some(&wallet_path), "password", None).unwrap();
+        assert!(report.ok, "{:?}", report.checks);
+        assert_eq!(report.address, Some(info.address));
+
+        let report = Wallet::verify(Some(&wallet_path), "wrong", None).unwrap();
+        assert!(!report.ok);
+    }
+
+    #[test]
+    fn test_verify_detects_tampered_address() {
+        let dir = tempdir().unwrap();
+        let wallet_path = dir.path().join("test_wallet.json");
+        Wallet::create(Some("password"), Some(&wallet_path), None).unwrap();
+
+        let mut keystore: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&wallet_path).unwrap()).unwrap();
+        keystore["address"] = "000000000000000000000000000000000000dead".into();
+        fs::write(&wallet_path, keystore.to_string()).unwrap();
+
+        let report = Wallet::verify(Some(&wallet_path), "password", None).unwrap();
+        assert!(!report.ok);
+        let check = report.checks.iter().find(|c| c.name == "address").unwrap();
+        assert_eq!(check.status, crate::checks::CheckStatus::Fail);
+    }
 }
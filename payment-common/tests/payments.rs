@@ -0,0 +1,50 @@
+use alloy::primitives::{Address, U256};
+use payment_common::payments::{Amount, PaymentError, PaymentEvent, PaymentRequest};
+use payment_common::{Config, ContactBook};
+use std::sync::{Arc, Mutex};
+use test_support::{MockChain, TestHome};
+
+const RECIPIENT: Address = Address::repeat_byte(0x52);
+
+#[tokio::test]
+async fn test_payment_request() {
+    let chain = MockChain::start();
+    let token = chain.deploy_token(6);
+    let home = TestHome::new(&chain, Some((token, 6)));
+    chain.fund(home.address(), U256::from(10u64.pow(18)));
+    chain.mint(token, home.address(), U256::from(2_000_000));
+    let config = Config::load_from(Some(&home.path().join("config.toml"))).unwrap();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&events);
+    let receipt = PaymentRequest::new(RECIPIENT.to_string(), Amount::Raw(U256::from(1_250_000)))
+        .on_progress(move |event| seen.lock().unwrap().push(event.clone()))
+        .send(&config)
+        .await
+        .unwrap();
+    assert!(receipt.confirmed);
+    assert_eq!(receipt.from, home.address());
+    assert_eq!(receipt.to, RECIPIENT);
+    assert_eq!(receipt.amount, "1.25");
+    assert_eq!(receipt.token.unwrap().address, token);
+    assert_eq!(receipt.block_number, Some(1));
+    assert_eq!(chain.token_balance(token, RECIPIENT), U256::from(1_250_000));
+    let events = std::mem::take(&mut *events.lock().unwrap());
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, PaymentEvent::Sent(hash) if *hash == receipt.tx_hash)));
+    assert!(matches!(events.last(), Some(PaymentEvent::Confirmed(1))));
+
+    let err = PaymentRequest::new(RECIPIENT.to_string(), Amount::Units("1".to_string()))
+        .contacts(ContactBook::default())
+        .send(&config)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, PaymentError::InsufficientBalance(_)),
+        "{}",
+        err
+    );
+    assert_eq!(err.exit_code(), 1);
+    assert_eq!(chain.nonce(home.address()), 1);
+}
@@ -0,0 +1,542 @@
+use alloy::primitives::utils::format_units;
+use alloy::primitives::{Address, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::sol;
+use payment_common::checks::{all_passed, check_private_file, Check};
+use payment_common::config::{ResolvedToken, SignerBackend};
+use payment_common::gas;
+use payment_common::rpc::{self, EndpointHealth};
+use payment_common::{Config, Wallet};
+use serde::Serialize;
+use std::time::Duration;
+
+sol! {
+    #[sol(rpc)]
+    contract IERC20Metadata {
+        function balanceOf(address account) external view returns (uint256);
+        function decimals() external view returns (uint8);
+        function symbol() external view returns (string);
+    }
+}
+
+/// Readiness report printed by `payment-config doctor`
+#[derive(Debug, Serialize)]
+pub struct DoctorReport {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    pub checks: Vec<Check>,
+}
+
+/// Run every readiness check against `config`, from config completeness to balances
+pub async fn diagnose(config: &Config) -> DoctorReport {
+    let mut checks = Vec::new();
+    let network = config.network.name.clone();
+    let use_network_fix = Some("payment-config use-network base-sepolia".to_string());
+
+    match config.check_network_config() {
+        Ok(()) => checks.push(Check::pass("config", "RPC endpoint and chain ID are set")),
+        Err(prompt) => checks.push(Check::fail(
+            "config",
+            format!("Missing {}", prompt.missing_fields.join(", ")),
+            use_network_fix.clone(),
+        )),
+    }
+
+    let address = check_wallet(config, &mut checks);
+
+    let endpoints = config.rpc_endpoints();
+    if endpoints.is_empty() {
+        return report(config, network, address, checks);
+    }
+    let timeout = config
+        .network
+        .rpc_timeout_secs
+        .map(Duration::from_secs)
+        .unwrap_or(rpc::DEFAULT_RPC_TIMEOUT);
+    let mut health = Vec::new();
+    for endpoint in &endpoints {
+        eprintln!("Checking {}...", endpoint.url);
+        health.push(rpc::check_endpoint(endpoint, timeout).await);
+    }
+    checks.push(check_reachable(&health));
+
+    // Everything below needs a live endpoint
+    let live: Vec<_> = health.iter().filter(|h| h.ok).collect();
+    if live.is_empty() {
+        return report(config, network, address, checks);
+    }
+    checks.push(check_chain_id(config.network.chain_id, &live));
+
+    let client =
+        match rpc::client_from_config(config, |failover| eprintln!("Warning: {}", failover)) {
+            Ok(client) => client,
+            Err(e) => {
+                checks.push(Check::fail("rpc_reachable", e.to_string(), None));
+                return report(config, network, address, checks);
+            }
+        };
+    let provider = ProviderBuilder::new().connect_client(client);
+
+    let token = config.default_token();
+    let token_ok = match &token {
+        Some(token) => check_token(&provider, token, config, &mut checks).await,
+        None => {
+            checks.push(Check::warn(
+                "token_code",
+                "payment.default_token is not set; only native transfers are possible",
+                use_network_fix,
+            ));
+            false
+        }
+    };
+
+    if let Some(owner) = address.as_deref().and_then(|a| a.parse::<Address>().ok()) {
+        let payment_token = token.as_ref().filter(|_| token_ok);
+        checks.push(check_native_balance(&provider, owner, payment_token).await);
+        if let (Some(token), true) = (&token, token_ok) {
+            checks.push(check_token_balance(&provider, owner, token, config).await);
+        }
+    }
+
+    report(config, network, address, checks)
+}
+
+fn report(
+    config: &Config,
+    network: Option<String>,
+    address: Option<String>,
+    checks: Vec<Check>,
+) -> DoctorReport {
+    DoctorReport {
+        ok: all_passed(&checks),
+        env: config.env_name().map(str::to_string),
+        network,
+        address,
+        checks,
+    }
+}
+
+/// Wallet (or remote signer) checks; returns the paying address when it is known
+fn check_wallet(config: &Config, checks: &mut Vec<Check>) -> Option<String> {
+    if config.signer.backend == SignerBackend::Remote {
+        return match (&config.signer.url, &config.signer.address) {
+            (Some(url), Some(address)) => {
+                checks.push(Check::pass(
+                    "signer",
+                    format!("Remote signer {} signs for {}", url, address),
+                ));
+                Some(address.clone())
+            }
+            _ => {
+                checks.push(Check::fail(
+                    "signer",
+                    "signer.backend is remote but signer.url or signer.address is not set",
+                    Some(
+                        "payment-config set signer.url <URL> signer.address <ADDRESS>".to_string(),
+                    ),
+                ));
+                None
+            }
+        };
+    }
+
+    let wallet_path = config.wallet_path();
+    if !wallet_path.exists() {
+        checks.push(Check::fail(
+            "wallet_exists",
+            format!("No wallet at {}", wallet_path.display()),
+            Some("create-wallet".to_string()),
+        ));
+        return None;
+    }
+    checks.push(Check::pass(
+        "wallet_exists",
+        format!("Wallet found at {}", wallet_path.display()),
+    ));
+    let address = Wallet::get_address(Some(&wallet_path)).ok();
+
+    let password = match config.wallet_password() {
+        Ok(password) => password,
+        Err(e) => {
+            checks.push(check_private_file("wallet_permissions", &wallet_path));
+            checks.push(Check::fail(
+                "wallet_password",
+                format!("Cannot load the wallet password: {}", e),
+                Some("Check wallet.password_file or wallet.secret_store".to_string()),
+            ));
+            return address;
+        }
+    };
+    let password_path = config
+        .secret_store_path()
+        .unwrap_or_else(|| config.password_path());
+    match Wallet::verify(Some(&wallet_path), &password, Some(&password_path)) {
+        Ok(verification) => {
+            checks.extend(verification.checks);
+            verification.address.or(address)
+        }
+        Err(e) => {
+            checks.push(Check::fail("wallet_password", e.to_string(), None));
+            address
+        }
+    }
+}
+
+fn check_reachable(health: &[EndpointHealth]) -> Check {
+    let up: Vec<_> = health.iter().filter(|h| h.ok).collect();
+    let down: Vec<_> = health.iter().filter(|h| !h.ok).collect();
+    let describe = |h: &EndpointHealth| match (h.latency_ms, &h.error) {
+        (Some(ms), _) => format!("{} ({} ms)", h.url, ms),
+        (None, Some(error)) => format!("{} ({})", h.url, error),
+        (None, None) => h.url.clone(),
+    };
+
+    if up.is_empty() {
+        Check::fail(
+            "rpc_reachable",
+            format!(
+                "No RPC endpoint is reachable: {}",
+                down.iter()
+                    .map(|h| describe(h))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Some("payment-config set network.rpc_url <URL>".to_string()),
+        )
+    } else if !down.is_empty() {
+        Check::warn(
+            "rpc_reachable",
+            format!(
+                "{} of {} endpoints reachable; down: {}",
+                up.len(),
+                health.len(),
+                down.iter()
+                    .map(|h| describe(h))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Some("Remove or replace the failing entries in network.rpc_urls".to_string()),
+        )
+    } else {
+        Check::pass(
+            "rpc_reachable",
+            up.iter()
+                .map(|h| describe(h))
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    }
+}
+
+/// Every live endpoint must serve the configured chain; failover would otherwise switch chains
+fn check_chain_id(expected: Option<u64>, live: &[&EndpointHealth]) -> Check {
+    let mut reported: Vec<u64> = live.iter().filter_map(|h| h.chain_id).collect();
+    reported.sort_unstable();
+    reported.dedup();
+
+    let Some(expected) = expected else {
+        return match reported.as_slice() {
+            [] => Check::fail("chain_id", "RPC did not report a chain ID", None),
+            [actual] => Check::fail(
+                "chain_id",
+                format!("network.chain_id is not set; RPC reports {}", actual),
+                Some(format!("payment-config set network.chain_id {}", actual)),
+            ),
+            _ => Check::fail(
+                "chain_id",
+                format!(
+                    "network.chain_id is not set and the endpoints disagree: {}",
+                    describe_chain_ids(live)
+                ),
+                Some("payment-config set network.chain_id <ID>".to_string()),
+            ),
+        };
+    };
+
+    let mismatched: Vec<_> = live
+        .iter()
+        .filter(|h| h.chain_id != Some(expected))
+        .copied()
+        .collect();
+    if mismatched.is_empty() {
+        return Check::pass("chain_id", format!("RPC reports chain ID {}", expected));
+    }
+    let fix = match reported.as_slice() {
+        // Every endpoint agrees on another chain: the config is probably what is wrong
+        [actual] if mismatched.len() == live.len() => format!(
+            "Point network.rpc_url at chain {} or run: payment-config set network.chain_id {}",
+            expected, actual
+        ),
+        _ => format!(
+            "Remove the endpoints that do not serve chain {} from network.rpc_url / network.rpc_urls",
+            expected
+        ),
+    };
+    Check::fail(
+        "chain_id",
+        format!(
+            "network.chain_id is {} but {}",
+            expected,
+            describe_chain_ids(&mismatched)
+        ),
+        Some(fix),
+    )
+}
+
+fn describe_chain_ids(endpoints: &[&EndpointHealth]) -> String {
+    endpoints
+        .iter()
+        .map(|h| match h.chain_id {
+            Some(id) => format!("{} reports chain ID {}", h.url, id),
+            None => format!("{} reports no chain ID", h.url),
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Check the default token contract exists and matches the configured metadata
+async fn check_token(
+    provider: &impl Provider,
+    token: &ResolvedToken,
+    config: &Config,
+    checks: &mut Vec<Check>,
+) -> bool {
+    let set_token_fix = Some("payment-config set payment.default_token <ADDRESS>".to_string());
+    match provider.get_code_at(token.address).await {
+        Ok(code) if code.is_empty() => {
+            checks.push(Check::fail(
+                "token_code",
+                format!(
+                    "No contract at {} on {}",
+                    token.address,
+                    config.network.name.as_deref().unwrap_or("this network")
+                ),
+                set_token_fix,
+            ));
+            return false;
+        }
+        Ok(code) => checks.push(Check::pass(
+            "token_code",
+            format!("{} has {} bytes of code", token.address, code.len()),
+        )),
+        Err(e) => {
+            checks.push(Check::fail(
+                "token_code",
+                format!("Failed to read code at {}: {}", token.address, e),
+                None,
+            ));
+            return false;
+        }
+    }
+
+    let contract = IERC20Metadata::new(token.address, provider);
+    let mut mismatches = Vec::new();
+    let mut fixes = Vec::new();
+    match contract.decimals().call().await {
+        Ok(decimals) if decimals != token.decimals => {
+            mismatches.push(format!(
+                "decimals is {} on-chain but {} in config",
+                decimals, token.decimals
+            ));
+            fixes.push(format!("payment.default_token_decimals {}", decimals));
+        }
+        Ok(_) => {}
+        Err(e) => mismatches.push(format!("decimals() failed: {}", e)),
+    }
+    match contract.symbol().call().await {
+        Ok(symbol) => match &token.symbol {
+            Some(configured) if *configured != symbol => {
+                mismatches.push(format!(
+                    "symbol is {} on-chain but {} in config",
+                    symbol, configured
+                ));
+                fixes.push(format!("payment.default_token_symbol {}", symbol));
+            }
+            _ => {}
+        },
+        // Some tokens (e.g. MKR) return bytes32 instead of a string
+        Err(e) => checks.push(Check::warn(
+            "token_metadata",
+            format!("symbol() could not be read: {}", e),
+            None,
+        )),
+    }
+
+    if mismatches.is_empty() {
+        checks.push(Check::pass(
+            "token_metadata",
+            format!(
+                "{} with {} decimals",
+                token.symbol.as_deref().unwrap_or("Token"),
+                token.decimals
+            ),
+        ));
+        true
+    } else {
+        let fix = (!fixes.is_empty()).then(|| format!("payment-config set {}", fixes.join(" ")));
+        checks.push(Check::fail("token_metadata", mismatches.join("; "), fix));
+        false
+    }
+}
+
+async fn check_native_balance(
+    provider: &impl Provider,
+    owner: Address,
+    token: Option<&ResolvedToken>,
+) -> Check {
+    let balance = match provider.get_balance(owner).await {
+        Ok(balance) => balance,
+        Err(e) => {
+            return Check::fail(
+                "native_balance",
+                format!("Failed to get balance: {}", e),
+                None,
+            )
+        }
+    };
+    // Gas of one payment, including the L1 data fee on OP-stack networks
+    let needed = gas::transfer_cost(provider, owner, token, None)
+        .await
+        .map(|cost| cost.total())
+        .unwrap_or_default();
+    let fund_fix = Some(format!("Send native currency for gas to {}", owner));
+    let eth = |wei: U256| format_units(wei, "ether").unwrap_or_else(|_| wei.to_string());
+
+    if balance.is_zero() {
+        Check::fail(
+            "native_balance",
+            "Balance is 0; gas cannot be paid",
+            fund_fix,
+        )
+    } else if balance < needed {
+        Check::warn(
+            "native_balance",
+            format!(
+                "Balance {} is below the ~{} needed for one transfer",
+                eth(balance),
+                eth(needed)
+            ),
+            fund_fix,
+        )
+    } else {
+        Check::pass("native_balance", format!("Balance {}", eth(balance)))
+    }
+}
+
+async fn check_token_balance(
+    provider: &impl Provider,
+    owner: Address,
+    token: &ResolvedToken,
+    config: &Config,
+) -> Check {
+    let symbol = token.symbol.as_deref().unwrap_or("tokens");
+    let balance = match IERC20Metadata::new(token.address, provider)
+        .balanceOf(owner)
+        .call()
+        .await
+    {
+        Ok(balance) => balance,
+        Err(e) => {
+            return Check::fail(
+                "token_balance",
+                format!("Failed to get token balance: {}", e),
+                None,
+            )
+        }
+    };
+    let human = format_units(balance, token.decimals).unwrap_or_else(|_| balance.to_string());
+    let fund_fix = Some(format!("Send {} to {}", symbol, owner));
+
+    if balance.is_zero() {
+        return Check::fail(
+            "token_balance",
+            format!("{} balance is 0", symbol),
+            fund_fix,
+        );
+    }
+    let below_max = config
+        .payment
+        .max_auto_payment
+        .as_deref()
+        .and_then(|max| alloy::primitives::utils::parse_units(max, token.decimals).ok())
+        .map(|max| max.get_absolute())
+        .filter(|max| balance < *max);
+    match below_max {
+        Some(_) => Check::warn(
+            "token_balance",
+            format!(
+                "{} {} is below payment.max_auto_payment ({})",
+                human,
+                symbol,
+                config
+                    .payment
+                    .max_auto_payment
+                    .as_deref()
+                    .unwrap_or_default()
+            ),
+            fund_fix,
+        ),
+        None => Check::pass("token_balance", format!("{} {}", human, symbol)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use payment_common::checks::CheckStatus;
+
+    #[tokio::test]
+    async fn test_incomplete_config_fails_with_fixes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.wallet.path = dir.path().join("wallet.json").display().to_string();
+
+        let report = diagnose(&config).await;
+        assert!(!report.ok);
+        for name in ["config", "wallet_exists"] {
+            let check = report.checks.iter().find(|c| c.name == name).unwrap();
+            assert_eq!(check.status, CheckStatus::Fail);
+            assert!(check.fix.is_some());
+        }
+        // Nothing on-chain is attempted without an endpoint
+        assert!(report.checks.iter().all(|c| c.name != "rpc_reachable"));
+    }
+
+    fn live(url: &str, chain_id: u64) -> EndpointHealth {
+        EndpointHealth {
+            url: url.to_string(),
+            ok: true,
+            chain_id: Some(chain_id),
+            latency_ms: Some(1),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_chain_id_mismatch() {
+        let sepolia = live("https://a.example", 84532);
+        let mainnet = live("https://b.example", 8453);
+        let also_mainnet = live("https://c.example", 8453);
+
+        assert_eq!(
+            check_chain_id(Some(84532), &[&sepolia]).status,
+            CheckStatus::Pass
+        );
+        let check = check_chain_id(Some(84532), &[&mainnet]);
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.detail.contains("8453"));
+        assert!(check.fix.unwrap().contains("set network.chain_id 8453"));
+
+        // A failover endpoint on another chain fails even when the first one is right
+        let check = check_chain_id(Some(84532), &[&sepolia, &mainnet, &also_mainnet]);
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(!check.detail.contains("a.example"));
+        assert!(check.detail.contains("b.example") && check.detail.contains("c.example"));
+        assert!(check.fix.unwrap().starts_with("Remove the endpoints"));
+    }
+}
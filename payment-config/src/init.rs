@@ -0,0 +1,545 @@
+use alloy::primitives::utils::parse_units;
+use payment_common::config::{ConfigQuestion, MissingConfigPrompt};
+use payment_common::secret_store::{machine_passphrase, secret_from_env, SealedSecret};
+use payment_common::{default_config_path, Config, Error, Result, Wallet};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Environment variable holding the private key to import
+pub const PRIVATE_KEY_ENV: &str = "PAYMENT_PRIVATE_KEY";
+
+/// Environment variable naming a file descriptor to read the private key to import from
+pub const PRIVATE_KEY_FD_ENV: &str = "PAYMENT_PRIVATE_KEY_FD";
+
+/// Answers-file field that is refused: answers files are plain text
+const PRIVATE_KEY_FIELD: &str = "wallet.private_key";
+
+/// Where the wizard's answers come from
+pub enum AnswerSource {
+    /// Prompt on the terminal, re-asking after an invalid answer
+    Terminal,
+    /// Answers keyed by question field, from a JSON answers file
+    File(BTreeMap<String, String>),
+}
+
+impl AnswerSource {
+    /// Load answers from a JSON file ("-" reads stdin).
+    ///
+    /// The file is a `MissingConfigPrompt` (or just its `questions` array) with an
+    /// `answer` filled in for each question; unanswered questions take their default.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = if path == Path::new("-") {
+            let mut content = String::new();
+            std::io::stdin().read_to_string(&mut content)?;
+            content
+        } else {
+            fs::read_to_string(path)?
+        };
+        let mut value: serde_json::Value = serde_json::from_str(&content)?;
+        if let Some(questions) = value.get_mut("questions") {
+            value = questions.take();
+        }
+        let questions: Vec<ConfigQuestion> = serde_json::from_value(value)?;
+        let answers: BTreeMap<_, _> = questions
+            .into_iter()
+            .filter_map(|q| Some((q.field, q.answer?)))
+            .collect();
+        if answers.contains_key(PRIVATE_KEY_FIELD) {
+            return Err(Error::InvalidArgument(format!(
+                "{} is not read from answers files; pass the key with {} (or {})",
+                PRIVATE_KEY_FIELD, PRIVATE_KEY_FD_ENV, PRIVATE_KEY_ENV
+            )));
+        }
+        Ok(Self::File(answers))
+    }
+
+    fn is_interactive(&self) -> bool {
+        matches!(self, Self::Terminal)
+    }
+
+    /// Answer `question`; empty answers fall back to the question's default
+    fn ask(&self, question: &ConfigQuestion, secret: bool) -> Result<Option<String>> {
+        let answer = match self {
+            Self::Terminal if secret => Some(rpassword::prompt_password(format!(
+                "{}: ",
+                question.question
+            ))?),
+            Self::Terminal => {
+                eprintln!();
+                eprintln!("{}", question.question);
+                if !question.examples.is_empty() {
+                    eprintln!("  e.g. {}", question.examples.join(", "));
+                }
+                match &question.default {
+                    Some(default) => eprint!("[{}] > ", default),
+                    None => eprint!("> "),
+                }
+                std::io::stderr().flush()?;
+                let mut line = String::new();
+                std::io::stdin().read_line(&mut line)?;
+                Some(line)
+            }
+            Self::File(answers) => answers.get(&question.field).cloned(),
+        };
+        Ok(answer
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty())
+            .or_else(|| question.default.clone()))
+    }
+
+    /// Ask until `apply` accepts the answer (terminal) or fail on the first bad one (file)
+    fn ask_until<T>(
+        &self,
+        question: &ConfigQuestion,
+        secret: bool,
+        mut apply: impl FnMut(Option<String>) -> Result<T>,
+    ) -> Result<T> {
+        loop {
+            match apply(self.ask(question, secret)?) {
+                Ok(value) => return Ok(value),
+                Err(e) if self.is_interactive() => eprintln!("{}", e),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// What to do about the wallet once the config is saved
+#[derive(Debug, PartialEq, Eq)]
+pub enum WalletChoice {
+    Keep,
+    Create,
+    Import(String),
+}
+
+fn question(
+    field: &str,
+    text: &str,
+    examples: Vec<String>,
+    default: Option<String>,
+) -> ConfigQuestion {
+    ConfigQuestion {
+        field: field.to_string(),
+        question: text.to_string(),
+        examples,
+        default,
+        answer: None,
+    }
+}
+
+fn network_question(config: &Config) -> ConfigQuestion {
+    question(
+        "network",
+        "Which blockchain network should be used for payments?",
+        config
+            .network_profiles()
+            .into_iter()
+            .map(|p| p.name)
+            .collect(),
+        Some(
+            config
+                .network
+                .name
+                .clone()
+                .unwrap_or_else(|| "base-sepolia".to_string()),
+        ),
+    )
+}
+
+fn rpc_question(config: &Config) -> ConfigQuestion {
+    question(
+        "network.rpc_url",
+        "Custom RPC endpoint URL? Leave empty to use the network's public endpoint",
+        config.network.rpc_url.iter().cloned().collect(),
+        None,
+    )
+}
+
+fn token_question(config: &Config) -> ConfigQuestion {
+    let network = config.network.name.as_deref().unwrap_or_default();
+    question(
+        "payment.token",
+        "Which token should payments use by default? (symbol or contract address)",
+        config.token_registry(network).into_keys().collect(),
+        config.payment.default_token_symbol.clone(),
+    )
+}
+
+fn limit_question(config: &Config) -> ConfigQuestion {
+    question(
+        "payment.max_auto_payment",
+        "Largest amount to pay without asking for confirmation? (none for no limit)",
+        vec!["1".to_string(), "10.50".to_string(), "none".to_string()],
+        config.payment.max_auto_payment.clone(),
+    )
+}
+
+fn wallet_question(config: &Config) -> ConfigQuestion {
+    let exists = config.wallet_path().exists();
+    let mut examples = vec!["create".to_string(), "import".to_string()];
+    if exists {
+        examples.push("keep".to_string());
+    }
+    question(
+        "wallet",
+        "Create a new wallet or import an existing private key? (without a terminal, import \
+         reads the key from PAYMENT_PRIVATE_KEY_FD or PAYMENT_PRIVATE_KEY)",
+        examples,
+        Some(if exists { "keep" } else { "create" }.to_string()),
+    )
+}
+
+fn private_key_question() -> ConfigQuestion {
+    question(
+        PRIVATE_KEY_FIELD,
+        "Private key to import (hex)",
+        Vec::new(),
+        None,
+    )
+}
+
+/// Every question the wizard may ask, for callers that cannot use a terminal
+pub fn questions(config: &Config) -> Vec<ConfigQuestion> {
+    vec![
+        network_question(config),
+        rpc_question(config),
+        token_question(config),
+        limit_question(config),
+        wallet_question(config),
+    ]
+}
+
+/// Walk through the questions, applying each answer to `config`
+pub fn configure(config: &mut Config, source: &AnswerSource, force: bool) -> Result<WalletChoice> {
+    source.ask_until(&network_question(config), false, |answer| {
+        let name =
+            answer.ok_or_else(|| Error::InvalidArgument("A network is required".to_string()))?;
+        config.apply_network_profile(&name)?;
+        config.network.rpc_urls.clear();
+        Ok(())
+    })?;
+
+    source.ask_until(&rpc_question(config), false, |answer| {
+        let Some(url) = answer else {
+            return Ok(());
+        };
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(Error::InvalidArgument(format!(
+                "RPC URL must start with http:// or https://: {}",
+                url
+            )));
+        }
+        // Keep the profile's endpoint as a fallback
+        if let Some(public) = config.network.rpc_url.replace(url.clone()) {
+            if public != url {
+                config.set("network.rpc_urls", &public)?;
+            }
+        }
+        Ok(())
+    })?;
+
+    source.ask_until(&token_question(config), false, |answer| {
+        let Some(token) = answer else {
+            return Ok(());
+        };
+        let token = config.resolve_token(&token)?;
+        config.payment.default_token = Some(token.address.to_string());
+        config.payment.default_token_symbol = token.symbol;
+        config.payment.default_token_decimals = Some(token.decimals);
+        Ok(())
+    })?;
+
+    let decimals = config.payment.default_token_decimals.unwrap_or(6);
+    source.ask_until(&limit_question(config), false, |answer| {
+        let answer = answer.filter(|limit| limit != "none");
+        if let Some(limit) = &answer {
+            parse_units(limit, decimals).map_err(|_| {
+                Error::InvalidArgument(format!(
+                    "Not an amount with at most {} decimals: {}",
+                    decimals, limit
+                ))
+            })?;
+        }
+        config.payment.max_auto_payment = answer;
+        Ok(())
+    })?;
+
+    let wallet_exists = config.wallet_path().exists();
+    let choice = source.ask_until(&wallet_question(config), false, |answer| {
+        match answer.as_deref() {
+            Some("keep") if wallet_exists => Ok(WalletChoice::Keep),
+            Some("keep") => Err(Error::InvalidArgument(
+                "There is no wallet to keep; answer create or import".to_string(),
+            )),
+            Some("create") | Some("import") if wallet_exists && !force => {
+                Err(Error::WalletExists(format!(
+                    "{} (answer keep, or rerun with --force to replace it)",
+                    config.wallet_path().display()
+                )))
+            }
+            Some("create") => Ok(WalletChoice::Create),
+            Some("import") => Ok(WalletChoice::Import(String::new())),
+            other => Err(Error::InvalidArgument(format!(
+                "Answer create, import or keep, not {}",
+                other.unwrap_or("nothing")
+            ))),
+        }
+    })?;
+    let WalletChoice::Import(_) = choice else {
+        return Ok(choice);
+    };
+
+    // The key never comes from the answers file: prompt for it, or read it from the environment
+    let validate = |answer: Option<String>| {
+        let key = answer.ok_or_else(|| {
+            Error::InvalidArgument("A private key is required to import a wallet".to_string())
+        })?;
+        // Validate now so a typo does not leave a half-initialized setup behind
+        key.trim()
+            .parse::<alloy::signers::local::PrivateKeySigner>()
+            .map_err(|_| Error::InvalidArgument("Invalid private key".to_string()))?;
+        Ok(WalletChoice::Import(key.trim().to_string()))
+    };
+    if let Some(key) = secret_from_env(PRIVATE_KEY_ENV, PRIVATE_KEY_FD_ENV, "private key")? {
+        return validate(Some(key));
+    }
+    if !source.is_interactive() {
+        return Err(Error::MissingConfig(format!(
+            "Importing a wallet without a terminal needs the key in {} (or {})",
+            PRIVATE_KEY_FD_ENV, PRIVATE_KEY_ENV
+        )));
+    }
+    source.ask_until(&private_key_question(), true, validate)
+}
+
+/// Run the wizard, save the config and create or import the wallet
+pub fn cmd_init(
+    answers: Option<&Path>,
+    force: bool,
+    env: Option<&str>,
+) -> Result<serde_json::Value> {
+    let mut config = crate::load_config_file(env)?;
+
+    let source = match answers {
+        Some(path) => AnswerSource::from_file(path)?,
+        None if std::io::stdin().is_terminal() => AnswerSource::Terminal,
+        None => {
+            let questions = questions(&config);
+            let prompt = MissingConfigPrompt {
+                error: "missing_config".to_string(),
+                missing_fields: questions.iter().map(|q| q.field.clone()).collect(),
+                prompt: "payment-config init needs answers to these questions.".to_string(),
+                questions,
+                hint: "Fill in each question's \"answer\" and run: payment-config init --answers <FILE>"
+                    .to_string(),
+            };
+            eprintln!("{}", serde_json::to_string_pretty(&prompt)?);
+            return Err(Error::MissingConfig(
+                "stdin is not a terminal; pass --answers <FILE>".to_string(),
+            ));
+        }
+    };
+
+    let choice = configure(&mut config, &source, force)?;
+
+    // The wallet goes first: a failure leaves both the old wallet and the old config in place
+    let wallet_path = config.wallet_path();
+    let address = match choice {
+        WalletChoice::Keep => Wallet::get_address(Some(&wallet_path))?,
+        choice => {
+            let replaced = wallet_path.exists();
+            let address = create_wallet(&config, choice)?;
+            if replaced {
+                eprintln!("Replaced existing wallet at {}", wallet_path.display());
+            }
+            eprintln!("Wallet saved to {}", wallet_path.display());
+            eprintln!("Fund {} to enable payments.", address);
+            address
+        }
+    };
+    config.save()?;
+    eprintln!("Saved configuration to {}", default_config_path().display());
+
+    Ok(serde_json::json!({
+        "config": default_config_path().display().to_string(),
+        "network": config.network.name,
+        "chain_id": config.network.chain_id,
+        "rpc_url": config.network.rpc_url,
+        "token": config.payment.default_token,
+        "token_symbol": config.payment.default_token_symbol,
+        "max_auto_payment": config.payment.max_auto_payment,
+        "address": address,
+    }))
+}
+
+/// Create or import the wallet, sealing a generated password when a secret store is configured.
+///
+/// The keystore and its password are written next to their final paths and renamed over
+/// any existing wallet only once everything has been written.
+fn create_wallet(config: &Config, choice: WalletChoice) -> Result<String> {
+    let sealed = match config.secret_store_path() {
+        Some(store_path) => {
+            let password = Wallet::generate_password();
+            let sealed = SealedSecret::seal(&password, &machine_passphrase()?)?;
+            Some((password, store_path, sealed))
+        }
+        None => None,
+    };
+    let password = sealed.as_ref().map(|(password, _, _)| password.as_str());
+
+    // (temporary, final) paths of every file the new wallet needs
+    let mut files = vec![(pending(&config.wallet_path()), config.wallet_path())];
+    match &sealed {
+        Some((_, store_path, _)) => files.push((pending(store_path), store_path.clone())),
+        None => files.push((pending(&config.password_path()), config.password_path())),
+    }
+    let result = write_wallet(&files, password, &sealed, choice).and_then(|address| {
+        for (pending, path) in &files {
+            fs::rename(pending, path)?;
+        }
+        Ok(address)
+    });
+    if result.is_err() {
+        for (pending, _) in &files {
+            let _ = fs::remove_file(pending);
+        }
+    }
+    result
+}
+
+fn write_wallet(
+    files: &[(PathBuf, PathBuf)],
+    password: Option<&str>,
+    sealed: &Option<(String, PathBuf, SealedSecret)>,
+    choice: WalletChoice,
+) -> Result<String> {
+    let (wallet_path, secret_path) = (&files[0].0, &files[1].0);
+    for (pending, _) in files {
+        if pending.exists() {
+            fs::remove_file(pending)?;
+        }
+    }
+    let info = match choice {
+        WalletChoice::Import(key) => {
+            Wallet::import(&key, password, Some(wallet_path), Some(secret_path))?
+        }
+        _ => Wallet::create(password, Some(wallet_path), Some(secret_path))?,
+    };
+    if let Some((_, _, sealed)) = sealed {
+        sealed.save(secret_path)?;
+    }
+    Ok(info.address)
+}
+
+/// Where a file is written before it replaces `path`
+fn pending(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".new");
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answers(pairs: &[(&str, &str)]) -> AnswerSource {
+        AnswerSource::File(
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_configure_from_answers() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.wallet.path = dir.path().join("wallet.json").display().to_string();
+
+        let source = answers(&[
+            ("network", "base-mainnet"),
+            ("network.rpc_url", "https://base.example.com"),
+            ("payment.token", "EURC"),
+            ("payment.max_auto_payment", "2.5"),
+        ]);
+        let choice = configure(&mut config, &source, false).unwrap();
+
+        assert_eq!(choice, WalletChoice::Create);
+        assert_eq!(config.network.chain_id, Some(8453));
+        assert_eq!(
+            config.network.rpc_url.as_deref(),
+            Some("https://base.example.com")
+        );
+        assert_eq!(config.rpc_endpoints()[1].url, "https://mainnet.base.org");
+        assert_eq!(config.payment.default_token_symbol.as_deref(), Some("EURC"));
+        assert_eq!(config.payment.max_auto_payment.as_deref(), Some("2.5"));
+    }
+
+    #[test]
+    fn test_answers_file_rejects_bad_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.wallet.path = dir.path().join("wallet.json").display().to_string();
+
+        let source = answers(&[
+            ("network", "base-sepolia"),
+            ("payment.max_auto_payment", "lots"),
+        ]);
+        assert!(configure(&mut config, &source, false).is_err());
+
+        // Without a terminal the key must come from the environment
+        let source = answers(&[("wallet", "import")]);
+        assert!(matches!(
+            configure(&mut config, &source, false),
+            Err(Error::MissingConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_answers_file_refuses_private_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("answers.json");
+        fs::write(
+            &path,
+            serde_json::json!([
+                { "field": "wallet", "question": "", "answer": "import" },
+                { "field": "wallet.private_key", "question": "", "answer": "0x12" },
+            ])
+            .to_string(),
+        )
+        .unwrap();
+
+        let Err(err) = AnswerSource::from_file(&path) else {
+            panic!("expected the private key to be refused");
+        };
+        assert!(err.to_string().contains(PRIVATE_KEY_FD_ENV));
+    }
+
+    #[test]
+    fn test_pending_path_is_next_to_target() {
+        let path = Path::new("/tmp/wallet.json");
+        assert_eq!(pending(path), Path::new("/tmp/wallet.json.new"));
+    }
+
+    #[test]
+    fn test_answers_file_accepts_prompt_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("answers.json");
+        let mut questions = questions(&Config::default());
+        questions[0].answer = Some("ethereum-sepolia".to_string());
+        fs::write(
+            &path,
+            serde_json::json!({ "error": "missing_config", "questions": questions }).to_string(),
+        )
+        .unwrap();
+
+        let AnswerSource::File(answers) = AnswerSource::from_file(&path).unwrap() else {
+            panic!("expected file answers");
+        };
+        assert_eq!(answers.len(), 1);
+        assert_eq!(answers["network"], "ethereum-sepolia");
+    }
+}
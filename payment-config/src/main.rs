@@ -1,21 +1,83 @@
 use clap::{Parser, Subcommand};
-use payment_common::config::NETWORK_PROFILES;
-use payment_common::Config;
+use payment_common::config::{
+    env_var_for_key, networks_path_for, upgrade_file, ProfileSource, ResolvedNetworkProfile,
+    TokenEntry, UserNetworkProfile, ValueOrigin, CONFIG_VERSION, KEY_SPECS,
+};
+use payment_common::contacts::Contact;
+use payment_common::secret_store::{machine_passphrase, SealedSecret};
+use payment_common::{
+    default_config_path, default_contacts_path, default_data_dir, rpc, Config, ContactBook, Wallet,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::time::Duration;
+
+mod doctor;
+mod init;
 
 /// Configuration management for payment tools
 #[derive(Parser, Debug)]
 #[command(name = "payment-config")]
 #[command(version, about, long_about = None)]
 struct Args {
+    /// Named environment to read and edit (default: $PAYMENT_ENV, then active_env)
+    #[arg(long, global = true)]
+    env: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Load the effective config (with environment variable overrides) for the selected environment
+fn load_config(env: Option<&str>) -> payment_common::Result<Config> {
+    Config::load_env(None, env).map(print_warnings)
+}
+
+/// Load config.toml for editing, without environment variable overrides
+fn load_config_file(env: Option<&str>) -> payment_common::Result<Config> {
+    Config::load_file_env(None, env).map(print_warnings)
+}
+
+/// Load config.toml with no environment selected, for managing the environments
+fn load_config_unselected() -> payment_common::Result<Config> {
+    Config::load_file_unselected(None).map(print_warnings)
+}
+
+/// Print what loading the config found wrong, passing the config on
+fn print_warnings(config: Config) -> Config {
+    for warning in config.warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+    config
+}
+
 #[derive(Subcommand, Debug)]
 enum Commands {
+    /// Set up network, token, spending limit and wallet step by step
+    ///
+    /// Prompts on the terminal. When stdin is not a terminal, prints the questions as
+    /// JSON; fill in each question's "answer" and pass the file with --answers.
+    Init {
+        /// JSON answers file ("-" for stdin)
+        #[arg(long)]
+        answers: Option<PathBuf>,
+
+        /// Replace an existing wallet when the answers say create or import
+        #[arg(long)]
+        force: bool,
+    },
+
     /// Display all current configuration
-    Show,
+    Show {
+        /// Print every key with where its value came from (default, file, env or flag)
+        #[arg(long)]
+        origin: bool,
+
+        /// Print the configuration as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
     /// Get a specific config value
     Get {
@@ -30,24 +92,189 @@ enum Commands {
         pairs: Vec<String>,
     },
 
+    /// Clear config values so their defaults apply again
+    Unset {
+        /// Config keys (e.g., payment.max_auto_payment)
+        #[arg(required = true)]
+        keys: Vec<String>,
+    },
+
     /// Apply a predefined network profile
     UseNetwork {
         /// Network profile name (e.g., base-sepolia, base-mainnet)
         profile: String,
     },
 
-    /// List available network profiles
+    /// List available network profiles (built-in and user-defined)
     ListNetworks,
 
+    /// Make an environment the default (used when neither --env nor PAYMENT_ENV is given)
+    UseEnv {
+        /// Environment name
+        #[arg(required_unless_present = "none")]
+        name: Option<String>,
+
+        /// Go back to the top-level settings outside any environment
+        #[arg(long, conflicts_with = "name")]
+        none: bool,
+    },
+
+    /// List the environments defined in config.toml
+    ListEnvs,
+
+    /// Create an environment from the current settings
+    AddEnv {
+        /// Environment name (e.g., sandbox, production)
+        name: String,
+
+        /// Use the current wallet instead of a separate wallet-<NAME>.json
+        #[arg(long)]
+        share_wallet: bool,
+    },
+
+    /// Remove an environment
+    RemoveEnv {
+        /// Environment name
+        name: String,
+    },
+
+    /// Define a custom network profile in config.toml
+    AddNetwork {
+        /// Profile name (e.g., arbitrum-one, devnet)
+        name: String,
+
+        /// Chain ID of the network
+        #[arg(long)]
+        chain_id: u64,
+
+        /// RPC endpoint URL
+        #[arg(long)]
+        rpc_url: String,
+
+        /// Default ERC-20 token contract address
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Default token symbol (e.g., USDC)
+        #[arg(long, requires = "token")]
+        token_symbol: Option<String>,
+
+        /// Default token decimals
+        #[arg(long, requires = "token")]
+        token_decimals: Option<u8>,
+
+        /// Symbol of the gas token, when it is not ETH (e.g., POL)
+        #[arg(long)]
+        native_symbol: Option<String>,
+    },
+
+    /// Remove a custom network profile from config.toml or networks.toml
+    RemoveNetwork {
+        /// Profile name
+        name: String,
+    },
+
     /// List all valid config keys
     ListKeys,
+
+    /// List tokens registered for a network
+    ListTokens {
+        /// Network profile name (default: the active network)
+        #[arg(long)]
+        network: Option<String>,
+    },
+
+    /// Register a token so it can be used by symbol (e.g., pay --token EURC)
+    AddToken {
+        /// Token symbol (e.g., EURC)
+        symbol: String,
+
+        /// Token contract address
+        #[arg(long)]
+        address: String,
+
+        /// Token decimals
+        #[arg(long)]
+        decimals: u8,
+
+        /// Network profile name (default: the active network)
+        #[arg(long)]
+        network: Option<String>,
+    },
+
+    /// Remove a token added with add-token
+    RemoveToken {
+        /// Token symbol
+        symbol: String,
+
+        /// Network profile name (default: the active network)
+        #[arg(long)]
+        network: Option<String>,
+    },
+
+    /// Manage the address book of payment recipients (pay --to @label)
+    Contacts {
+        #[command(subcommand)]
+        command: ContactsCommand,
+    },
+
+    /// Check every configured RPC endpoint and report its latency and chain ID
+    TestRpc,
+
+    /// Check end to end that payments can be made: RPC, chain ID, token, wallet and balances
+    Doctor,
+
+    /// Move the plaintext wallet password into the encrypted secret store
+    MigrateSecrets {
+        /// Secret store file (relative paths are resolved against the data directory)
+        #[arg(long, default_value = "password.enc")]
+        store: String,
+
+        /// Keep the plaintext password file after migrating
+        #[arg(long)]
+        keep_plaintext: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ContactsCommand {
+    /// Add or replace a contact
+    Add {
+        /// Label used as pay --to @<LABEL>
+        label: String,
+
+        /// Recipient address (stored EIP-55 checksummed)
+        address: String,
+
+        /// Token to pay this contact in when pay gets no --token (symbol or address)
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Free-form note (e.g., what the contact is paid for)
+        #[arg(long)]
+        note: Option<String>,
+    },
+
+    /// List contacts
+    List {
+        /// Print the address book as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Remove a contact
+    Remove {
+        /// Contact label
+        label: String,
+    },
 }
 
 fn main() -> ExitCode {
     let args = Args::parse();
 
     match run(args) {
-        Ok(()) => ExitCode::SUCCESS,
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
         Err(e) => {
             eprintln!("Error: {}", e);
             ExitCode::from(e.exit_code() as u8)
@@ -55,23 +282,109 @@ fn main() -> ExitCode {
     }
 }
 
-fn run(args: Args) -> payment_common::Result<()> {
+fn run(args: Args) -> payment_common::Result<bool> {
+    let env = args.env.as_deref();
+    if !matches!(args.command, Commands::Init { .. }) {
+        upgrade_config()?;
+    }
     match args.command {
-        Commands::Show => cmd_show(),
-        Commands::Get { key } => cmd_get(&key),
-        Commands::Set { pairs } => cmd_set(&pairs),
-        Commands::UseNetwork { profile } => cmd_use_network(&profile),
-        Commands::ListNetworks => cmd_list_networks(),
+        Commands::Doctor => return cmd_doctor(env),
+        Commands::Init { answers, force } => cmd_init(answers.as_deref(), force, env),
+        Commands::Show { origin, json: true } => cmd_show_json(origin, env),
+        Commands::Show {
+            origin: false,
+            json: false,
+        } => cmd_show(env),
+        Commands::Show {
+            origin: true,
+            json: false,
+        } => cmd_show_origin(env),
+        Commands::Get { key } => cmd_get(&key, env),
+        Commands::Set { pairs } => cmd_set(&pairs, env),
+        Commands::Unset { keys } => cmd_unset(&keys, env),
+        Commands::UseNetwork { profile } => cmd_use_network(&profile, env),
+        Commands::ListNetworks => cmd_list_networks(env),
+        Commands::UseEnv { name, none: _ } => cmd_use_env(name.as_deref()),
+        Commands::ListEnvs => cmd_list_envs(),
+        Commands::Contacts { command } => cmd_contacts(command, env),
+        Commands::AddEnv { name, share_wallet } => cmd_add_env(&name, share_wallet, env),
+        Commands::RemoveEnv { name } => cmd_remove_env(&name),
+        Commands::AddNetwork {
+            name,
+            chain_id,
+            rpc_url,
+            token,
+            token_symbol,
+            token_decimals,
+            native_symbol,
+        } => cmd_add_network(
+            &name,
+            UserNetworkProfile {
+                chain_id,
+                rpc_url,
+                default_token: token,
+                default_token_symbol: token_symbol,
+                default_token_decimals: token_decimals,
+                native_symbol,
+            },
+            env,
+        ),
+        Commands::RemoveNetwork { name } => cmd_remove_network(&name, env),
         Commands::ListKeys => cmd_list_keys(),
+        Commands::ListTokens { network } => cmd_list_tokens(network, env),
+        Commands::AddToken {
+            symbol,
+            address,
+            decimals,
+            network,
+        } => cmd_add_token(&symbol, TokenEntry { address, decimals }, network, env),
+        Commands::RemoveToken { symbol, network } => cmd_remove_token(&symbol, network, env),
+        Commands::TestRpc => cmd_test_rpc(env),
+        Commands::MigrateSecrets {
+            store,
+            keep_plaintext,
+        } => cmd_migrate_secrets(&store, keep_plaintext, env),
+    }?;
+    Ok(true)
+}
+
+/// Write an older config.toml back at the current config version, before any command reads it
+fn upgrade_config() -> payment_common::Result<()> {
+    let path = default_config_path();
+    if let Some((backup, notes)) = upgrade_file(Some(&path))? {
+        eprintln!(
+            "Upgraded {} to config version {} (backup: {}):",
+            path.display(),
+            CONFIG_VERSION,
+            backup.display()
+        );
+        for note in notes {
+            eprintln!("  - {}", note);
+        }
     }
+    Ok(())
 }
 
-fn cmd_show() -> payment_common::Result<()> {
-    let config = Config::load()?;
+fn cmd_init(answers: Option<&Path>, force: bool, env: Option<&str>) -> payment_common::Result<()> {
+    let summary = init::cmd_init(answers, force, env)?;
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+    Ok(())
+}
+
+fn cmd_show(env: Option<&str>) -> payment_common::Result<()> {
+    let config = load_config(env)?;
 
+    println!("config_version = {}", config.config_version);
+    if let Some(env) = config.env_name() {
+        println!("# environment: {}", env);
+    }
+    println!();
     println!("[wallet]");
     println!("path = \"{}\"", config.wallet.path);
     println!("password_file = \"{}\"", config.wallet.password_file);
+    if let Some(store) = &config.wallet.secret_store {
+        println!("secret_store = \"{}\"", store);
+    }
     println!();
 
     println!("[network]");
@@ -84,6 +397,18 @@ fn cmd_show() -> payment_common::Result<()> {
     if let Some(rpc_url) = &config.network.rpc_url {
         println!("rpc_url = \"{}\"", rpc_url);
     }
+    if !config.network.rpc_urls.is_empty() {
+        let urls: Vec<_> = config
+            .network
+            .rpc_urls
+            .iter()
+            .map(|e| format!("\"{}\"", e.url))
+            .collect();
+        println!("rpc_urls = [{}]", urls.join(", "));
+    }
+    if let Some(timeout) = config.network.rpc_timeout_secs {
+        println!("rpc_timeout_secs = {}", timeout);
+    }
     println!();
 
     println!("[payment]");
@@ -99,12 +424,83 @@ fn cmd_show() -> payment_common::Result<()> {
     if let Some(max) = &config.payment.max_auto_payment {
         println!("max_auto_payment = \"{}\"", max);
     }
+    if let Some(age) = config.payment.max_price_age_secs {
+        println!("max_price_age_secs = {}", age);
+    }
+    if let Some(runway) = config.payment.min_gas_runway {
+        println!("min_gas_runway = {}", runway);
+    }
+    println!();
+
+    println!("[signer]");
+    println!("backend = \"{}\"", config.signer.backend);
+    if let Some(url) = &config.signer.url {
+        println!("url = \"{}\"", url);
+    }
+    if let Some(address) = &config.signer.address {
+        println!("address = \"{}\"", address);
+    }
+    println!("timeout_secs = {}", config.signer.timeout_secs);
+
+    Ok(())
+}
+
+fn cmd_show_origin(env: Option<&str>) -> payment_common::Result<()> {
+    let config = load_config(env)?;
+
+    println!("# data directory: {}", default_data_dir().display());
+    println!("# config file: {}", default_config_path().display());
+    if let Some(env) = config.env_name() {
+        println!("# environment: {}", env);
+    }
+    for key in Config::valid_keys() {
+        let value = config.get(key).unwrap_or_else(|| "(unset)".to_string());
+        let origin = match config.origin(key) {
+            ValueOrigin::Env => format!("env {}", env_var_for_key(key)),
+            origin => origin.to_string(),
+        };
+        println!("{:<32} = {:<44} # {}", key, value, origin);
+    }
 
     Ok(())
 }
 
-fn cmd_get(key: &str) -> payment_common::Result<()> {
-    let config = Config::load()?;
+fn cmd_show_json(origin: bool, env: Option<&str>) -> payment_common::Result<()> {
+    let config = load_config(env)?;
+
+    if !origin {
+        println!("{}", serde_json::to_string_pretty(&config)?);
+        return Ok(());
+    }
+
+    let keys: Vec<_> = Config::valid_keys()
+        .into_iter()
+        .map(|key| {
+            let origin = config.origin(key);
+            let mut entry = serde_json::json!({
+                "key": key,
+                "value": config.get(key),
+                "origin": origin,
+            });
+            if origin == ValueOrigin::Env {
+                entry["env"] = env_var_for_key(key).into();
+            }
+            entry
+        })
+        .collect();
+    let json = serde_json::json!({
+        "data_dir": default_data_dir().display().to_string(),
+        "config_file": default_config_path().display().to_string(),
+        "env": config.env_name(),
+        "keys": keys,
+    });
+    println!("{}", serde_json::to_string_pretty(&json)?);
+
+    Ok(())
+}
+
+fn cmd_get(key: &str, env: Option<&str>) -> payment_common::Result<()> {
+    let config = load_config(env)?;
 
     match config.get(key) {
         Some(value) => {
@@ -126,14 +522,14 @@ fn cmd_get(key: &str) -> payment_common::Result<()> {
     }
 }
 
-fn cmd_set(pairs: &[String]) -> payment_common::Result<()> {
+fn cmd_set(pairs: &[String], env: Option<&str>) -> payment_common::Result<()> {
     if !pairs.len().is_multiple_of(2) {
         return Err(payment_common::Error::InvalidArgument(
             "Arguments must be key-value pairs".to_string(),
         ));
     }
 
-    let mut config = Config::load()?;
+    let mut config = load_config_file(env)?;
 
     for chunk in pairs.chunks(2) {
         let key = &chunk[0];
@@ -148,8 +544,25 @@ fn cmd_set(pairs: &[String]) -> payment_common::Result<()> {
     Ok(())
 }
 
-fn cmd_use_network(profile: &str) -> payment_common::Result<()> {
-    let mut config = Config::load()?;
+fn cmd_unset(keys: &[String], env: Option<&str>) -> payment_common::Result<()> {
+    let mut config = load_config_file(env)?;
+
+    for key in keys {
+        config.unset(key)?;
+        match config.get(key) {
+            Some(default) => eprintln!("Unset {} (default: {})", key, default),
+            None => eprintln!("Unset {}", key),
+        }
+    }
+
+    config.save()?;
+    eprintln!("Configuration saved.");
+
+    Ok(())
+}
+
+fn cmd_use_network(profile: &str, env: Option<&str>) -> payment_common::Result<()> {
+    let mut config = load_config_file(env)?;
     config.apply_network_profile(profile)?;
     config.save()?;
 
@@ -191,20 +604,35 @@ fn cmd_use_network(profile: &str) -> payment_common::Result<()> {
     Ok(())
 }
 
-fn cmd_list_networks() -> payment_common::Result<()> {
+fn cmd_list_networks(env: Option<&str>) -> payment_common::Result<()> {
+    let config = load_config(env)?;
+
     println!("Available network profiles:");
     println!();
-    for profile in NETWORK_PROFILES {
+    for ResolvedNetworkProfile {
+        name,
+        source,
+        profile,
+    } in config.network_profiles()
+    {
+        let origin = if source == ProfileSource::BuiltIn {
+            String::new()
+        } else {
+            format!(" [{}]", source)
+        };
         println!(
-            "  {:<20} chain_id={:<10} {}",
-            profile.name, profile.chain_id, profile.rpc_url
+            "  {:<20} chain_id={:<10} {}{}",
+            name, profile.chain_id, profile.rpc_url, origin
         );
-        if let Some(token) = profile.default_token {
+        if let Some(symbol) = &profile.native_symbol {
+            println!("  {:<20} native_symbol={}", "", symbol);
+        }
+        if let Some(token) = &profile.default_token {
             println!(
                 "  {:<20} default_token={} ({})",
                 "",
                 token,
-                profile.default_token_symbol.unwrap_or("")
+                profile.default_token_symbol.as_deref().unwrap_or("")
             );
         }
     }
@@ -214,15 +642,411 @@ fn cmd_list_networks() -> payment_common::Result<()> {
     Ok(())
 }
 
+fn cmd_add_network(
+    name: &str,
+    profile: UserNetworkProfile,
+    env: Option<&str>,
+) -> payment_common::Result<()> {
+    let mut config = load_config_file(env)?;
+    let replaced = config.find_network_profile(name).map(|p| p.source);
+    config.add_network_profile(name, profile)?;
+    config.save()?;
+
+    match replaced {
+        Some(ProfileSource::BuiltIn) => {
+            eprintln!(
+                "Added network profile {} (overrides the built-in profile)",
+                name
+            )
+        }
+        Some(ProfileSource::Config) => eprintln!("Updated network profile {}", name),
+        Some(ProfileSource::NetworksFile) => eprintln!(
+            "Added network profile {} (overrides the networks.toml profile)",
+            name
+        ),
+        None => eprintln!("Added network profile {}", name),
+    }
+    eprintln!("Apply it with: payment-config use-network {}", name);
+
+    Ok(())
+}
+
+fn cmd_remove_network(name: &str, env: Option<&str>) -> payment_common::Result<()> {
+    let mut config = load_config_file(env)?;
+    match config.remove_network_profile(name)? {
+        ProfileSource::NetworksFile => {
+            config.save_networks_file(&networks_path_for(&default_config_path()))?
+        }
+        _ => config.save()?,
+    }
+    eprintln!("Removed network profile {}", name);
+
+    if config.network.name.as_deref() == Some(name) {
+        eprintln!(
+            "Note: the active network is still configured as {}; run payment-config use-network to switch",
+            name
+        );
+    }
+
+    Ok(())
+}
+
+fn cmd_use_env(name: Option<&str>) -> payment_common::Result<()> {
+    let mut config = load_config_unselected()?;
+    if let Some(name) = name {
+        if !config.envs.contains_key(name) {
+            return Err(payment_common::Error::Config(format!(
+                "Unknown environment: {} (create it with payment-config add-env {})",
+                name, name
+            )));
+        }
+    }
+    config.active_env = name.map(str::to_string);
+    config.save()?;
+
+    match name {
+        Some(name) => eprintln!("Default environment: {}", name),
+        None => eprintln!("Default environment cleared; using the top-level settings"),
+    }
+    Ok(())
+}
+
+fn cmd_list_envs() -> payment_common::Result<()> {
+    let config = load_config_unselected()?;
+    if config.envs.is_empty() {
+        eprintln!("No environments defined. Create one with: payment-config add-env <NAME>");
+        return Ok(());
+    }
+
+    println!("Environments:");
+    println!();
+    for (name, env) in &config.envs {
+        let marker = if config.active_env.as_deref() == Some(name) {
+            "*"
+        } else {
+            " "
+        };
+        println!(
+            "{} {:<16} {:<20} chain_id={:<10} wallet={}",
+            marker,
+            name,
+            env.network.name.as_deref().unwrap_or("-"),
+            env.network
+                .chain_id
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            env.wallet.path
+        );
+    }
+    println!();
+    println!("* = default (payment-config use-env); select another with --env <NAME>");
+
+    Ok(())
+}
+
+fn cmd_add_env(name: &str, share_wallet: bool, env: Option<&str>) -> payment_common::Result<()> {
+    let mut config = load_config_file(env)?;
+    if config.envs.contains_key(name) {
+        return Err(payment_common::Error::Config(format!(
+            "Environment {} already exists",
+            name
+        )));
+    }
+
+    let mut env = config.sections();
+    if !share_wallet {
+        env.wallet.path = format!("wallet-{}.json", name);
+        env.wallet.password_file = format!("password-{}.txt", name);
+        if env.wallet.secret_store.is_some() {
+            env.wallet.secret_store = Some(format!("password-{}.enc", name));
+        }
+    }
+    config.add_env(name, env)?;
+    config.save()?;
+
+    eprintln!("Added environment {}", name);
+    if !share_wallet {
+        eprintln!(
+            "Create its wallet with: create-wallet --env {}   (or import one with payment-config --env {} init)",
+            name, name
+        );
+    }
+    Ok(())
+}
+
+fn cmd_remove_env(name: &str) -> payment_common::Result<()> {
+    let mut config = load_config_unselected()?;
+    let was_default = config.active_env.as_deref() == Some(name);
+    config.remove_env(name)?;
+    config.save()?;
+
+    eprintln!("Removed environment {}", name);
+    if was_default {
+        eprintln!("Default environment cleared; using the top-level settings");
+    }
+    Ok(())
+}
+
 fn cmd_list_keys() -> payment_common::Result<()> {
     println!("Valid configuration keys:");
     println!();
-    for key in Config::valid_keys() {
-        println!("  {}", key);
+    for spec in KEY_SPECS {
+        println!(
+            "  {:<32} {:<14} {}",
+            spec.key,
+            spec.kind.to_string(),
+            spec.description
+        );
     }
     println!();
     println!("Usage: payment-config get <key>");
     println!("       payment-config set <key> <value> [<key> <value> ...]");
+    println!("       payment-config unset <key> [<key> ...]");
+
+    Ok(())
+}
+
+/// Network to operate on: the --network flag or the active network
+fn token_network(config: &Config, network: Option<String>) -> payment_common::Result<String> {
+    network
+        .or_else(|| config.network.name.clone())
+        .ok_or_else(|| {
+            payment_common::Error::MissingConfig("network.name (or pass --network)".to_string())
+        })
+}
+
+fn cmd_list_tokens(network: Option<String>, env: Option<&str>) -> payment_common::Result<()> {
+    let config = load_config(env)?;
+    let network = token_network(&config, network)?;
+
+    println!("Tokens on {}:", network);
+    println!();
+    for (symbol, entry) in config.token_registry(&network) {
+        println!(
+            "  {:<10} {} decimals={}",
+            symbol, entry.address, entry.decimals
+        );
+    }
+    println!();
+    println!("Usage: pay --token <SYMBOL> ...");
+
+    Ok(())
+}
+
+fn cmd_add_token(
+    symbol: &str,
+    entry: TokenEntry,
+    network: Option<String>,
+    env: Option<&str>,
+) -> payment_common::Result<()> {
+    let mut config = load_config_file(env)?;
+    let network = token_network(&config, network)?;
+    config.add_token(&network, symbol, entry)?;
+    config.save()?;
+
+    eprintln!("Registered {} on {}", symbol, network);
+    Ok(())
+}
+
+fn cmd_remove_token(
+    symbol: &str,
+    network: Option<String>,
+    env: Option<&str>,
+) -> payment_common::Result<()> {
+    let mut config = load_config_file(env)?;
+    let network = token_network(&config, network)?;
+    config.remove_token(&network, symbol)?;
+    config.save()?;
+
+    eprintln!("Removed {} from {}", symbol, network);
+    Ok(())
+}
+
+fn cmd_contacts(command: ContactsCommand, env: Option<&str>) -> payment_common::Result<()> {
+    let mut book = ContactBook::load()?;
+    match command {
+        ContactsCommand::Add {
+            label,
+            address,
+            token,
+            note,
+        } => {
+            if let Some(token) = &token {
+                // Catch typos now rather than at payment time; the decimals of an
+                // unregistered address are read from its contract when paying
+                if token.starts_with("0x") {
+                    token.parse::<alloy::primitives::Address>().map_err(|_| {
+                        payment_common::Error::InvalidArgument(format!(
+                            "Invalid token address: {}",
+                            token
+                        ))
+                    })?;
+                } else {
+                    load_config(env)?.resolve_token(token)?;
+                }
+            }
+            let existing = book
+                .contacts()
+                .iter()
+                .find(|(other, c)| {
+                    other.as_str() != label && c.address.eq_ignore_ascii_case(&address)
+                })
+                .map(|(other, _)| other.clone());
+            book.add(
+                &label,
+                Contact {
+                    address,
+                    token,
+                    note,
+                },
+            )?;
+            book.save()?;
+
+            if let Some(other) = existing {
+                eprintln!("Note: the same address is also saved as @{}", other);
+            }
+            eprintln!("Saved contact @{}", label.trim_start_matches('@'));
+        }
+        ContactsCommand::List { json: true } => {
+            println!("{}", serde_json::to_string_pretty(book.contacts())?);
+        }
+        ContactsCommand::List { json: false } => {
+            if book.contacts().is_empty() {
+                eprintln!(
+                    "No contacts in {}. Add one with: payment-config contacts add <LABEL> <ADDRESS>",
+                    default_contacts_path().display()
+                );
+                return Ok(());
+            }
+            println!("Contacts:");
+            println!();
+            for (label, contact) in book.contacts() {
+                println!(
+                    "  @{:<16} {} token={}{}",
+                    label,
+                    contact.address,
+                    contact.token.as_deref().unwrap_or("-"),
+                    contact
+                        .note
+                        .as_deref()
+                        .map(|n| format!("  # {}", n))
+                        .unwrap_or_default()
+                );
+            }
+            println!();
+            println!("Usage: pay --to @<LABEL> --amount <AMOUNT>");
+        }
+        ContactsCommand::Remove { label } => {
+            let contact = book.remove(&label)?;
+            book.save()?;
+            eprintln!(
+                "Removed contact @{} ({})",
+                label.trim_start_matches('@'),
+                contact.address
+            );
+        }
+    }
+    Ok(())
+}
+
+fn cmd_test_rpc(env: Option<&str>) -> payment_common::Result<()> {
+    let config = load_config(env)?;
+    let endpoints = config.rpc_endpoints();
+    if endpoints.is_empty() {
+        return Err(payment_common::Error::MissingConfig(
+            "network.rpc_url".to_string(),
+        ));
+    }
+    let timeout = config
+        .network
+        .rpc_timeout_secs
+        .map(Duration::from_secs)
+        .unwrap_or(rpc::DEFAULT_RPC_TIMEOUT);
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    let mut results = Vec::new();
+    for endpoint in &endpoints {
+        eprintln!("Testing {}...", endpoint.url);
+        let health = runtime.block_on(rpc::check_endpoint(endpoint, timeout));
+        let mut result = serde_json::to_value(&health)?;
+        if let (Some(expected), Some(actual)) = (config.network.chain_id, health.chain_id) {
+            result["chain_id_matches"] = (expected == actual).into();
+        }
+        results.push(result);
+    }
+
+    let report = serde_json::json!({
+        "expected_chain_id": config.network.chain_id,
+        "endpoints": results,
+    });
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    if !results.iter().any(|r| r["ok"] == true) {
+        return Err(payment_common::Error::Network(
+            "No RPC endpoint is reachable".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn cmd_doctor(env: Option<&str>) -> payment_common::Result<bool> {
+    let config = load_config(env)?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    let report = runtime.block_on(doctor::diagnose(&config));
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    if !report.ok {
+        eprintln!("Some checks failed; see the suggested fixes.");
+    }
+    Ok(report.ok)
+}
+
+fn cmd_migrate_secrets(
+    store: &str,
+    keep_plaintext: bool,
+    env: Option<&str>,
+) -> payment_common::Result<()> {
+    let mut config = load_config_file(env)?;
+    if let Some(existing) = &config.wallet.secret_store {
+        return Err(payment_common::Error::Config(format!(
+            "Secret store already configured: {}",
+            existing
+        )));
+    }
+
+    let password_path = config.password_path();
+    let password = Wallet::load_password(Some(&password_path))?;
+    let passphrase = machine_passphrase()?;
+
+    eprintln!("Sealing wallet password...");
+    let sealed = SealedSecret::seal(&password, &passphrase)?;
+    // Make sure the store opens before touching the plaintext file
+    if sealed.open(&passphrase)? != password {
+        return Err(payment_common::Error::SecretStore(
+            "Sealed secret did not round-trip".to_string(),
+        ));
+    }
+
+    config.set("wallet.secret_store", store)?;
+    let store_path = config
+        .secret_store_path()
+        .expect("secret store was just configured");
+    sealed.save(&store_path)?;
+    config.save()?;
+    eprintln!("Password sealed in {}", store_path.display());
+
+    if keep_plaintext {
+        eprintln!(
+            "Kept plaintext password file {} (delete it once you have checked the migration)",
+            password_path.display()
+        );
+    } else {
+        fs::remove_file(&password_path)?;
+        eprintln!(
+            "Removed plaintext password file {}",
+            password_path.display()
+        );
+    }
 
     Ok(())
 }
@@ -0,0 +1,216 @@
+use alloy::eips::BlockId;
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol_types::SolCall;
+use clap::Parser;
+use payment_common::allowances::{self, IERC20Approve};
+use payment_common::signer::{load_signer, send_transaction, SignerEvent};
+use payment_common::{payments, rpc, Config, Error};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Revoke an ERC-20 allowance granted by the payment wallet
+///
+/// Sends `approve(spender, 0)` on the token, signed with the same backend as `pay`
+/// (remote signer, payment-agent or the keystore). Prints the transaction hash.
+#[derive(Parser, Debug)]
+#[command(name = "revoke")]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Spender whose allowance to remove
+    #[arg(long)]
+    spender: Address,
+
+    /// Token the allowance is on (registry symbol, e.g. USDC, or contract address)
+    #[arg(long)]
+    token: String,
+
+    /// Path to the wallet keystore file
+    #[arg(long, short = 'w')]
+    wallet: Option<PathBuf>,
+
+    /// Wallet password
+    #[arg(long)]
+    password: Option<String>,
+
+    /// Read wallet password from file
+    #[arg(long, conflicts_with = "password")]
+    password_file: Option<PathBuf>,
+
+    /// Path to configuration file
+    #[arg(long, short = 'c')]
+    config: Option<PathBuf>,
+
+    /// Named environment from config.toml (default: $PAYMENT_ENV, then active_env)
+    #[arg(long)]
+    env: Option<String>,
+
+    /// Don't wait for transaction confirmation
+    #[arg(long)]
+    no_wait: bool,
+
+    /// Print a JSON summary instead of only the transaction hash
+    #[arg(long)]
+    json: bool,
+}
+
+/// Summary of a revocation, printed with --json
+#[derive(Serialize)]
+struct RevokeOutput {
+    /// Absent when there was no allowance to revoke
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tx_hash: Option<String>,
+    status: &'static str,
+    owner: String,
+    token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_symbol: Option<String>,
+    spender: String,
+    /// Raw allowance before the revocation
+    previous_allowance: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    network: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_number: Option<u64>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+    let json = args.json;
+
+    match run(args).await {
+        Ok(output) if json => match serde_json::to_string_pretty(&output) {
+            Ok(output) => {
+                println!("{}", output);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("Error serializing output: {}", e);
+                ExitCode::from(1)
+            }
+        },
+        Ok(output) => {
+            if let Some(tx_hash) = output.tx_hash {
+                println!("{}", tx_hash);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code() as u8)
+        }
+    }
+}
+
+async fn run(args: Args) -> payment_common::Result<RevokeOutput> {
+    let config = Config::load_env(args.config.as_deref(), args.env.as_deref())?;
+    for warning in config.warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+    if let Some(env) = config.env_name() {
+        eprintln!("Environment: {}", env);
+    }
+    let wallet_path = args.wallet.unwrap_or_else(|| config.wallet_path());
+
+    let signer = load_signer(
+        &config,
+        &wallet_path,
+        args.password,
+        args.password_file.as_deref(),
+        print_signer_event,
+    )
+    .await?;
+    let owner = signer.address();
+    let provider = ProviderBuilder::new()
+        .connect_client(rpc::client_from_config(&config, |failover| {
+            eprintln!("Warning: {}", failover)
+        })?);
+
+    if let Some(expected) = config.network.chain_id {
+        let actual = provider
+            .get_chain_id()
+            .await
+            .map_err(|e| Error::Network(format!("Failed to get chain ID: {}", e)))?;
+        if actual != expected {
+            return Err(Error::Config(format!(
+                "Chain ID mismatch: expected {}, got {}",
+                expected, actual
+            )));
+        }
+    }
+    let token = payments::resolve_token(&config, &args.token, &provider).await?;
+
+    let previous = allowances::allowance(
+        &provider,
+        token.address,
+        owner,
+        args.spender,
+        BlockId::latest(),
+    )
+    .await?;
+    let mut output = RevokeOutput {
+        tx_hash: None,
+        status: "none",
+        owner: owner.to_string(),
+        token: token.address.to_string(),
+        token_symbol: token.symbol.clone(),
+        spender: args.spender.to_string(),
+        previous_allowance: previous.to_string(),
+        network: config.network.name.clone(),
+        block_number: None,
+    };
+    let token_name = token
+        .symbol
+        .clone()
+        .unwrap_or_else(|| token.address.to_string());
+    if previous.is_zero() {
+        eprintln!("{} has no {} allowance to revoke", args.spender, token_name);
+        return Ok(output);
+    }
+
+    eprintln!(
+        "Revoking {} allowance of {} (raw: {})...",
+        token_name, args.spender, previous
+    );
+    let tx = TransactionRequest::default()
+        .with_from(owner)
+        .with_to(token.address)
+        .with_input(IERC20Approve::approveCall::new((args.spender, U256::ZERO)).abi_encode());
+    let pending_tx = send_transaction(&provider, signer.as_ref(), tx).await?;
+
+    let tx_hash = *pending_tx.tx_hash();
+    eprintln!("Transaction sent: {}", tx_hash);
+    output.tx_hash = Some(tx_hash.to_string());
+    output.status = "sent";
+
+    if !args.no_wait {
+        eprintln!("Waiting for confirmation...");
+        let receipt = pending_tx
+            .get_receipt()
+            .await
+            .map_err(|e| Error::Transaction(e.to_string()))?;
+        if !receipt.status() {
+            return Err(Error::Transaction("Transaction reverted".to_string()));
+        }
+        eprintln!(
+            "Confirmed in block {}",
+            receipt.block_number.unwrap_or_default()
+        );
+        output.status = "confirmed";
+        output.block_number = receipt.block_number;
+    }
+    Ok(output)
+}
+
+/// Report which signer is used
+fn print_signer_event(event: &SignerEvent) {
+    if event.is_warning() {
+        eprintln!("Warning: {}", event);
+    } else {
+        eprintln!("{}", event);
+    }
+}
@@ -0,0 +1,91 @@
+use alloy::primitives::hex;
+use clap::Parser;
+use payment_common::signer::{load_signer, message_bytes, SignerEvent};
+use payment_common::Config;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Sign a message with the payment wallet
+///
+/// Produces an EIP-191 `personal_sign` signature, e.g. to prove control of
+/// the payment address when registering with a service. Prints JSON.
+#[derive(Parser, Debug)]
+#[command(name = "sign-message")]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Message to sign (UTF-8 text, or hex with --hex)
+    message: String,
+
+    /// Treat the message as 0x-prefixed hex bytes
+    #[arg(long)]
+    hex: bool,
+
+    /// Path to the wallet keystore file
+    #[arg(long, short = 'w')]
+    wallet: Option<PathBuf>,
+
+    /// Wallet password
+    #[arg(long)]
+    password: Option<String>,
+
+    /// Read wallet password from file
+    #[arg(long, conflicts_with = "password")]
+    password_file: Option<PathBuf>,
+
+    /// Path to configuration file
+    #[arg(long, short = 'c')]
+    config: Option<PathBuf>,
+
+    /// Named environment from config.toml (default: $PAYMENT_ENV, then active_env)
+    #[arg(long)]
+    env: Option<String>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code() as u8)
+        }
+    }
+}
+
+async fn run(args: Args) -> payment_common::Result<()> {
+    let config = Config::load_env(args.config.as_deref(), args.env.as_deref())?;
+    for warning in config.warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+    let wallet_path = args.wallet.unwrap_or_else(|| config.wallet_path());
+    let message = message_bytes(&args.message, args.hex)?;
+
+    let signer = load_signer(
+        &config,
+        &wallet_path,
+        args.password,
+        args.password_file.as_deref(),
+        print_signer_event,
+    )
+    .await?;
+    let signature = signer.sign_message(&message).await?;
+
+    let output = serde_json::json!({
+        "address": signer.address().to_string(),
+        "message": args.message,
+        "signature": hex::encode_prefixed(signature.as_bytes()),
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+/// Report which signer is used
+fn print_signer_event(event: &SignerEvent) {
+    if event.is_warning() {
+        eprintln!("Warning: {}", event);
+    } else {
+        eprintln!("{}", event);
+    }
+}
@@ -0,0 +1,93 @@
+use alloy::primitives::hex;
+use clap::Parser;
+use payment_common::signer::{load_signer, load_typed_data, SignerEvent};
+use payment_common::{Config, Error};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Sign EIP-712 typed data with the payment wallet
+///
+/// Reads the typed data JSON (`types`, `primaryType`, `domain`, `message`,
+/// as accepted by `eth_signTypedData_v4`) and prints the signing hash and
+/// signature as JSON.
+#[derive(Parser, Debug)]
+#[command(name = "sign-typed-data")]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Typed data JSON file (`-` reads stdin)
+    file: PathBuf,
+
+    /// Path to the wallet keystore file
+    #[arg(long, short = 'w')]
+    wallet: Option<PathBuf>,
+
+    /// Wallet password
+    #[arg(long)]
+    password: Option<String>,
+
+    /// Read wallet password from file
+    #[arg(long, conflicts_with = "password")]
+    password_file: Option<PathBuf>,
+
+    /// Path to configuration file
+    #[arg(long, short = 'c')]
+    config: Option<PathBuf>,
+
+    /// Named environment from config.toml (default: $PAYMENT_ENV, then active_env)
+    #[arg(long)]
+    env: Option<String>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code() as u8)
+        }
+    }
+}
+
+async fn run(args: Args) -> payment_common::Result<()> {
+    let config = Config::load_env(args.config.as_deref(), args.env.as_deref())?;
+    for warning in config.warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+    let wallet_path = args.wallet.unwrap_or_else(|| config.wallet_path());
+
+    let typed_data = load_typed_data(&args.file)?;
+    let hash = typed_data
+        .eip712_signing_hash()
+        .map_err(|e| Error::InvalidArgument(format!("Invalid typed data: {}", e)))?;
+
+    let signer = load_signer(
+        &config,
+        &wallet_path,
+        args.password,
+        args.password_file.as_deref(),
+        print_signer_event,
+    )
+    .await?;
+    let signature = signer.sign_typed_data(&typed_data).await?;
+
+    let output = serde_json::json!({
+        "address": signer.address().to_string(),
+        "primary_type": typed_data.primary_type,
+        "hash": hash.to_string(),
+        "signature": hex::encode_prefixed(signature.as_bytes()),
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+/// Report which signer is used
+fn print_signer_event(event: &SignerEvent) {
+    if event.is_warning() {
+        eprintln!("Warning: {}", event);
+    } else {
+        eprintln!("{}", event);
+    }
+}
@@ -0,0 +1,799 @@
+use crate::server;
+use alloy::consensus::transaction::SignerRecoverable;
+use alloy::consensus::{Transaction, TxEnvelope};
+use alloy::eips::eip2718::Decodable2718;
+use alloy::primitives::aliases::U80;
+use alloy::primitives::{hex, keccak256, Address, Bytes, Log, LogData, TxKind, B256, I256, U256};
+use alloy::sol;
+use alloy::sol_types::{SolCall, SolEvent, SolInterface};
+use payment_common::gas::{self, NATIVE_TRANSFER_GAS};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Chain ID the mock chain reports unless created with `with_chain_id`
+pub const DEFAULT_CHAIN_ID: u64 = 31337;
+
+/// Gas price (and base fee) in wei: 1 gwei
+pub const GAS_PRICE: u128 = 1_000_000_000;
+
+/// Gas charged for any token call
+pub const TOKEN_CALL_GAS: u64 = 52_000;
+
+/// Timestamp of block 0; each later block is 12 seconds after the previous one
+const GENESIS_TIMESTAMP: u64 = 1_700_000_000;
+
+sol! {
+    interface IMockToken {
+        event Transfer(address indexed from, address indexed to, uint256 value);
+        event Approval(address indexed owner, address indexed spender, uint256 value);
+
+        function balanceOf(address account) external view returns (uint256);
+        function decimals() external view returns (uint8);
+        function allowance(address owner, address spender) external view returns (uint256);
+        function transfer(address to, uint256 amount) external returns (bool);
+        function approve(address spender, uint256 amount) external returns (bool);
+    }
+
+    interface IMockGasPriceOracle {
+        function getL1Fee(bytes memory data) external view returns (uint256);
+    }
+
+    interface IMockPriceFeed {
+        function decimals() external view returns (uint8);
+        function description() external view returns (string);
+        function latestRoundData() external view returns (
+            uint80 roundId,
+            int256 answer,
+            uint256 startedAt,
+            uint256 updatedAt,
+            uint80 answeredInRound
+        );
+    }
+}
+
+/// An Ethereum JSON-RPC node served from a background thread, with ERC-20 tokens
+/// simulated natively rather than by an EVM.
+///
+/// Transactions are checked like a real node checks them (signature, chain ID, nonce,
+/// balance for gas and value) and each one is mined into its own block immediately.
+#[derive(Clone)]
+pub struct MockChain {
+    url: String,
+    state: Arc<Mutex<ChainState>>,
+}
+
+#[derive(Clone, Default)]
+struct Account {
+    balance: U256,
+    nonce: u64,
+}
+
+#[derive(Clone)]
+struct Token {
+    decimals: u8,
+    balances: HashMap<Address, U256>,
+    allowances: HashMap<(Address, Address), U256>,
+}
+
+/// A Chainlink aggregator whose answer is always current
+struct PriceFeed {
+    description: String,
+    answer: U256,
+    decimals: u8,
+}
+
+/// Accounts and tokens as of one block
+type Snapshot = (HashMap<Address, Account>, HashMap<Address, Token>);
+
+struct ChainState {
+    chain_id: u64,
+    accounts: HashMap<Address, Account>,
+    tokens: HashMap<Address, Token>,
+    feeds: HashMap<Address, PriceFeed>,
+    /// L1 data fee charged by the OP-stack GasPriceOracle, when the chain has one
+    l1_fee: Option<U256>,
+    /// Transaction hashes mined in each block, by block number
+    blocks: Vec<Vec<B256>>,
+    /// Accounts and tokens at the end of each block before the latest, by block number
+    history: Vec<Snapshot>,
+    receipts: HashMap<B256, Value>,
+    methods: Vec<String>,
+}
+
+/// A JSON-RPC error response
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self {
+            code: -32602,
+            message: message.into(),
+        }
+    }
+
+    /// What geth returns for a rejected transaction
+    fn rejected(message: impl Into<String>) -> Self {
+        Self {
+            code: -32000,
+            message: message.into(),
+        }
+    }
+
+    fn reverted(reason: &str) -> Self {
+        Self {
+            code: 3,
+            message: format!("execution reverted: {}", reason),
+        }
+    }
+}
+
+type RpcResult = Result<Value, RpcError>;
+
+impl MockChain {
+    /// Start a chain with `DEFAULT_CHAIN_ID`
+    pub fn start() -> Self {
+        Self::with_chain_id(DEFAULT_CHAIN_ID)
+    }
+
+    pub fn with_chain_id(chain_id: u64) -> Self {
+        let state = Arc::new(Mutex::new(ChainState {
+            chain_id,
+            accounts: HashMap::new(),
+            tokens: HashMap::new(),
+            feeds: HashMap::new(),
+            l1_fee: None,
+            blocks: vec![Vec::new()],
+            history: Vec::new(),
+            receipts: HashMap::new(),
+            methods: Vec::new(),
+        }));
+
+        let served = Arc::clone(&state);
+        let url = server::spawn_json_rpc(move |request| {
+            served
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .handle(request)
+        });
+        Self { url, state }
+    }
+
+    /// HTTP endpoint to use as `network.rpc_url`
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.state().chain_id
+    }
+
+    /// Set the native balance of `address`
+    pub fn fund(&self, address: Address, wei: U256) {
+        self.state().accounts.entry(address).or_default().balance = wei;
+    }
+
+    /// Create a token with `decimals` at a fresh address
+    pub fn deploy_token(&self, decimals: u8) -> Address {
+        let mut state = self.state();
+        let address = Address::from_word(keccak256(format!("token-{}", state.tokens.len())));
+        state.tokens.insert(
+            address,
+            Token {
+                decimals,
+                balances: HashMap::new(),
+                allowances: HashMap::new(),
+            },
+        );
+        address
+    }
+
+    /// Create a `<symbol> / USD` price feed answering `answer` scaled by `10^decimals`
+    pub fn deploy_price_feed(&self, symbol: &str, answer: U256, decimals: u8) -> Address {
+        let mut state = self.state();
+        let address = Address::from_word(keccak256(format!("feed-{}", state.feeds.len())));
+        state.feeds.insert(
+            address,
+            PriceFeed {
+                description: format!("{} / USD", symbol),
+                answer,
+                decimals,
+            },
+        );
+        address
+    }
+
+    /// Act as an OP-stack chain whose GasPriceOracle quotes `wei` of L1 data fee for
+    /// any transaction (the fee is only quoted, not charged)
+    pub fn set_l1_fee(&self, wei: U256) {
+        self.state().l1_fee = Some(wei);
+    }
+
+    /// Set the `token` balance of `holder` (in raw units)
+    pub fn mint(&self, token: Address, holder: Address, amount: U256) {
+        self.state()
+            .tokens
+            .get_mut(&token)
+            .expect("token deployed on the mock chain")
+            .balances
+            .insert(holder, amount);
+    }
+
+    pub fn balance(&self, address: Address) -> U256 {
+        self.state()
+            .accounts
+            .get(&address)
+            .map(|a| a.balance)
+            .unwrap_or_default()
+    }
+
+    pub fn token_balance(&self, token: Address, holder: Address) -> U256 {
+        self.state()
+            .tokens
+            .get(&token)
+            .and_then(|t| t.balances.get(&holder).copied())
+            .unwrap_or_default()
+    }
+
+    pub fn nonce(&self, address: Address) -> u64 {
+        self.state()
+            .accounts
+            .get(&address)
+            .map(|a| a.nonce)
+            .unwrap_or_default()
+    }
+
+    pub fn block_number(&self) -> u64 {
+        self.state().latest()
+    }
+
+    /// Mine an empty block: later `fund` and `mint` calls no longer show at the blocks before it
+    pub fn mine(&self) {
+        let mut state = self.state();
+        state.close_block();
+        state.blocks.push(Vec::new());
+    }
+
+    /// JSON-RPC methods called so far, in order
+    pub fn methods(&self) -> Vec<String> {
+        self.state().methods.clone()
+    }
+
+    fn state(&self) -> MutexGuard<'_, ChainState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Answer an aggregator call, reporting the price as updated just now
+fn price_feed_call(feed: &PriceFeed, input: &[u8]) -> RpcResult {
+    use IMockPriceFeed::IMockPriceFeedCalls as Calls;
+    let output = match Calls::abi_decode(input) {
+        Ok(Calls::decimals(_)) => IMockPriceFeed::decimalsCall::abi_encode_returns(&feed.decimals),
+        Ok(Calls::description(_)) => {
+            IMockPriceFeed::descriptionCall::abi_encode_returns(&feed.description)
+        }
+        Ok(Calls::latestRoundData(_)) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            IMockPriceFeed::latestRoundDataCall::abi_encode_returns(
+                &IMockPriceFeed::latestRoundDataReturn {
+                    roundId: U80::from(1),
+                    answer: I256::from_raw(feed.answer),
+                    startedAt: U256::from(now),
+                    updatedAt: U256::from(now),
+                    answeredInRound: U80::from(1),
+                },
+            )
+        }
+        Err(_) => return Err(RpcError::reverted("unknown function")),
+    };
+    Ok(json!(Bytes::from(output)))
+}
+
+impl ChainState {
+    fn handle(&mut self, request: &Value) -> Value {
+        let method = request["method"].as_str().unwrap_or_default();
+        self.methods.push(method.to_string());
+        match self.dispatch(method, &request["params"]) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+            Err(e) => json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": { "code": e.code, "message": e.message },
+            }),
+        }
+    }
+
+    fn dispatch(&mut self, method: &str, params: &Value) -> RpcResult {
+        match method {
+            "eth_chainId" => Ok(quantity(self.chain_id)),
+            "net_version" => Ok(json!(self.chain_id.to_string())),
+            "eth_gasPrice" => Ok(quantity(GAS_PRICE)),
+            "eth_maxPriorityFeePerGas" => Ok(quantity(0)),
+            "eth_feeHistory" => Ok(self.fee_history(params)),
+            "eth_blockNumber" => Ok(quantity(self.latest())),
+            "eth_getBlockByNumber" => Ok(self.block_by_number(&params[0])),
+            "eth_getBalance" => {
+                let address = param::<Address>(params, 0)?;
+                let accounts = self.accounts_at(&params[1])?;
+                Ok(quantity(
+                    accounts
+                        .get(&address)
+                        .map(|a| a.balance)
+                        .unwrap_or_default(),
+                ))
+            }
+            "eth_getTransactionCount" => {
+                let address = param::<Address>(params, 0)?;
+                let accounts = self.accounts_at(&params[1])?;
+                Ok(quantity(
+                    accounts.get(&address).map(|a| a.nonce).unwrap_or_default(),
+                ))
+            }
+            "eth_getCode" => {
+                let address = param::<Address>(params, 0)?;
+                // Any non-empty code marks a contract
+                let oracle = address == gas::GAS_PRICE_ORACLE && self.l1_fee.is_some();
+                Ok(json!(if self.tokens.contains_key(&address)
+                    || self.feeds.contains_key(&address)
+                    || oracle
+                {
+                    "0xfe"
+                } else {
+                    "0x"
+                }))
+            }
+            "eth_call" => self.call(&params[0], &params[1]),
+            "eth_estimateGas" => self.estimate_gas(&params[0]),
+            "eth_sendRawTransaction" => {
+                let raw = param::<Bytes>(params, 0)?;
+                self.send_raw_transaction(&raw)
+            }
+            "eth_getTransactionReceipt" => {
+                let hash = param::<B256>(params, 0)?;
+                Ok(self.receipts.get(&hash).cloned().unwrap_or(Value::Null))
+            }
+            // Block watching for pending transactions: receipts are polled instead
+            "eth_newBlockFilter" => Ok(json!("0x1")),
+            "eth_getFilterChanges" => Ok(json!([])),
+            "eth_uninstallFilter" => Ok(json!(true)),
+            _ => Err(RpcError {
+                code: -32601,
+                message: format!("the method {} does not exist/is not available", method),
+            }),
+        }
+    }
+
+    fn latest(&self) -> u64 {
+        self.blocks.len() as u64 - 1
+    }
+
+    /// Snapshot of the block a request names, or `None` for the latest block
+    /// (also when it names none)
+    fn snapshot(&self, block: &Value) -> Result<Option<&Snapshot>, RpcError> {
+        let number = match block.as_str() {
+            Some("latest" | "pending" | "safe" | "finalized") | None => return Ok(None),
+            Some("earliest") => 0,
+            Some(number) => u64::from_str_radix(number.trim_start_matches("0x"), 16)
+                .map_err(|_| RpcError::invalid_params(format!("invalid block: {}", number)))?,
+        };
+        match self.history.get(number as usize) {
+            Some(snapshot) => Ok(Some(snapshot)),
+            None if number == self.latest() => Ok(None),
+            None => Err(RpcError::rejected("header not found")),
+        }
+    }
+
+    fn accounts_at(&self, block: &Value) -> Result<&HashMap<Address, Account>, RpcError> {
+        Ok(self
+            .snapshot(block)?
+            .map_or(&self.accounts, |(accounts, _)| accounts))
+    }
+
+    fn tokens_at(&self, block: &Value) -> Result<&HashMap<Address, Token>, RpcError> {
+        Ok(self
+            .snapshot(block)?
+            .map_or(&self.tokens, |(_, tokens)| tokens))
+    }
+
+    /// Remember the state at the end of the latest block, before the next one changes it
+    fn close_block(&mut self) {
+        self.history
+            .push((self.accounts.clone(), self.tokens.clone()));
+    }
+
+    fn account(&mut self, address: Address) -> &mut Account {
+        self.accounts.entry(address).or_default()
+    }
+
+    fn block_by_number(&self, tag: &Value) -> Value {
+        let number = match tag.as_str() {
+            Some("latest" | "pending" | "safe" | "finalized") | None => self.latest(),
+            Some("earliest") => 0,
+            Some(number) => match u64::from_str_radix(number.trim_start_matches("0x"), 16) {
+                Ok(number) => number,
+                Err(_) => return Value::Null,
+            },
+        };
+        let Some(transactions) = self.blocks.get(number as usize) else {
+            return Value::Null;
+        };
+        json!({
+            "hash": block_hash(number),
+            "parentHash": if number == 0 { B256::ZERO } else { block_hash(number - 1) },
+            "sha3Uncles": B256::ZERO,
+            "miner": Address::ZERO,
+            "stateRoot": B256::ZERO,
+            "transactionsRoot": B256::ZERO,
+            "receiptsRoot": B256::ZERO,
+            "logsBloom": empty_bloom(),
+            "difficulty": "0x0",
+            "number": quantity(number),
+            "gasLimit": quantity(30_000_000),
+            "gasUsed": "0x0",
+            "timestamp": quantity(GENESIS_TIMESTAMP + 12 * number),
+            "extraData": "0x",
+            "mixHash": B256::ZERO,
+            "nonce": "0x0000000000000000",
+            "baseFeePerGas": quantity(GAS_PRICE),
+            "size": "0x200",
+            "uncles": [],
+            "transactions": transactions,
+        })
+    }
+
+    fn fee_history(&self, params: &Value) -> Value {
+        let blocks = params[0]
+            .as_str()
+            .and_then(|b| u64::from_str_radix(b.trim_start_matches("0x"), 16).ok())
+            .or_else(|| params[0].as_u64())
+            .unwrap_or(1)
+            .clamp(1, self.latest() + 1);
+        let percentiles = params[2].as_array().map_or(0, Vec::len);
+        json!({
+            "oldestBlock": quantity(self.latest() + 1 - blocks),
+            "baseFeePerGas": vec![quantity(GAS_PRICE); blocks as usize + 1],
+            "gasUsedRatio": vec![0.5; blocks as usize],
+            "reward": vec![vec![quantity(0); percentiles]; blocks as usize],
+        })
+    }
+
+    fn call(&self, tx: &Value, block: &Value) -> RpcResult {
+        let to = field::<Address>(tx, "to")?;
+        let tokens = self.tokens_at(block)?;
+        if let Some(feed) = self.feeds.get(&to) {
+            return price_feed_call(feed, &input(tx)?);
+        }
+        if let (true, Some(fee)) = (to == gas::GAS_PRICE_ORACLE, self.l1_fee) {
+            return match IMockGasPriceOracle::getL1FeeCall::abi_decode(&input(tx)?) {
+                Ok(_) => Ok(json!(Bytes::from(
+                    IMockGasPriceOracle::getL1FeeCall::abi_encode_returns(&fee)
+                ))),
+                Err(_) => Err(RpcError::reverted("unknown function")),
+            };
+        }
+        let Some(token) = tokens.get(&to) else {
+            // No contract there (e.g. Multicall3): empty return data, like a real node
+            return Ok(json!("0x"));
+        };
+        let input = input(tx)?;
+        let word = |value: U256| json!(format!("0x{:064x}", value));
+        match IMockToken::IMockTokenCalls::abi_decode(&input) {
+            Ok(IMockToken::IMockTokenCalls::balanceOf(call)) => Ok(word(
+                token
+                    .balances
+                    .get(&call.account)
+                    .copied()
+                    .unwrap_or_default(),
+            )),
+            Ok(IMockToken::IMockTokenCalls::decimals(_)) => Ok(word(U256::from(token.decimals))),
+            Ok(IMockToken::IMockTokenCalls::allowance(call)) => Ok(word(
+                token
+                    .allowances
+                    .get(&(call.owner, call.spender))
+                    .copied()
+                    .unwrap_or_default(),
+            )),
+            Ok(_) => Err(RpcError::reverted("state-changing call")),
+            Err(_) => Err(RpcError::reverted("unknown function")),
+        }
+    }
+
+    fn estimate_gas(&self, tx: &Value) -> RpcResult {
+        let from = field::<Address>(tx, "from").unwrap_or_default();
+        let Ok(to) = field::<Address>(tx, "to") else {
+            return Err(RpcError::invalid_params(
+                "contract creation is not supported",
+            ));
+        };
+        let Some(token) = self.tokens.get(&to) else {
+            return Ok(quantity(NATIVE_TRANSFER_GAS));
+        };
+        if let Ok(IMockToken::IMockTokenCalls::transfer(call)) =
+            IMockToken::IMockTokenCalls::abi_decode(&input(tx)?)
+        {
+            let balance = token.balances.get(&from).copied().unwrap_or_default();
+            if balance < call.amount {
+                return Err(RpcError::reverted("transfer amount exceeds balance"));
+            }
+        }
+        Ok(quantity(TOKEN_CALL_GAS))
+    }
+
+    fn send_raw_transaction(&mut self, raw: &[u8]) -> RpcResult {
+        let tx = TxEnvelope::decode_2718(&mut &raw[..])
+            .map_err(|e| RpcError::invalid_params(format!("invalid transaction: {}", e)))?;
+        let from = tx
+            .recover_signer()
+            .map_err(|_| RpcError::rejected("invalid sender: signature does not recover"))?;
+        if tx.chain_id() != Some(self.chain_id) {
+            return Err(RpcError::rejected(format!(
+                "invalid chain id: expected {}, got {:?}",
+                self.chain_id,
+                tx.chain_id()
+            )));
+        }
+
+        let nonce = self.account(from).nonce;
+        if tx.nonce() < nonce {
+            return Err(RpcError::rejected(format!(
+                "nonce too low: next nonce {}, tx nonce {}",
+                nonce,
+                tx.nonce()
+            )));
+        }
+        if tx.nonce() > nonce {
+            return Err(RpcError::rejected(format!(
+                "nonce too high: next nonce {}, tx nonce {}",
+                nonce,
+                tx.nonce()
+            )));
+        }
+
+        let to = match tx.kind() {
+            TxKind::Call(to) => to,
+            TxKind::Create => {
+                return Err(RpcError::rejected("contract creation is not supported"));
+            }
+        };
+        let is_token = self.tokens.contains_key(&to);
+        let intrinsic = if is_token {
+            TOKEN_CALL_GAS
+        } else {
+            NATIVE_TRANSFER_GAS
+        };
+        if tx.gas_limit() < intrinsic {
+            return Err(RpcError::rejected(format!(
+                "intrinsic gas too low: have {}, want {}",
+                tx.gas_limit(),
+                intrinsic
+            )));
+        }
+        let gas_price = tx.effective_gas_price(Some(GAS_PRICE as u64));
+        if gas_price < GAS_PRICE {
+            return Err(RpcError::rejected(
+                "max fee per gas less than block base fee",
+            ));
+        }
+        let upfront = U256::from(tx.gas_limit()) * U256::from(gas_price) + tx.value();
+        let balance = self.account(from).balance;
+        if balance < upfront {
+            return Err(RpcError::rejected(format!(
+                "insufficient funds for gas * price + value: balance {}, tx cost {}",
+                balance, upfront
+            )));
+        }
+
+        // Execute: the sender pays for the gas used even if the call fails
+        self.close_block();
+        let hash = *tx.tx_hash();
+        let number = self.latest() + 1;
+        let cost = U256::from(intrinsic) * U256::from(gas_price);
+        let account = self.account(from);
+        account.nonce += 1;
+        account.balance -= cost;
+        let (success, logs) = if is_token {
+            self.execute_token_call(from, to, tx.value(), tx.input())
+        } else {
+            self.account(from).balance -= tx.value();
+            self.account(to).balance += tx.value();
+            (true, Vec::new())
+        };
+
+        let logs: Vec<Value> = logs
+            .into_iter()
+            .enumerate()
+            .map(|(index, log)| {
+                json!({
+                    "address": log.address,
+                    "topics": log.data.topics(),
+                    "data": log.data.data,
+                    "blockNumber": quantity(number),
+                    "blockHash": block_hash(number),
+                    "transactionHash": hash,
+                    "transactionIndex": "0x0",
+                    "logIndex": quantity(index as u64),
+                    "removed": false,
+                })
+            })
+            .collect();
+        self.receipts.insert(
+            hash,
+            json!({
+                "type": quantity(tx.tx_type() as u8),
+                "status": if success { "0x1" } else { "0x0" },
+                "cumulativeGasUsed": quantity(intrinsic),
+                "gasUsed": quantity(intrinsic),
+                "effectiveGasPrice": quantity(gas_price),
+                "logs": logs,
+                "logsBloom": empty_bloom(),
+                "transactionHash": hash,
+                "transactionIndex": "0x0",
+                "blockHash": block_hash(number),
+                "blockNumber": quantity(number),
+                "from": from,
+                "to": to,
+                "contractAddress": null,
+            }),
+        );
+        self.blocks.push(vec![hash]);
+        Ok(json!(hash))
+    }
+
+    /// Apply a token `transfer` or `approve`; anything else reverts
+    fn execute_token_call(
+        &mut self,
+        from: Address,
+        address: Address,
+        value: U256,
+        input: &Bytes,
+    ) -> (bool, Vec<Log>) {
+        let token = self.tokens.get_mut(&address).expect("token exists");
+        let log = |data: LogData| Log { address, data };
+        if !value.is_zero() {
+            return (false, Vec::new());
+        }
+        match IMockToken::IMockTokenCalls::abi_decode(input) {
+            Ok(IMockToken::IMockTokenCalls::transfer(call)) => {
+                let balance = token.balances.get(&from).copied().unwrap_or_default();
+                if balance < call.amount {
+                    return (false, Vec::new());
+                }
+                token.balances.insert(from, balance - call.amount);
+                *token.balances.entry(call.to).or_default() += call.amount;
+                let event = IMockToken::Transfer {
+                    from,
+                    to: call.to,
+                    value: call.amount,
+                };
+                (true, vec![log(event.encode_log_data())])
+            }
+            Ok(IMockToken::IMockTokenCalls::approve(call)) => {
+                token.allowances.insert((from, call.spender), call.amount);
+                let event = IMockToken::Approval {
+                    owner: from,
+                    spender: call.spender,
+                    value: call.amount,
+                };
+                (true, vec![log(event.encode_log_data())])
+            }
+            _ => (false, Vec::new()),
+        }
+    }
+}
+
+fn quantity(value: impl std::fmt::LowerHex) -> Value {
+    json!(format!("{:#x}", value))
+}
+
+fn block_hash(number: u64) -> B256 {
+    keccak256(number.to_be_bytes())
+}
+
+fn empty_bloom() -> String {
+    hex::encode_prefixed([0u8; 256])
+}
+
+fn param<T: serde::de::DeserializeOwned>(params: &Value, index: usize) -> Result<T, RpcError> {
+    serde_json::from_value(params[index].clone())
+        .map_err(|e| RpcError::invalid_params(format!("invalid parameter {}: {}", index, e)))
+}
+
+fn field<T: serde::de::DeserializeOwned>(tx: &Value, name: &str) -> Result<T, RpcError> {
+    serde_json::from_value(tx[name].clone())
+        .map_err(|e| RpcError::invalid_params(format!("invalid {}: {}", name, e)))
+}
+
+/// Call data of a transaction object (`input`, or the older `data`)
+fn input(tx: &Value) -> Result<Bytes, RpcError> {
+    match tx.get("input").or_else(|| tx.get("data")) {
+        Some(data) => serde_json::from_value(data.clone())
+            .map_err(|e| RpcError::invalid_params(format!("invalid input: {}", e))),
+        None => Ok(Bytes::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::consensus::{SignableTransaction, TxLegacy};
+    use alloy::eips::eip2718::Encodable2718;
+    use alloy::network::TxSignerSync;
+    use alloy::signers::local::PrivateKeySigner;
+
+    const RECIPIENT: Address = Address::repeat_byte(0x22);
+
+    fn transfer(signer: &PrivateKeySigner, chain_id: u64, nonce: u64, wei: u64) -> Bytes {
+        let mut tx = TxLegacy {
+            chain_id: Some(chain_id),
+            nonce,
+            gas_price: GAS_PRICE,
+            gas_limit: NATIVE_TRANSFER_GAS,
+            to: TxKind::Call(RECIPIENT),
+            value: U256::from(wei),
+            input: Bytes::new(),
+        };
+        let signature = signer.sign_transaction_sync(&mut tx).unwrap();
+        TxEnvelope::from(tx.into_signed(signature))
+            .encoded_2718()
+            .into()
+    }
+
+    fn send(chain: &MockChain, raw: Bytes) -> Result<Value, String> {
+        chain
+            .state()
+            .send_raw_transaction(&raw)
+            .map_err(|e| e.message)
+    }
+
+    #[test]
+    fn test_send_raw_transaction_checks_nonce_and_chain() {
+        let chain = MockChain::start();
+        let signer = PrivateKeySigner::random();
+        let initial = U256::from(10u64.pow(18));
+        chain.fund(signer.address(), initial);
+
+        send(&chain, transfer(&signer, DEFAULT_CHAIN_ID, 0, 1000)).unwrap();
+        assert_eq!(chain.nonce(signer.address()), 1);
+        assert_eq!(chain.balance(RECIPIENT), U256::from(1000));
+        assert_eq!(
+            chain.balance(signer.address()),
+            initial - U256::from(1000 + NATIVE_TRANSFER_GAS as u128 * GAS_PRICE)
+        );
+        assert_eq!(chain.block_number(), 1);
+
+        // Replayed, skipped and foreign-chain transactions are rejected
+        let replay = send(&chain, transfer(&signer, DEFAULT_CHAIN_ID, 0, 1000));
+        assert!(replay.unwrap_err().contains("nonce too low"));
+        let gap = send(&chain, transfer(&signer, DEFAULT_CHAIN_ID, 2, 1000));
+        assert!(gap.unwrap_err().contains("nonce too high"));
+        let foreign = send(&chain, transfer(&signer, 1, 1, 1000));
+        assert!(foreign.unwrap_err().contains("invalid chain id"));
+
+        // Without funds for gas and value
+        let broke = PrivateKeySigner::random();
+        let unfunded = send(&chain, transfer(&broke, DEFAULT_CHAIN_ID, 0, 1));
+        assert!(unfunded.unwrap_err().contains("insufficient funds"));
+        assert_eq!(chain.block_number(), 1);
+    }
+
+    #[test]
+    fn test_send_raw_transaction_rejects_tampered_signature() {
+        let chain = MockChain::start();
+        let signer = PrivateKeySigner::random();
+        chain.fund(signer.address(), U256::from(10u64.pow(18)));
+
+        // Changing the value after signing makes the signature recover another sender
+        let mut raw = transfer(&signer, DEFAULT_CHAIN_ID, 0, 1000).to_vec();
+        let value = raw.iter().rposition(|b| *b == 0x03).unwrap();
+        raw[value] = 0x04;
+        assert!(send(&chain, raw.into()).is_err());
+        assert_eq!(chain.nonce(signer.address()), 0);
+    }
+}
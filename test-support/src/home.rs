@@ -0,0 +1,113 @@
+use crate::chain::MockChain;
+use alloy::primitives::Address;
+use payment_common::config::UserNetworkProfile;
+use payment_common::{Config, Wallet};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Network profile name the test config uses for the mock chain
+pub const NETWORK: &str = "mock";
+
+/// Symbol the test config registers a mock token under
+pub const TOKEN_SYMBOL: &str = "TEST";
+
+/// A temporary data directory (`PAYMENT_HOME`) with a new wallet, its password file,
+/// and a config.toml pointing at a mock chain
+pub struct TestHome {
+    dir: TempDir,
+    address: Address,
+}
+
+impl TestHome {
+    /// Set up a home on `chain`, with `token` (if any) as the default token
+    pub fn new(chain: &MockChain, token: Option<(Address, u8)>) -> Self {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let wallet_path = dir.path().join("wallet.json");
+        let password_path = dir.path().join("password.txt");
+        let wallet =
+            Wallet::create(None, Some(&wallet_path), Some(&password_path)).expect("create wallet");
+
+        let mut config = Config::default();
+        config.wallet.path = wallet_path.display().to_string();
+        config.wallet.password_file = password_path.display().to_string();
+        config
+            .add_network_profile(
+                NETWORK,
+                UserNetworkProfile {
+                    chain_id: chain.chain_id(),
+                    rpc_url: chain.url().to_string(),
+                    default_token: token.map(|(address, _)| address.to_string()),
+                    default_token_symbol: token.map(|_| TOKEN_SYMBOL.to_string()),
+                    default_token_decimals: token.map(|(_, decimals)| decimals),
+                    native_symbol: None,
+                },
+            )
+            .expect("add mock network profile");
+        // Name lookups go to the mainnet registries: keep them on the mock chain too
+        for network in ["ethereum-mainnet", "base-mainnet"] {
+            config
+                .add_network_profile(
+                    network,
+                    UserNetworkProfile {
+                        chain_id: chain.chain_id(),
+                        rpc_url: chain.url().to_string(),
+                        default_token: None,
+                        default_token_symbol: None,
+                        default_token_decimals: None,
+                        native_symbol: None,
+                    },
+                )
+                .expect("redirect mainnet profile");
+        }
+        config
+            .apply_network_profile(NETWORK)
+            .expect("apply mock network profile");
+        config
+            .save_to(Some(&dir.path().join("config.toml")))
+            .expect("save config");
+
+        Self {
+            address: wallet.address.parse().expect("wallet address"),
+            dir,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Address of the home's wallet
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Register `feed` as the mock network's `<symbol> / USD` price feed
+    pub fn add_price_feed(&self, symbol: &str, feed: Address) {
+        let path = self.path().join("config.toml");
+        let mut config = std::fs::read_to_string(&path).expect("read config");
+        config.push_str(&format!(
+            "\n[price_feeds.{}.{}]\naddress = \"{}\"\n",
+            NETWORK, symbol, feed
+        ));
+        std::fs::write(&path, config).expect("write config");
+    }
+
+    pub fn wallet_path(&self) -> PathBuf {
+        self.path().join("wallet.json")
+    }
+
+    /// A command running `program` against this home, isolated from the caller's
+    /// `PAYMENT_*` environment (data directory, environment, key overrides)
+    pub fn command(&self, program: impl AsRef<OsStr>) -> Command {
+        let mut command = Command::new(program);
+        for (key, _) in std::env::vars_os() {
+            if key.to_string_lossy().starts_with("PAYMENT_") {
+                command.env_remove(key);
+            }
+        }
+        command.env(payment_common::PAYMENT_HOME_ENV, self.path());
+        command
+    }
+}
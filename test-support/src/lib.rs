@@ -0,0 +1,6 @@
+mod chain;
+mod home;
+pub mod server;
+
+pub use chain::{MockChain, DEFAULT_CHAIN_ID, GAS_PRICE, TOKEN_CALL_GAS};
+pub use home::{TestHome, NETWORK, TOKEN_SYMBOL};
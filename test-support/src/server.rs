@@ -0,0 +1,72 @@
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+/// Bind a JSON-RPC server on a free local port and serve it from a background thread,
+/// returning its URL
+pub fn spawn_json_rpc(handle: impl Fn(&Value) -> Value + Send + 'static) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind JSON-RPC server");
+    let url = format!("http://{}", listener.local_addr().expect("local address"));
+    std::thread::spawn(move || serve_json_rpc(listener, handle));
+    url
+}
+
+/// Answer JSON-RPC requests over HTTP on `listener` until the process exits.
+///
+/// `handle` answers one request; batches are answered request by request. Each
+/// connection carries one HTTP request and is closed after the response.
+pub fn serve_json_rpc(listener: TcpListener, handle: impl Fn(&Value) -> Value) {
+    for stream in listener.incoming().flatten() {
+        if let Err(e) = handle_connection(stream, &handle) {
+            eprintln!("Warning: JSON-RPC request failed: {}", e);
+        }
+    }
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    handle: impl Fn(&Value) -> Value,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+
+    // Skip the request line and headers, remembering the body length
+    let mut content_length = 0;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body)?;
+
+    let response = match serde_json::from_slice::<Value>(&body) {
+        Ok(Value::Array(requests)) => Value::Array(requests.iter().map(&handle).collect()),
+        Ok(request) => handle(&request),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": null,
+            "error": { "code": -32700, "message": format!("Parse error: {}", e) },
+        }),
+    };
+    let payload = response.to_string();
+
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        payload.len(),
+        payload
+    )?;
+    stream.flush()
+}
@@ -0,0 +1,84 @@
+use alloy::primitives::Address;
+use clap::Parser;
+use payment_common::signer::{load_typed_data, message_bytes, parse_signature};
+use payment_common::Error;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Verify a message signature
+///
+/// Recovers the signer of an EIP-191 message or EIP-712 typed data signature
+/// and compares it to the expected address. Prints JSON and exits 1 when the
+/// signature was made by a different address.
+#[derive(Parser, Debug)]
+#[command(name = "verify-message")]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Signed message (UTF-8 text, or hex with --hex)
+    #[arg(required_unless_present = "typed_data", conflicts_with = "typed_data")]
+    message: Option<String>,
+
+    /// Treat the message as 0x-prefixed hex bytes
+    #[arg(long)]
+    hex: bool,
+
+    /// Verify an EIP-712 signature over this typed data JSON file (`-` reads stdin)
+    #[arg(long)]
+    typed_data: Option<PathBuf>,
+
+    /// 65-byte hex signature
+    #[arg(long)]
+    signature: String,
+
+    /// Address expected to have signed
+    #[arg(long)]
+    address: String,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    match run(args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code() as u8)
+        }
+    }
+}
+
+fn run(args: Args) -> payment_common::Result<bool> {
+    let expected: Address = args
+        .address
+        .parse()
+        .map_err(|_| Error::InvalidArgument(format!("Invalid address: {}", args.address)))?;
+    let signature = parse_signature(&args.signature)?;
+
+    let recovered = match (&args.typed_data, &args.message) {
+        (Some(path), _) => {
+            let hash = load_typed_data(path)?
+                .eip712_signing_hash()
+                .map_err(|e| Error::InvalidArgument(format!("Invalid typed data: {}", e)))?;
+            signature.recover_address_from_prehash(&hash)
+        }
+        (None, Some(message)) => {
+            signature.recover_address_from_msg(message_bytes(message, args.hex)?)
+        }
+        (None, None) => unreachable!("clap requires a message or --typed-data"),
+    }
+    .map_err(|e| Error::InvalidArgument(format!("Cannot recover signer: {}", e)))?;
+
+    let valid = recovered == expected;
+    let output = serde_json::json!({
+        "valid": valid,
+        "recovered": recovered.to_string(),
+        "expected": expected.to_string(),
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    if !valid {
+        eprintln!("Signature was not made by {}", expected);
+    }
+    Ok(valid)
+}
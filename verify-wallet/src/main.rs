@@ -0,0 +1,74 @@
+use clap::Parser;
+use payment_common::{Config, Wallet};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Verify the integrity of a payment wallet keystore
+///
+/// Decrypts the keystore with its password to check the MAC and KDF
+/// parameters, confirms the stored address matches the key, and checks that
+/// the wallet and password files are private. Prints a JSON report.
+#[derive(Parser, Debug)]
+#[command(name = "verify-wallet")]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Path to the wallet keystore file
+    #[arg(long, short = 'w')]
+    wallet: Option<PathBuf>,
+
+    /// Wallet password
+    #[arg(long)]
+    password: Option<String>,
+
+    /// Read wallet password from file
+    #[arg(long, conflicts_with = "password")]
+    password_file: Option<PathBuf>,
+
+    /// Path to configuration file
+    #[arg(long, short = 'c')]
+    config: Option<PathBuf>,
+
+    /// Named environment from config.toml (default: $PAYMENT_ENV, then active_env)
+    #[arg(long)]
+    env: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    match run(args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code() as u8)
+        }
+    }
+}
+
+fn run(args: Args) -> payment_common::Result<bool> {
+    let config = Config::load_env(args.config.as_deref(), args.env.as_deref())?;
+    for warning in config.warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+    let wallet_path = args.wallet.unwrap_or_else(|| config.wallet_path());
+
+    // Password: CLI > --password-file > config's secret store or password file
+    let password = match (args.password, &args.password_file) {
+        (Some(pw), _) => pw,
+        (None, Some(path)) => Wallet::load_password(Some(path))?,
+        (None, None) => config.wallet_password()?,
+    };
+    let password_path = args
+        .password_file
+        .or_else(|| config.secret_store_path())
+        .unwrap_or_else(|| config.password_path());
+
+    let report = Wallet::verify(Some(&wallet_path), &password, Some(&password_path))?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    if !report.ok {
+        eprintln!("Wallet verification failed.");
+    }
+    Ok(report.ok)
+}
@@ -0,0 +1,35 @@
+use serde_json::Value;
+use test_support::{MockChain, TestHome};
+
+const VERIFY_WALLET: &str = env!("CARGO_BIN_EXE_verify-wallet");
+
+#[test]
+fn test_verify_wallet() {
+    let chain = MockChain::start();
+    let home = TestHome::new(&chain, None);
+
+    let output = home
+        .command(VERIFY_WALLET)
+        .output()
+        .expect("run verify-wallet");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: Value = serde_json::from_slice(&output.stdout).expect("JSON report");
+    assert_eq!(report["ok"], true);
+}
+
+#[test]
+fn test_verify_wallet_wrong_password() {
+    let chain = MockChain::start();
+    let home = TestHome::new(&chain, None);
+
+    let output = home
+        .command(VERIFY_WALLET)
+        .args(["--password", "not the password"])
+        .output()
+        .expect("run verify-wallet");
+    assert!(!output.status.success());
+}